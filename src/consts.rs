@@ -1,4 +1,8 @@
-use druid::{Point, Selector};
+use std::path::PathBuf;
+
+use druid::{FileInfo, Point, Selector};
+
+use crate::data::{export::ExportFormat, keymap::Action, layers::LayerAction};
 
 pub const CHAR_HOR_L: char = '─';
 pub const CHAR_VER_L: char = '│';
@@ -11,6 +15,8 @@ pub const CHAR_HOR_DOWN_L: char = '┬';
 pub const CHAR_VER_RIGHT_L: char = '├';
 pub const CHAR_VER_LEFT_L: char = '┤';
 pub const CHAR_CROSS: char = '┼';
+pub const CHAR_DIAGONAL_UP: char = '/';
+pub const CHAR_DIAGONAL_DOWN: char = '\\';
 
 pub const CHAR_ARROW_UP: char = '▲';
 pub const CHAR_ARROW_DOWN: char = '▼';
@@ -27,3 +33,21 @@ pub const BUTTON_HIGHLIGHT_COMMAND: Selector<String> = Selector::new("button-hig
 pub const SELECTION_START_COMMAND: Selector<Point> = Selector::new("selection-start");
 pub const SELECTION_END_COMMAND: Selector<Point> = Selector::new("selection-end");
 pub const SELECTION_MOVE_COMMAND: Selector<Point> = Selector::new("selection-move");
+
+pub const COPY_AS_COMMAND: Selector<ExportFormat> = Selector::new("copy-as");
+pub const EXPORT_AS_COMMAND: Selector<ExportFormat> = Selector::new("export-as");
+pub const EXPORT_FILE_COMMAND: Selector<FileInfo> = Selector::new("export-file");
+
+pub const PLACE_BLOCK_COMMAND: Selector<String> = Selector::new("place-block");
+pub const CANCEL_PLACING_COMMAND: Selector<()> = Selector::new("cancel-placing");
+
+pub const SAVE_STENCIL_COMMAND: Selector<()> = Selector::new("save-stencil");
+pub const STENCIL_SAVED_COMMAND: Selector<PathBuf> = Selector::new("stencil-saved");
+
+pub const LAYER_COMMAND: Selector<LayerAction> = Selector::new("layer");
+
+// Run one of the actions the keyboard shortcuts can do, from the command palette
+pub const ACTION_COMMAND: Selector<Action> = Selector::new("action");
+
+pub const INSERT_DIRECTORY_TREE_COMMAND: Selector<FileInfo> =
+    Selector::new("insert-directory-tree");
@@ -0,0 +1,182 @@
+// Top left and bottom right cells of a shape
+pub type Bounds = ((usize, usize), (usize, usize));
+
+// How far an edge or a centre can be from another one to snap to it, in cells
+const SNAP_DISTANCE: isize = 1;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Arrange {
+    AlignLeft,
+    AlignRight,
+    AlignTop,
+    AlignBottom,
+    // The centre column and the middle row of the shapes
+    AlignCenter,
+    AlignMiddle,
+    DistributeHorizontally,
+    DistributeVertically,
+}
+
+// A line drawn across the canvas while dragging shapes, where an edge or a
+// centre lines up with another shape. The position is in cells, counted from
+// the top or left edge of the canvas, a centre can fall in the middle of a cell.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Guide {
+    Row(f64),
+    Column(f64),
+}
+
+// The edges and centre of the bounds on one axis, doubled so a centre falling
+// in the middle of a cell is still a whole number
+fn features(start: usize, end: usize) -> [isize; 3] {
+    let (start, end) = (start as isize * 2, (end as isize + 1) * 2);
+    [start, (start + end) / 2, end]
+}
+
+fn rows(bounds: &Bounds) -> [isize; 3] {
+    features(bounds.0 .0, bounds.1 .0)
+}
+
+fn columns(bounds: &Bounds) -> [isize; 3] {
+    features(bounds.0 .1, bounds.1 .1)
+}
+
+fn translate(bounds: &Bounds, offset: (isize, isize)) -> Bounds {
+    let move_by = |value: usize, by: isize| (value as isize + by).max(0) as usize;
+    (
+        (
+            move_by(bounds.0 .0, offset.0),
+            move_by(bounds.0 .1, offset.1),
+        ),
+        (
+            move_by(bounds.1 .0, offset.0),
+            move_by(bounds.1 .1, offset.1),
+        ),
+    )
+}
+
+// The smallest whole number of cells to move by so one of the features lines
+// up with one of the others
+fn nearest(moving: [isize; 3], others: &[[isize; 3]]) -> Option<isize> {
+    let mut best: Option<isize> = None;
+    for other in others {
+        for a in moving.iter() {
+            for b in other.iter() {
+                let distance = b - a;
+                if distance % 2 != 0 || distance.abs() > SNAP_DISTANCE * 2 {
+                    continue;
+                }
+                if best.filter(|best| distance.abs() >= best.abs()).is_none() {
+                    best = Some(distance);
+                }
+            }
+        }
+    }
+    best.map(|distance| distance / 2)
+}
+
+// Change the offset of the shapes being dragged, so their edges or centres snap
+// to the ones of the other shapes close to them
+pub fn snap(moving: &Bounds, offset: (isize, isize), others: &[Bounds]) -> (isize, isize) {
+    let moved = translate(moving, offset);
+    let other_rows: Vec<[isize; 3]> = others.iter().map(rows).collect();
+    let other_columns: Vec<[isize; 3]> = others.iter().map(columns).collect();
+    (
+        offset.0 + nearest(rows(&moved), &other_rows).unwrap_or(0),
+        offset.1 + nearest(columns(&moved), &other_columns).unwrap_or(0),
+    )
+}
+
+pub fn guides(moving: &Bounds, others: &[Bounds]) -> Vec<Guide> {
+    let mut guides = vec![];
+    for other in others {
+        for a in rows(moving) {
+            let guide = Guide::Row(a as f64 / 2.0);
+            if rows(other).contains(&a) && !guides.contains(&guide) {
+                guides.push(guide);
+            }
+        }
+        for a in columns(moving) {
+            let guide = Guide::Column(a as f64 / 2.0);
+            if columns(other).contains(&a) && !guides.contains(&guide) {
+                guides.push(guide);
+            }
+        }
+    }
+    guides
+}
+
+// How far each of the shapes has to move, in the same order as the bounds
+pub fn arrange(arrange: Arrange, bounds: &[Bounds]) -> Vec<(isize, isize)> {
+    let mut offsets = vec![(0, 0); bounds.len()];
+    if bounds.len() < 2 {
+        return offsets;
+    }
+    let top = bounds.iter().map(|b| b.0 .0).min().unwrap_or(0) as isize;
+    let left = bounds.iter().map(|b| b.0 .1).min().unwrap_or(0) as isize;
+    let bottom = bounds.iter().map(|b| b.1 .0).max().unwrap_or(0) as isize;
+    let right = bounds.iter().map(|b| b.1 .1).max().unwrap_or(0) as isize;
+    for (offset, ((start_row, start_col), (end_row, end_col))) in
+        offsets.iter_mut().zip(bounds.iter())
+    {
+        let (start_row, start_col) = (*start_row as isize, *start_col as isize);
+        let (end_row, end_col) = (*end_row as isize, *end_col as isize);
+        *offset = match arrange {
+            Arrange::AlignLeft => (0, left - start_col),
+            Arrange::AlignRight => (0, right - end_col),
+            Arrange::AlignTop => (top - start_row, 0),
+            Arrange::AlignBottom => (bottom - end_row, 0),
+            Arrange::AlignCenter => (0, (left + right - start_col - end_col) / 2),
+            Arrange::AlignMiddle => ((top + bottom - start_row - end_row) / 2, 0),
+            Arrange::DistributeHorizontally | Arrange::DistributeVertically => (0, 0),
+        };
+    }
+    match arrange {
+        Arrange::DistributeHorizontally => {
+            let spans: Vec<(usize, usize)> = bounds.iter().map(|b| (b.0 .1, b.1 .1)).collect();
+            for (i, by) in distribute(&spans) {
+                offsets[i].1 = by;
+            }
+        }
+        Arrange::DistributeVertically => {
+            let spans: Vec<(usize, usize)> = bounds.iter().map(|b| (b.0 .0, b.1 .0)).collect();
+            for (i, by) in distribute(&spans) {
+                offsets[i].0 = by;
+            }
+        }
+        _ => {}
+    }
+    offsets
+}
+
+// The first and last shapes stay where they are, the ones between them are
+// moved so the gaps between all of them are the same
+fn distribute(spans: &[(usize, usize)]) -> Vec<(usize, isize)> {
+    let mut order: Vec<usize> = (0..spans.len()).collect();
+    order.sort_by_key(|&i| (spans[i].0, spans[i].1));
+    if order.len() < 3 {
+        return vec![];
+    }
+    let first = spans[order[0]].0 as isize;
+    let last = spans[order[order.len() - 1]].1 as isize;
+    let sizes: isize = spans
+        .iter()
+        .map(|(start, end)| (end - start + 1) as isize)
+        .sum();
+    let gaps = order.len() as isize - 1;
+    let space = last - first + 1 - sizes;
+    let mut position = first;
+    let mut result = vec![];
+    for (n, &i) in order.iter().enumerate() {
+        let (start, end) = spans[i];
+        result.push((i, position - start as isize));
+        // The cells that can't be shared evenly go to the first gaps
+        let extra = if (n as isize) < space.rem_euclid(gaps) {
+            1
+        } else {
+            0
+        };
+        position += (end - start + 1) as isize + space.div_euclid(gaps) + extra;
+    }
+    result
+}
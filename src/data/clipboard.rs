@@ -0,0 +1,213 @@
+use crate::shapes::{
+    block::BlockShape, bounds, freehand::FreehandShape, group::GroupShape, line::LineShape,
+    rect::RectShape, table::TableShape, Shape,
+};
+
+// Shapes copied from a canvas are put on the clipboard in this format next to
+// the plain text, so another window can paste them back as real shapes
+pub const SHAPES_FORMAT: &str = "rocks.huy.ascii-d.shapes";
+
+const HEADER: &str = "ascii-d shapes 1";
+
+// Every shape is written on its own line as a list of space separated values,
+// the shapes of a group follow the line of the group. Positions are relative to
+// the top left corner of all the shapes.
+pub fn serialize(shapes: &[Box<dyn Shape>]) -> String {
+    let mut lines = vec![HEADER.to_string()];
+    let origin = shapes
+        .iter()
+        .map(|shape| bounds(shape.as_ref()).0)
+        .reduce(|a, b| (a.0.min(b.0), a.1.min(b.1)))
+        .unwrap_or((0, 0));
+    for shape in shapes {
+        let mut shape = shape.duplicate();
+        shape.translate((-(origin.0 as isize), -(origin.1 as isize)));
+        write_shape(shape.as_ref(), &mut lines);
+    }
+    lines.join("\n")
+}
+
+fn write_shape(shape: &dyn Shape, lines: &mut Vec<String>) {
+    let shape = shape.as_any();
+    if let Some(rect) = shape.downcast_ref::<RectShape>() {
+        lines.push(format!(
+            "rect {} {} {} {}",
+            rect.start.0, rect.start.1, rect.end.0, rect.end.1
+        ));
+    } else if let Some(line) = shape.downcast_ref::<LineShape>() {
+        lines.push(format!(
+            "line {} {} {} {} {}",
+            line.start.0, line.start.1, line.end.0, line.end.1, line.head as u8
+        ));
+    } else if let Some(block) = shape.downcast_ref::<BlockShape>() {
+        lines.push(format!(
+            "block {} {} {} {}",
+            block.start.0,
+            block.start.1,
+            block.opaque as u8,
+            escape(&block.content)
+        ));
+    } else if let Some(table) = shape.downcast_ref::<TableShape>() {
+        let mut line = format!(
+            "table {} {} {} {}",
+            table.start.0,
+            table.start.1,
+            table.rows(),
+            table.cols()
+        );
+        for cell in table.cells.iter().flatten() {
+            line.push(' ');
+            line.push_str(&escape(cell));
+        }
+        lines.push(line);
+    } else if let Some(freehand) = shape.downcast_ref::<FreehandShape>() {
+        let brush = freehand.brush.map(|c| c.to_string()).unwrap_or_default();
+        let mut line = format!("freehand {}", escape(&brush));
+        for (row, col) in freehand.points.iter() {
+            line.push_str(&format!(" {} {}", row, col));
+        }
+        lines.push(line);
+    } else if let Some(group) = shape.downcast_ref::<GroupShape>() {
+        lines.push(format!("group {}", group.shapes.len()));
+        for shape in group.shapes.iter() {
+            write_shape(shape.as_ref(), lines);
+        }
+    }
+}
+
+// The shapes are placed with their top left corner at (0, 0). The text comes
+// from another app as well, anything that doesn't fit in a grid of the given
+// size is rejected.
+pub fn deserialize(source: &str, grid_size: (usize, usize)) -> Option<Vec<Box<dyn Shape>>> {
+    let mut lines = source.lines();
+    if lines.next()? != HEADER {
+        return None;
+    }
+    let (rows, cols) = grid_size;
+    let mut shapes = vec![];
+    while let Some(shape) = read_shape(&mut lines, grid_size) {
+        let shape = shape?;
+        let (_, (bottom, right)) = bounds(shape.as_ref());
+        if bottom >= rows || right >= cols {
+            return None;
+        }
+        shapes.push(shape);
+    }
+    if shapes.is_empty() {
+        None
+    } else {
+        Some(shapes)
+    }
+}
+
+// None once there are no lines left, Some(None) if the line can't be read
+fn read_shape<'a>(
+    lines: &mut impl Iterator<Item = &'a str>,
+    grid_size: (usize, usize),
+) -> Option<Option<Box<dyn Shape>>> {
+    let line = lines.next()?;
+    Some(parse_shape(line, lines, grid_size))
+}
+
+fn parse_shape<'a>(
+    line: &str,
+    lines: &mut impl Iterator<Item = &'a str>,
+    grid_size: (usize, usize),
+) -> Option<Box<dyn Shape>> {
+    let (rows, cols) = grid_size;
+    let values: Vec<&str> = line.split(' ').collect();
+    let number = |i: usize| values.get(i).and_then(|value| value.parse::<usize>().ok());
+    // Positions have to be on the grid
+    let row = |i: usize| number(i).filter(|row| *row < rows);
+    let col = |i: usize| number(i).filter(|col| *col < cols);
+    let shape: Box<dyn Shape> = match values[0] {
+        "rect" => Box::new(RectShape::from_points(
+            (row(1)?, col(2)?),
+            (row(3)?, col(4)?),
+        )),
+        "line" => {
+            let mut line = LineShape::from_points((row(1)?, col(2)?), (row(3)?, col(4)?));
+            line.head = number(5)? != 0;
+            Box::new(line)
+        }
+        "block" => {
+            let content = unescape(values.get(4)?);
+            Box::new(BlockShape::new(row(1)?, col(2)?, content).with_opaque(number(3)? != 0))
+        }
+        "table" => {
+            let (table_rows, table_cols) = (number(3)?, number(4)?);
+            if table_rows == 0 || table_cols == 0 || table_rows > rows || table_cols > cols {
+                return None;
+            }
+            let cells: Vec<String> = values.iter().skip(5).map(|cell| unescape(cell)).collect();
+            if cells.len() != table_rows.checked_mul(table_cols)? {
+                return None;
+            }
+            let cells = cells.chunks(table_cols).map(|row| row.to_vec()).collect();
+            Box::new(TableShape::from_rows(row(1)?, col(2)?, cells))
+        }
+        "freehand" => {
+            let brush = unescape(values.get(1)?).chars().next();
+            if values.len() < 4 || values.len() % 2 == 1 {
+                return None;
+            }
+            let points = (2..values.len())
+                .step_by(2)
+                .map(|i| Some((row(i)?, col(i + 1)?)))
+                .collect::<Option<_>>()?;
+            Box::new(FreehandShape::from_points(points, brush))
+        }
+        "group" => {
+            let count = number(1)?;
+            let mut shapes = vec![];
+            for _ in 0..count {
+                shapes.push(read_shape(lines, grid_size)??);
+            }
+            if shapes.is_empty() {
+                return None;
+            }
+            Box::new(GroupShape::new(shapes))
+        }
+        _ => return None,
+    };
+    Some(shape)
+}
+
+// Text values can't contain the separators, an empty one is written as \e
+fn escape(text: &str) -> String {
+    if text.is_empty() {
+        return "\\e".to_string();
+    }
+    let mut result = String::new();
+    for c in text.chars() {
+        match c {
+            '\\' => result.push_str("\\\\"),
+            ' ' => result.push_str("\\s"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            _ => result.push(c),
+        }
+    }
+    result
+}
+
+fn unescape(text: &str) -> String {
+    let mut result = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('s') => result.push(' '),
+            Some('n') => result.push('\n'),
+            Some('r') => result.push('\r'),
+            Some('t') => result.push('\t'),
+            Some('e') | None => {}
+            Some(c) => result.push(c),
+        }
+    }
+    result
+}
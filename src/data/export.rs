@@ -0,0 +1,148 @@
+use druid::FileSpec;
+use std::fmt::Display;
+
+use super::settings::SETTINGS;
+
+// Line comment prefixes offered next to the one set in the settings
+const COMMENT_PREFIXES: [&str; 3] = ["// ", "# ", "-- "];
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum ExportFormat {
+    Plain,
+    Markdown,
+    Html,
+    LineComment(String),
+    BlockComment,
+}
+
+impl Display for ExportFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExportFormat::Plain => write!(f, "Plain Text"),
+            ExportFormat::Markdown => write!(f, "Markdown"),
+            ExportFormat::Html => write!(f, "HTML"),
+            ExportFormat::LineComment(prefix) => write!(f, "Comment ({})", prefix.trim()),
+            ExportFormat::BlockComment => write!(f, "Comment (/* */)"),
+        }
+    }
+}
+
+impl ExportFormat {
+    // Every format, with a line comment for each prefix
+    pub fn all() -> Vec<ExportFormat> {
+        let mut prefixes = vec![SETTINGS.comment_prefix.to_owned()];
+        for prefix in COMMENT_PREFIXES {
+            if !prefixes.iter().any(|other| other.trim() == prefix.trim()) {
+                prefixes.push(prefix.to_string());
+            }
+        }
+        let mut formats = vec![
+            ExportFormat::Plain,
+            ExportFormat::Markdown,
+            ExportFormat::Html,
+        ];
+        formats.extend(prefixes.into_iter().map(ExportFormat::LineComment));
+        formats.push(ExportFormat::BlockComment);
+        formats
+    }
+
+    pub fn file_spec(&self) -> FileSpec {
+        match self {
+            ExportFormat::Markdown => FileSpec::new("Markdown", &["md"]),
+            ExportFormat::Html => FileSpec::new("HTML", &["html", "htm"]),
+            _ => FileSpec::TEXT,
+        }
+    }
+
+    pub fn apply(&self, content: &str, trim_trailing_whitespace: bool) -> String {
+        let lines = content_lines(content, trim_trailing_whitespace);
+        match self {
+            ExportFormat::Plain => lines.join("\n"),
+            ExportFormat::Markdown => {
+                // Make the fence longer than any backtick run inside the diagram, so the
+                // block can't be closed early
+                let longest_run = lines
+                    .iter()
+                    .map(|line| longest_backtick_run(line))
+                    .max()
+                    .unwrap_or(0);
+                let fence = "`".repeat(longest_run.max(2) + 1);
+                format!("{}\n{}\n{}", fence, lines.join("\n"), fence)
+            }
+            ExportFormat::Html => format!("<pre>\n{}\n</pre>", escape_html(&lines.join("\n"))),
+            ExportFormat::LineComment(prefix) => lines
+                .iter()
+                .map(|line| {
+                    if line.is_empty() {
+                        prefix.trim_end().to_string()
+                    } else {
+                        format!("{}{}", prefix, line)
+                    }
+                })
+                .collect::<Vec<String>>()
+                .join("\n"),
+            ExportFormat::BlockComment => {
+                let body = lines
+                    .iter()
+                    .map(|line| {
+                        if line.is_empty() {
+                            " *".to_string()
+                        } else {
+                            format!(" * {}", line)
+                        }
+                    })
+                    .collect::<Vec<String>>()
+                    .join("\n");
+                format!("/*\n{}\n */", body)
+            }
+        }
+    }
+}
+
+fn content_lines(content: &str, trim_trailing_whitespace: bool) -> Vec<String> {
+    let mut lines: Vec<String> = content
+        .lines()
+        .map(|line| {
+            if trim_trailing_whitespace {
+                line.trim_end().to_string()
+            } else {
+                line.to_string()
+            }
+        })
+        .collect();
+    if trim_trailing_whitespace {
+        while lines.last().is_some_and(|line| line.is_empty()) {
+            lines.pop();
+        }
+    }
+    lines
+}
+
+fn longest_backtick_run(line: &str) -> usize {
+    let mut longest = 0;
+    let mut current = 0;
+    for c in line.chars() {
+        if c == '`' {
+            current += 1;
+            longest = longest.max(current);
+        } else {
+            current = 0;
+        }
+    }
+    longest
+}
+
+fn escape_html(content: &str) -> String {
+    let mut result = String::with_capacity(content.len());
+    for c in content.chars() {
+        match c {
+            '&' => result.push_str("&amp;"),
+            '<' => result.push_str("&lt;"),
+            '>' => result.push_str("&gt;"),
+            '"' => result.push_str("&quot;"),
+            '\'' => result.push_str("&#39;"),
+            _ => result.push(c),
+        }
+    }
+    result
+}
@@ -1,7 +1,12 @@
 use super::{
+    export::ExportFormat,
     grid_cell::GridCell,
     history::{Version, HISTORY_MANAGER},
+    overlap,
+    transform::{Area, Transform},
 };
+use crate::consts::CHAR_SPACE;
+use crate::shapes::line::LineDirection;
 use druid::Rect;
 use std::fmt::Display;
 
@@ -91,6 +96,14 @@ impl GridList {
         result.join("\n")
     }
 
+    pub fn export_highlighted(&mut self, format: &ExportFormat, trim_whitespace: bool) -> String {
+        format.apply(&self.get_highlighted_content(), trim_whitespace)
+    }
+
+    pub fn export(&self, format: &ExportFormat, trim_whitespace: bool) -> String {
+        format.apply(&self.to_string(), trim_whitespace)
+    }
+
     pub fn highlight(&mut self, index: usize) {
         self.clear_all_highlight();
         self.data[index].highlight(index);
@@ -124,7 +137,39 @@ impl GridList {
         self.current_selection = Some(((start_row, start_col), (end_row, end_col)));
     }
 
-    pub fn erase_highlighted(&mut self) {
+    // Highlight the cells between two corners, without making it a selection
+    pub fn highlight_area(&mut self, from: (usize, usize), to: (usize, usize)) {
+        self.clear_all_highlight();
+        let (rows, cols) = self.grid_size;
+        for row in from.0.min(to.0)..=from.0.max(to.0).min(rows - 1) {
+            for col in from.1.min(to.1)..=from.1.max(to.1).min(cols - 1) {
+                let index = row * cols + col;
+                self.data[index].highlight(index);
+            }
+        }
+    }
+
+    pub fn highlight_cells(&mut self, indices: &[usize]) {
+        self.clear_all_highlight();
+        for &index in indices {
+            self.data[index].highlight(index);
+        }
+    }
+
+    // Change the content of the given cells in a single version, returns the
+    // cells that changed
+    pub fn write_cells(&mut self, cells: &[(usize, char)]) -> Vec<usize> {
+        let mut version = Version::new();
+        for &(i, c) in cells {
+            if self.data[i].content != c {
+                version.push(i, self.data[i].content, c);
+                self.data[i].set_content(c);
+            }
+        }
+        save(version)
+    }
+
+    pub fn erase_highlighted(&mut self) -> Vec<usize> {
         let mut version = Version::new();
         self.data
             .iter_mut()
@@ -134,9 +179,151 @@ impl GridList {
                 version.push(i, cell.content, ' ');
                 cell.clear();
             });
+        save(version)
+    }
+
+    pub fn fill_highlighted(&mut self, content: char) -> Vec<usize> {
+        let mut version = Version::new();
+        self.data
+            .iter_mut()
+            .enumerate()
+            .filter(|(_, cell)| cell.highlighted && cell.content != content)
+            .for_each(|(i, cell)| {
+                version.push(i, cell.content, content);
+                cell.set_content(content);
+            });
+        save(version)
+    }
+
+    // Fill the region of same-content cells around the given cell, 8-connected
+    // regions also spread through diagonal gaps. Nothing is filled if the region is
+    // bigger than max_area, returns the filled cells if the fill happened.
+    pub fn flood_fill(
+        &mut self,
+        from: (usize, usize),
+        content: char,
+        diagonal: bool,
+        max_area: usize,
+    ) -> Option<Vec<usize>> {
+        let (rows, cols) = self.grid_size;
+        if from.0 >= rows || from.1 >= cols {
+            return None;
+        }
+        let target = self.data[from.0 * cols + from.1].content;
+        if target == content {
+            return None;
+        }
+        let mut visited = vec![false; rows * cols];
+        let mut region = vec![];
+        let mut stack = vec![from];
+        visited[from.0 * cols + from.1] = true;
+        while let Some((row, col)) = stack.pop() {
+            region.push(row * cols + col);
+            if region.len() > max_area {
+                return None;
+            }
+            for (d_row, d_col) in [
+                (-1, 0),
+                (1, 0),
+                (0, -1),
+                (0, 1),
+                (-1, -1),
+                (-1, 1),
+                (1, -1),
+                (1, 1),
+            ] {
+                if !diagonal && d_row != 0 && d_col != 0 {
+                    continue;
+                }
+                let next_row = row as isize + d_row;
+                let next_col = col as isize + d_col;
+                if next_row < 0
+                    || next_col < 0
+                    || next_row >= rows as isize
+                    || next_col >= cols as isize
+                {
+                    continue;
+                }
+                let i = next_row as usize * cols + next_col as usize;
+                if !visited[i] && self.data[i].content == target {
+                    visited[i] = true;
+                    stack.push((next_row as usize, next_col as usize));
+                }
+            }
+        }
+        let mut version = Version::new();
+        for i in region {
+            version.push(i, self.data[i].content, content);
+            self.data[i].set_content(content);
+        }
+        Some(save(version))
+    }
+
+    pub fn erase_area(&mut self, from: (usize, usize), to: (usize, usize)) -> Vec<usize> {
+        let mut version = Version::new();
+        let (rows, cols) = self.grid_size;
+        for row in from.0..=to.0.min(rows - 1) {
+            for col in from.1..=to.1.min(cols - 1) {
+                let i = row * cols + col;
+                if self.data[i].content != ' ' {
+                    version.push(i, self.data[i].content, ' ');
+                    self.data[i].clear();
+                }
+            }
+        }
+        save(version)
+    }
+
+    // Flip or rotate the content of an area, the transformed area keeps its top
+    // left corner. The cells of the old area it no longer covers are cleared, and
+    // where it reaches past the old area its spaces are transparent, so what's
+    // around the selection is kept. Returns the new area, or None when it doesn't
+    // fit in the canvas.
+    pub fn transform_area(&mut self, area: Area, transform: Transform) -> Option<Area> {
+        let (rows, cols) = self.grid_size;
+        let ((top, left), (bottom, right)) = area;
+        let new_area = transform.map_area(area);
+        let ((_, _), (new_bottom, new_right)) = new_area;
+        if new_bottom >= rows || new_right >= cols {
+            return None;
+        }
+        let content: Vec<Vec<char>> = (top..=bottom)
+            .map(|row| {
+                (left..=right)
+                    .map(|col| self.data[row * cols + col].content)
+                    .collect()
+            })
+            .collect();
+        let mut contents = vec![];
+        for row in top..=bottom {
+            for col in left..=right {
+                contents.push((row * cols + col, CHAR_SPACE));
+            }
+        }
+        for (row, chars) in (top..).zip(transform.apply(&content)) {
+            for (col, c) in (left..).zip(chars) {
+                if c == CHAR_SPACE && (row > bottom || col > right) {
+                    continue;
+                }
+                contents.push((row * cols + col, c));
+            }
+        }
+        let mut version = Version::new();
+        for (i, content) in contents {
+            let cell = &mut self.data[i];
+            if cell.content != content {
+                version.push(i, cell.content, content);
+                cell.set_content(content);
+            }
+        }
         unsafe {
             HISTORY_MANAGER.save_version(version);
         }
+        Some(new_area)
+    }
+
+    pub fn has_highlight(&self) -> bool {
+        self.data.iter().any(|cell| cell.highlighted)
     }
 
     pub fn clear_all_highlight(&mut self) {
@@ -170,19 +357,71 @@ impl GridList {
         }
     }
 
-    pub fn put_preview_at(&mut self, content: &str, row: usize, col: usize) {
+    // Remove every preview from the grid and hand them back, with the direction of
+    // the line they start, so they can be put back later
+    pub fn take_previews(&mut self) -> Vec<(usize, char, Option<LineDirection>)> {
+        let mut previews = vec![];
+        for (i, cell) in self.data.iter_mut().enumerate() {
+            if let Some(preview) = cell.preview {
+                previews.push((i, preview, cell.line_direction));
+                cell.discard();
+            }
+        }
+        previews
+    }
+
+    // Same as take_previews(), only looking at the cells between the given top left
+    // and bottom right cells
+    pub fn take_previews_in(
+        &mut self,
+        from: (usize, usize),
+        to: (usize, usize),
+    ) -> Vec<(usize, char, Option<LineDirection>)> {
+        let mut previews = vec![];
+        let (rows, cols) = self.grid_size;
+        for row in from.0..=to.0.min(rows - 1) {
+            for col in from.1..=to.1.min(cols - 1) {
+                let cell = &mut self.data[row * cols + col];
+                if let Some(preview) = cell.preview {
+                    previews.push((row * cols + col, preview, cell.line_direction));
+                    cell.discard();
+                }
+            }
+        }
+        previews
+    }
+
+    pub fn put_previews(&mut self, previews: Vec<(usize, char, Option<LineDirection>)>) {
+        for (i, preview, direction) in previews {
+            let cell = &mut self.data[i];
+            cell.preview = Some(match cell.preview {
+                Some(current) => overlap::calculate_cell_content(direction, current, preview),
+                None => preview,
+            });
+            if cell.line_direction.is_none() {
+                cell.line_direction = direction;
+            }
+        }
+    }
+
+    // Spaces are skipped, unless the content is opaque: then the whole rectangle
+    // it covers is put on the grid, padded with spaces
+    pub fn put_preview_at(&mut self, content: &str, row: usize, col: usize, opaque: bool) {
         let (_, cols) = self.grid_size;
-        let mut row = row;
-        for line in content.lines() {
-            let mut col = col;
-            for c in line.chars() {
-                if !c.is_whitespace() {
+        let width = content
+            .lines()
+            .map(|line| line.chars().count())
+            .max()
+            .unwrap_or(0);
+        for (row, line) in (row..).zip(content.lines()) {
+            let padding = width - line.chars().count();
+            let chars = line.chars().chain((0..padding).map(|_| CHAR_SPACE));
+            for (col, c) in (col..).zip(chars) {
+                if opaque || !c.is_whitespace() {
                     let i = row * cols + col;
-                    self.data[i].set_preview(c);
+                    self.data[i].set_preview(if c.is_whitespace() { CHAR_SPACE } else { c });
                 }
-                col += 1;
             }
-            row += 1;
         }
     }
 
@@ -211,3 +450,13 @@ impl GridList {
         self.load_content_at(content, 0, 0);
     }
 }
+
+// Keep a version of cells written by hand in the history, and hand back the
+// cells it changed so the shapes drawn there can be detached
+fn save(version: Version) -> Vec<usize> {
+    let cells = version.cells();
+    unsafe {
+        HISTORY_MANAGER.save_version(version);
+    }
+    cells
+}
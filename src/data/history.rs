@@ -1,4 +1,5 @@
-use super::grid_list::GridList;
+use super::{grid_list::GridList, shape_list::ShapeList};
+use crate::shapes::Shape;
 use once_cell::sync::Lazy;
 
 #[derive(Debug, Clone)]
@@ -14,14 +15,41 @@ impl Edit {
     }
 }
 
-#[derive(Debug, Clone)]
+// A run of shapes on the canvas before and after a change, starting at the
+// same place in the list. Only the shapes that changed are kept, so undo and
+// redo bring back the shapes along with their cells.
+struct ShapesEdit {
+    at: usize,
+    from: Vec<Box<dyn Shape>>,
+    to: Vec<Box<dyn Shape>>,
+}
+
+fn duplicate(shapes: &[Box<dyn Shape>]) -> Vec<Box<dyn Shape>> {
+    shapes.iter().map(|shape| shape.duplicate()).collect()
+}
+
+impl Clone for ShapesEdit {
+    fn clone(&self) -> Self {
+        Self {
+            at: self.at,
+            from: duplicate(&self.from),
+            to: duplicate(&self.to),
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct Version {
     edits: Vec<Edit>,
+    shapes: Vec<ShapesEdit>,
 }
 
 impl Version {
     pub fn new() -> Self {
-        Self { edits: vec![] }
+        Self {
+            edits: vec![],
+            shapes: vec![],
+        }
     }
 
     pub fn push(&mut self, index: usize, from: char, to: char) {
@@ -30,16 +58,40 @@ impl Version {
 
     pub fn clear(&mut self) {
         self.edits.clear();
+        self.shapes.clear();
     }
 
     pub fn len(&self) -> usize {
         self.edits.len()
     }
+
+    // The cells changed in this version
+    pub fn cells(&self) -> Vec<usize> {
+        self.edits.iter().map(|edit| edit.index).collect()
+    }
+
+    // Merge a later version into this one. A shape change picking up where the
+    // last one left off replaces its result, so the many moves of a drag are kept
+    // as a single change.
+    fn extend(&mut self, version: Version) {
+        self.edits.extend(version.edits);
+        for shapes in version.shapes {
+            match self.shapes.last_mut() {
+                Some(last) if last.at == shapes.at && last.to.len() == shapes.from.len() => {
+                    last.to = shapes.to;
+                }
+                _ => self.shapes.push(shapes),
+            }
+        }
+    }
 }
 
 pub struct History {
     versions: Vec<Version>,
     index: usize,
+    group: Option<Version>,
+    // Groups can be nested, the outermost one is saved
+    group_depth: usize,
 }
 
 impl History {
@@ -47,42 +99,147 @@ impl History {
         Self {
             versions: vec![],
             index: 0,
+            group: None,
+            group_depth: 0,
+        }
+    }
+
+    // Forget every version, for a document that starts over
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    // Every version saved between begin_group() and end_group() will be merged
+    // into a single version, so the whole action can be undone in one step
+    pub fn begin_group(&mut self) {
+        self.group_depth += 1;
+        if self.group.is_none() {
+            self.group = Some(Version::new());
+        }
+    }
+
+    pub fn end_group(&mut self) {
+        self.group_depth = self.group_depth.saturating_sub(1);
+        if self.group_depth > 0 {
+            return;
+        }
+        if let Some(version) = self.group.take() {
+            self.save_version(version);
         }
     }
 
     pub fn save_version(&mut self, version: Version) {
-        if version.len() > 0 {
+        if let Some(group) = self.group.as_mut() {
+            group.extend(version);
+            return;
+        }
+        if version.len() > 0 || !version.shapes.is_empty() {
             if self.index + 1 >= self.versions.len() {
                 // Push new history
                 self.versions.push(version);
             } else {
                 // Overwriting history
-                self.versions = self.versions[0..self.index].to_vec();
+                self.versions.truncate(self.index);
                 self.versions.push(version);
             }
             self.index = self.versions.len();
         }
     }
 
-    pub fn undo(&mut self, grid_list: &mut GridList) {
+    // Keep a change to the shape list, the shapes starting at `at` went from
+    // `from` to `to`. It's merged into the current group.
+    pub fn save_shapes(&mut self, at: usize, from: Vec<Box<dyn Shape>>, to: Vec<Box<dyn Shape>>) {
+        let mut version = Version::new();
+        version.shapes.push(ShapesEdit { at, from, to });
+        self.save_version(version);
+    }
+
+    pub fn undo(&mut self, grid_list: &mut GridList, shape_list: &mut ShapeList) {
         if self.index > 0 {
             self.index -= 1;
             let version = &self.versions[self.index];
-            for edit in &version.edits {
+            for edit in version.edits.iter().rev() {
                 grid_list.set(edit.index, edit.from);
             }
+            for shapes in version.shapes.iter().rev() {
+                shape_list.restore(shapes.at, shapes.to.len(), duplicate(&shapes.from));
+            }
         }
     }
 
-    pub fn redo(&mut self, grid_list: &mut GridList) {
+    pub fn redo(&mut self, grid_list: &mut GridList, shape_list: &mut ShapeList) {
         if self.index < self.versions.len() {
             let version = &self.versions[self.index];
             for edit in &version.edits {
                 grid_list.set(edit.index, edit.to);
             }
+            for shapes in &version.shapes {
+                shape_list.restore(shapes.at, shapes.from.len(), duplicate(&shapes.to));
+            }
             self.index += 1;
         }
     }
 }
 
 pub static mut HISTORY_MANAGER: Lazy<History> = Lazy::new(History::new);
+
+// The tests using the shared history take turns
+#[cfg(test)]
+pub static HISTORY_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shapes::{bounds, rect::RectShape};
+
+    fn content(grid_list: &mut GridList) -> String {
+        let (rows, cols) = grid_list.grid_size;
+        (0..rows * cols).map(|i| grid_list.get(i).content).collect()
+    }
+
+    #[test]
+    fn a_drag_keeps_only_the_moved_shape_once() {
+        let _lock = HISTORY_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        unsafe {
+            HISTORY_MANAGER.reset();
+        }
+        let mut grid_list = GridList::new(10.0, 10.0, 10, 20);
+        let mut shape_list = ShapeList::new();
+        shape_list.insert_shapes(
+            vec![
+                Box::new(RectShape::from_points((0, 0), (2, 3))),
+                Box::new(RectShape::from_points((0, 6), (2, 9))),
+                Box::new(RectShape::from_points((5, 0), (7, 3))),
+            ],
+            &mut grid_list,
+        );
+        let before = content(&mut grid_list);
+
+        shape_list.select(1, false);
+        unsafe {
+            HISTORY_MANAGER.begin_group();
+        }
+        shape_list.lift_selected(&mut grid_list);
+        for offset in [(1, 0), (1, 0), (0, 1), (1, 1)] {
+            shape_list.move_selected(offset, &mut grid_list);
+        }
+        shape_list.drop_selected(&mut grid_list);
+        unsafe {
+            HISTORY_MANAGER.end_group();
+        }
+        let after = content(&mut grid_list);
+
+        let history = unsafe { &mut HISTORY_MANAGER };
+        let shapes = &history.versions.last().unwrap().shapes;
+        assert_eq!(shapes.len(), 1);
+        assert_eq!((shapes[0].at, shapes[0].from.len()), (1, 1));
+
+        history.undo(&mut grid_list, &mut shape_list);
+        assert_eq!(content(&mut grid_list), before);
+        assert_eq!(bounds(shape_list.data[1].as_ref()), ((0, 6), (2, 9)));
+        history.redo(&mut grid_list, &mut shape_list);
+        assert_eq!(content(&mut grid_list), after);
+        assert_eq!(bounds(shape_list.data[1].as_ref()), ((3, 8), (5, 11)));
+        assert_eq!(shape_list.data.len(), 3);
+    }
+}
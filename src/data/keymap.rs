@@ -0,0 +1,536 @@
+use druid::{Code, KeyEvent};
+use once_cell::sync::Lazy;
+use std::{fmt, str::FromStr};
+
+use super::settings::SETTINGS;
+
+// Everything the keyboard shortcuts can do, the keys of each action can be
+// changed in the keybindings table of the settings, by its name
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Action {
+    SelectTool,
+    LineTool,
+    RectTool,
+    TextTool,
+    EraserTool,
+    TableTool,
+    FreehandTool,
+    FillTool,
+    Cancel,
+    Delete,
+    ToggleKeyboardMode,
+    ToggleLayers,
+    ToggleLibrary,
+    ToggleTheme,
+    ToggleGrid,
+    ToggleTrimWhitespace,
+    ZoomIn,
+    ZoomOut,
+    ResetZoom,
+    Duplicate,
+    Group,
+    Ungroup,
+    BringForward,
+    BringToFront,
+    SendBackward,
+    SendToBack,
+    AlignLeft,
+    AlignRight,
+    AlignTop,
+    AlignBottom,
+    AlignCenter,
+    AlignMiddle,
+    DistributeHorizontally,
+    DistributeVertically,
+    ConvertToShapes,
+    FlipHorizontal,
+    FlipVertical,
+    RotateClockwise,
+    RotateCounterClockwise,
+    Copy,
+    CopyMarkdown,
+    CopyHtml,
+    CopyComment,
+    Cut,
+    Paste,
+    PasteOpaque,
+    PasteAsShapes,
+    SelectAll,
+    InsertTree,
+    InsertDirectoryTree,
+    SaveStencil,
+    FillSelection,
+    AddLayer,
+    NewFile,
+    Open,
+    Save,
+    SaveAs,
+    Undo,
+    Redo,
+    CommandPalette,
+}
+
+impl Action {
+    pub const ALL: [Action; 60] = [
+        Action::SelectTool,
+        Action::LineTool,
+        Action::RectTool,
+        Action::TextTool,
+        Action::EraserTool,
+        Action::TableTool,
+        Action::FreehandTool,
+        Action::FillTool,
+        Action::Cancel,
+        Action::Delete,
+        Action::ToggleKeyboardMode,
+        Action::ToggleLayers,
+        Action::ToggleLibrary,
+        Action::ToggleTheme,
+        Action::ToggleGrid,
+        Action::ToggleTrimWhitespace,
+        Action::ZoomIn,
+        Action::ZoomOut,
+        Action::ResetZoom,
+        Action::Duplicate,
+        Action::Group,
+        Action::Ungroup,
+        Action::BringForward,
+        Action::BringToFront,
+        Action::SendBackward,
+        Action::SendToBack,
+        Action::AlignLeft,
+        Action::AlignRight,
+        Action::AlignTop,
+        Action::AlignBottom,
+        Action::AlignCenter,
+        Action::AlignMiddle,
+        Action::DistributeHorizontally,
+        Action::DistributeVertically,
+        Action::ConvertToShapes,
+        Action::FlipHorizontal,
+        Action::FlipVertical,
+        Action::RotateClockwise,
+        Action::RotateCounterClockwise,
+        Action::Copy,
+        Action::CopyMarkdown,
+        Action::CopyHtml,
+        Action::CopyComment,
+        Action::Cut,
+        Action::Paste,
+        Action::PasteOpaque,
+        Action::PasteAsShapes,
+        Action::SelectAll,
+        Action::InsertTree,
+        Action::InsertDirectoryTree,
+        Action::SaveStencil,
+        Action::FillSelection,
+        Action::AddLayer,
+        Action::NewFile,
+        Action::Open,
+        Action::Save,
+        Action::SaveAs,
+        Action::Undo,
+        Action::Redo,
+        Action::CommandPalette,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::SelectTool => "select_tool",
+            Action::LineTool => "line_tool",
+            Action::RectTool => "rect_tool",
+            Action::TextTool => "text_tool",
+            Action::EraserTool => "eraser_tool",
+            Action::TableTool => "table_tool",
+            Action::FreehandTool => "freehand_tool",
+            Action::FillTool => "fill_tool",
+            Action::Cancel => "cancel",
+            Action::Delete => "delete",
+            Action::ToggleKeyboardMode => "toggle_keyboard_mode",
+            Action::ToggleLayers => "toggle_layers",
+            Action::ToggleLibrary => "toggle_library",
+            Action::ToggleTheme => "toggle_theme",
+            Action::ToggleGrid => "toggle_grid",
+            Action::ToggleTrimWhitespace => "toggle_trim_whitespace",
+            Action::ZoomIn => "zoom_in",
+            Action::ZoomOut => "zoom_out",
+            Action::ResetZoom => "reset_zoom",
+            Action::Duplicate => "duplicate",
+            Action::Group => "group",
+            Action::Ungroup => "ungroup",
+            Action::BringForward => "bring_forward",
+            Action::BringToFront => "bring_to_front",
+            Action::SendBackward => "send_backward",
+            Action::SendToBack => "send_to_back",
+            Action::AlignLeft => "align_left",
+            Action::AlignRight => "align_right",
+            Action::AlignTop => "align_top",
+            Action::AlignBottom => "align_bottom",
+            Action::AlignCenter => "align_center",
+            Action::AlignMiddle => "align_middle",
+            Action::DistributeHorizontally => "distribute_horizontally",
+            Action::DistributeVertically => "distribute_vertically",
+            Action::ConvertToShapes => "convert_to_shapes",
+            Action::FlipHorizontal => "flip_horizontal",
+            Action::FlipVertical => "flip_vertical",
+            Action::RotateClockwise => "rotate_clockwise",
+            Action::RotateCounterClockwise => "rotate_counter_clockwise",
+            Action::Copy => "copy",
+            Action::CopyMarkdown => "copy_markdown",
+            Action::CopyHtml => "copy_html",
+            Action::CopyComment => "copy_comment",
+            Action::Cut => "cut",
+            Action::Paste => "paste",
+            Action::PasteOpaque => "paste_opaque",
+            Action::PasteAsShapes => "paste_as_shapes",
+            Action::SelectAll => "select_all",
+            Action::InsertTree => "insert_tree",
+            Action::InsertDirectoryTree => "insert_directory_tree",
+            Action::SaveStencil => "save_stencil",
+            Action::FillSelection => "fill_selection",
+            Action::AddLayer => "add_layer",
+            Action::NewFile => "new_file",
+            Action::Open => "open",
+            Action::Save => "save",
+            Action::SaveAs => "save_as",
+            Action::Undo => "undo",
+            Action::Redo => "redo",
+            Action::CommandPalette => "command_palette",
+        }
+    }
+
+    // How the action is shown in the command palette
+    pub fn label(&self) -> &'static str {
+        match self {
+            Action::SelectTool => "Select Tool",
+            Action::LineTool => "Line Tool",
+            Action::RectTool => "Rectangle Tool",
+            Action::TextTool => "Text Tool",
+            Action::EraserTool => "Eraser Tool",
+            Action::TableTool => "Table Tool",
+            Action::FreehandTool => "Freehand Tool",
+            Action::FillTool => "Fill Tool",
+            Action::Cancel => "Cancel",
+            Action::Delete => "Delete Selection",
+            Action::ToggleKeyboardMode => "Toggle Keyboard Mode",
+            Action::ToggleLayers => "Toggle Layers Panel",
+            Action::ToggleLibrary => "Toggle Library Panel",
+            Action::ToggleTheme => "Toggle Theme",
+            Action::ToggleGrid => "Toggle Grid Lines",
+            Action::ToggleTrimWhitespace => "Toggle Trimming Trailing Whitespace",
+            Action::ZoomIn => "Zoom In",
+            Action::ZoomOut => "Zoom Out",
+            Action::ResetZoom => "Actual Size",
+            Action::Duplicate => "Duplicate",
+            Action::Group => "Group",
+            Action::Ungroup => "Ungroup",
+            Action::BringForward => "Bring Forward",
+            Action::BringToFront => "Bring to Front",
+            Action::SendBackward => "Send Backward",
+            Action::SendToBack => "Send to Back",
+            Action::AlignLeft => "Align Left",
+            Action::AlignRight => "Align Right",
+            Action::AlignTop => "Align Top",
+            Action::AlignBottom => "Align Bottom",
+            Action::AlignCenter => "Align Centers",
+            Action::AlignMiddle => "Align Middles",
+            Action::DistributeHorizontally => "Distribute Horizontally",
+            Action::DistributeVertically => "Distribute Vertically",
+            Action::ConvertToShapes => "Convert Text to Shapes",
+            Action::FlipHorizontal => "Flip Horizontally",
+            Action::FlipVertical => "Flip Vertically",
+            Action::RotateClockwise => "Rotate Clockwise",
+            Action::RotateCounterClockwise => "Rotate Counterclockwise",
+            Action::Copy => "Copy",
+            Action::CopyMarkdown => "Copy as Markdown",
+            Action::CopyHtml => "Copy as HTML",
+            Action::CopyComment => "Copy as Comment",
+            Action::Cut => "Cut",
+            Action::Paste => "Paste",
+            Action::PasteOpaque => "Paste Opaque",
+            Action::PasteAsShapes => "Paste as Shapes",
+            Action::SelectAll => "Select All",
+            Action::InsertTree => "Insert Tree from Clipboard",
+            Action::InsertDirectoryTree => "Insert Directory Tree",
+            Action::SaveStencil => "Save Selection as Stencil",
+            Action::FillSelection => "Fill Selection",
+            Action::AddLayer => "Add Layer",
+            Action::NewFile => "New Window",
+            Action::Open => "Open…",
+            Action::Save => "Save",
+            Action::SaveAs => "Save As…",
+            Action::Undo => "Undo",
+            Action::Redo => "Redo",
+            Action::CommandPalette => "Command Palette",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Action> {
+        Action::ALL
+            .iter()
+            .copied()
+            .find(|action| action.name() == name)
+    }
+
+    fn default_keys(&self) -> &'static [&'static str] {
+        match self {
+            Action::SelectTool => &[],
+            Action::LineTool => &["1", "L", "A"],
+            Action::RectTool => &["2", "R"],
+            Action::TextTool => &["3", "T"],
+            Action::EraserTool => &["4", "E"],
+            Action::TableTool => &["5", "G"],
+            Action::FreehandTool => &["6", "F"],
+            Action::FillTool => &["7", "B"],
+            Action::Cancel => &["Escape"],
+            Action::Delete => &["Delete", "Backspace"],
+            Action::ToggleKeyboardMode => &["F6"],
+            Action::ToggleLayers => &["F7"],
+            Action::ToggleLibrary => &["Ctrl+Shift+L"],
+            Action::ToggleTheme => &["Ctrl+Shift+D"],
+            Action::ToggleGrid => &[],
+            Action::ToggleTrimWhitespace => &[],
+            Action::ZoomIn => &["Ctrl+=", "Ctrl+Shift+="],
+            Action::ZoomOut => &["Ctrl+-"],
+            Action::ResetZoom => &["Ctrl+0"],
+            Action::Duplicate => &["Ctrl+D"],
+            Action::Group => &["Ctrl+G"],
+            Action::Ungroup => &["Ctrl+Shift+G"],
+            Action::BringForward => &["Ctrl+]"],
+            Action::BringToFront => &["Ctrl+Shift+]"],
+            Action::SendBackward => &["Ctrl+["],
+            Action::SendToBack => &["Ctrl+Shift+["],
+            Action::AlignLeft => &["Ctrl+Alt+Left"],
+            Action::AlignRight => &["Ctrl+Alt+Right"],
+            Action::AlignTop => &["Ctrl+Alt+Up"],
+            Action::AlignBottom => &["Ctrl+Alt+Down"],
+            Action::AlignCenter => &["Ctrl+Alt+E"],
+            Action::AlignMiddle => &["Ctrl+Alt+M"],
+            Action::DistributeHorizontally => &["Ctrl+Alt+Shift+Left", "Ctrl+Alt+Shift+Right"],
+            Action::DistributeVertically => &["Ctrl+Alt+Shift+Up", "Ctrl+Alt+Shift+Down"],
+            Action::ConvertToShapes => &["Ctrl+Alt+B"],
+            Action::FlipHorizontal => &["Ctrl+Alt+H"],
+            Action::FlipVertical => &["Ctrl+Alt+V"],
+            Action::RotateClockwise => &["Ctrl+Alt+R"],
+            Action::RotateCounterClockwise => &["Ctrl+Alt+Shift+R"],
+            Action::Copy => &["Ctrl+C"],
+            Action::CopyMarkdown => &["Ctrl+Shift+C"],
+            Action::CopyHtml => &["Ctrl+Alt+C"],
+            Action::CopyComment => &["Ctrl+Alt+Shift+C"],
+            Action::Cut => &["Ctrl+X"],
+            Action::Paste => &["Ctrl+V"],
+            Action::PasteOpaque => &["Ctrl+Shift+V"],
+            Action::PasteAsShapes => &["Ctrl+Alt+Shift+V"],
+            Action::SelectAll => &["Ctrl+A"],
+            Action::InsertTree => &["Ctrl+Alt+T"],
+            Action::InsertDirectoryTree => &["Ctrl+Alt+Shift+T"],
+            Action::SaveStencil => &["Ctrl+Alt+L"],
+            Action::FillSelection => &["Ctrl+Shift+F"],
+            Action::AddLayer => &["Ctrl+Shift+N"],
+            Action::NewFile => &["Ctrl+N"],
+            Action::Open => &["Ctrl+O"],
+            Action::Save => &["Ctrl+S"],
+            Action::SaveAs => &["Ctrl+Shift+S"],
+            Action::Undo => &["Ctrl+Z"],
+            Action::Redo => &["Ctrl+Shift+Z"],
+            Action::CommandPalette => &["Ctrl+Shift+P"],
+        }
+    }
+}
+
+// A key and the modifiers held with it, Ctrl also stands for Cmd on macOS. Keys
+// are matched by their place on the keyboard, not by the character they type.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct KeyBinding {
+    code: Code,
+    ctrl: bool,
+    shift: bool,
+    alt: bool,
+}
+
+// Short names for the keys, the other ones are written like druid's Code
+const KEY_NAMES: [(&str, Code); 17] = [
+    ("Left", Code::ArrowLeft),
+    ("Right", Code::ArrowRight),
+    ("Up", Code::ArrowUp),
+    ("Down", Code::ArrowDown),
+    ("Esc", Code::Escape),
+    ("Del", Code::Delete),
+    ("[", Code::BracketLeft),
+    ("]", Code::BracketRight),
+    ("-", Code::Minus),
+    ("=", Code::Equal),
+    (",", Code::Comma),
+    (".", Code::Period),
+    ("/", Code::Slash),
+    (";", Code::Semicolon),
+    ("'", Code::Quote),
+    ("`", Code::Backquote),
+    ("\\", Code::Backslash),
+];
+
+impl KeyBinding {
+    // Matches what the key event held. With `extra_shift`, Shift can be held on
+    // top of the modifiers of the binding.
+    pub fn matches(&self, event: &KeyEvent, extra_shift: bool) -> bool {
+        event.code == self.code
+            && (event.mods.ctrl() || event.mods.meta()) == self.ctrl
+            && (event.mods.shift() == self.shift || extra_shift && event.mods.shift())
+            && event.mods.alt() == self.alt
+    }
+}
+
+impl FromStr for KeyBinding {
+    type Err = String;
+
+    // Like Ctrl+Shift+Z, the key comes last
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        let mut parts: Vec<&str> = source.split('+').map(|part| part.trim()).collect();
+        let key = parts.pop().unwrap_or_default();
+        let mut binding = KeyBinding {
+            code: Code::Unidentified,
+            ctrl: false,
+            shift: false,
+            alt: false,
+        };
+        for part in parts {
+            match part.to_lowercase().as_str() {
+                "ctrl" | "control" | "cmd" | "command" | "meta" | "super" => binding.ctrl = true,
+                "shift" => binding.shift = true,
+                "alt" | "option" => binding.alt = true,
+                _ => return Err(format!("unknown modifier {part} in {source}")),
+            }
+        }
+        let mut chars = key.chars();
+        binding.code = match (chars.next(), chars.next()) {
+            (Some(c), None) if c.is_ascii_alphabetic() => {
+                Code::from_str(&format!("Key{}", c.to_ascii_uppercase()))
+            }
+            (Some(c), None) if c.is_ascii_digit() => Code::from_str(&format!("Digit{c}")),
+            _ => match KEY_NAMES
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(key))
+            {
+                Some((_, code)) => Ok(*code),
+                // Also f6 or escape, written in lower case
+                None => Code::from_str(key).or_else(|_| {
+                    let mut chars = key.chars();
+                    let first = chars.next().map(|c| c.to_ascii_uppercase());
+                    Code::from_str(&first.into_iter().chain(chars).collect::<String>())
+                }),
+            },
+        }
+        .ok()
+        .filter(|code| *code != Code::Unidentified)
+        .ok_or_else(|| format!("unknown key {key} in {source}"))?;
+        Ok(binding)
+    }
+}
+
+impl fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.ctrl {
+            f.write_str("Ctrl+")?;
+        }
+        if self.alt {
+            f.write_str("Alt+")?;
+        }
+        if self.shift {
+            f.write_str("Shift+")?;
+        }
+        let code = self.code.to_string();
+        match KEY_NAMES.iter().find(|(_, code)| *code == self.code) {
+            Some((name, _)) => f.write_str(name),
+            None => f.write_str(
+                code.strip_prefix("Key")
+                    .or_else(|| code.strip_prefix("Digit"))
+                    .unwrap_or(&code),
+            ),
+        }
+    }
+}
+
+pub struct Keymap {
+    bindings: Vec<(KeyBinding, Action)>,
+    // Unknown actions, keys that can't be read and keys taken twice, they're
+    // shown when a window opens
+    pub errors: Vec<String>,
+}
+
+impl Keymap {
+    // The keys set in the settings replace the default keys of their action. A
+    // key can only do one thing, the first action to take it keeps it, and the
+    // ones set in the settings come first.
+    pub fn new(config: &[(String, Vec<String>)]) -> Self {
+        let mut keymap = Keymap {
+            bindings: vec![],
+            errors: vec![],
+        };
+        let mut configured = vec![];
+        for (name, keys) in config {
+            let action = match Action::from_name(name) {
+                Some(action) => action,
+                None => {
+                    keymap.errors.push(format!("unknown action {name}"));
+                    continue;
+                }
+            };
+            configured.push(action);
+            for key in keys {
+                match key.parse::<KeyBinding>() {
+                    Ok(binding) => keymap.bind(binding, action),
+                    Err(e) => keymap.errors.push(e),
+                }
+            }
+        }
+        for action in Action::ALL {
+            if configured.contains(&action) {
+                continue;
+            }
+            for key in action.default_keys() {
+                match key.parse::<KeyBinding>() {
+                    Ok(binding) => keymap.bind(binding, action),
+                    Err(e) => keymap.errors.push(e),
+                }
+            }
+        }
+        for e in keymap.errors.iter() {
+            println!("Error in keybindings: {e}");
+        }
+        keymap
+    }
+
+    fn bind(&mut self, binding: KeyBinding, action: Action) {
+        match self.bindings.iter().find(|(other, _)| *other == binding) {
+            Some((_, other)) if *other == action => {}
+            Some((_, other)) => self.errors.push(format!(
+                "{binding} is already bound to {}, it can't be used for {}",
+                other.name(),
+                action.name()
+            )),
+            None => self.bindings.push((binding, action)),
+        }
+    }
+
+    pub fn keys(&self, action: Action) -> Vec<KeyBinding> {
+        self.bindings
+            .iter()
+            .filter(|(_, other)| *other == action)
+            .map(|(binding, _)| *binding)
+            .collect()
+    }
+
+    // The binding with exactly the held modifiers wins, otherwise Shift held on
+    // top of a binding still triggers it, like while extending a selection
+    pub fn action(&self, event: &KeyEvent) -> Option<Action> {
+        let find = |extra_shift| {
+            self.bindings
+                .iter()
+                .find(|(binding, _)| binding.matches(event, extra_shift))
+                .map(|(_, action)| *action)
+        };
+        find(false).or_else(|| find(true))
+    }
+}
+
+pub static KEYMAP: Lazy<Keymap> = Lazy::new(|| Keymap::new(&SETTINGS.keybindings));
@@ -0,0 +1,231 @@
+use crate::consts::CHAR_SPACE;
+
+use super::{
+    grid_list::GridList,
+    history::{History, HISTORY_MANAGER},
+    shape_list::ShapeList,
+};
+
+// What the layers panel needs to know about a layer
+#[derive(Clone, PartialEq, Debug)]
+pub struct LayerInfo {
+    pub name: String,
+    pub visible: bool,
+    pub locked: bool,
+}
+
+impl LayerInfo {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            visible: true,
+            locked: false,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum LayerAction {
+    Add,
+    Remove,
+    Select(usize),
+    ToggleVisible(usize),
+    ToggleLocked(usize),
+    MoveUp,
+    MoveDown,
+    Rename(usize, String),
+}
+
+struct Layer {
+    info: LayerInfo,
+    grid_list: GridList,
+    shape_list: ShapeList,
+    history: History,
+}
+
+impl Layer {
+    fn new(name: &str, grid_list: GridList) -> Self {
+        Self {
+            info: LayerInfo::new(name),
+            grid_list,
+            shape_list: ShapeList::new(),
+            history: History::new(),
+        }
+    }
+}
+
+// The layers of a canvas, from the bottom to the top. The content of the active
+// layer is owned by the canvas while it's being edited, so its slot here only
+// holds whatever was swapped out of the canvas.
+pub struct LayerList {
+    layers: Vec<Layer>,
+    pub active: usize,
+    // Used to name the new layers
+    next_id: usize,
+}
+
+impl LayerList {
+    pub fn new() -> Self {
+        Self {
+            layers: vec![Layer::new("Layer 1", GridList::default())],
+            active: 0,
+            next_id: 2,
+        }
+    }
+
+    pub fn infos(&self) -> Vec<LayerInfo> {
+        self.layers.iter().map(|layer| layer.info.clone()).collect()
+    }
+
+    // Tools only work on a visible and unlocked layer
+    pub fn is_editable(&self) -> bool {
+        let info = &self.layers[self.active].info;
+        info.visible && !info.locked
+    }
+
+    // Put the content of the active layer back in its slot and take out the one
+    // of the given layer. Every layer keeps its own undo history.
+    fn swap_active(&mut self, grid_list: &mut GridList, shape_list: &mut ShapeList) {
+        let layer = &mut self.layers[self.active];
+        std::mem::swap(&mut layer.grid_list, grid_list);
+        std::mem::swap(&mut layer.shape_list, shape_list);
+        unsafe {
+            std::mem::swap(&mut layer.history, &mut *HISTORY_MANAGER);
+        }
+    }
+
+    pub fn activate(&mut self, index: usize, grid_list: &mut GridList, shape_list: &mut ShapeList) {
+        if index >= self.layers.len() || index == self.active {
+            return;
+        }
+        shape_list.commit_all(grid_list);
+        shape_list.selected.clear();
+        grid_list.clear_all_highlight();
+        self.swap_active(grid_list, shape_list);
+        self.active = index;
+        self.swap_active(grid_list, shape_list);
+    }
+
+    // New layers are added right above the active one
+    pub fn add(&mut self, grid_list: &mut GridList, shape_list: &mut ShapeList) {
+        let (cell_width, cell_height) = grid_list.cell_size;
+        let (rows, cols) = grid_list.grid_size;
+        let name = format!("Layer {}", self.next_id);
+        self.next_id += 1;
+        let layer = Layer::new(&name, GridList::new(cell_width, cell_height, rows, cols));
+        self.layers.insert(self.active + 1, layer);
+        self.activate(self.active + 1, grid_list, shape_list);
+    }
+
+    pub fn remove(&mut self, grid_list: &mut GridList, shape_list: &mut ShapeList) {
+        if self.layers.len() < 2 {
+            return;
+        }
+        let removed = self.active;
+        let next = if removed > 0 { removed - 1 } else { 1 };
+        self.activate(next, grid_list, shape_list);
+        self.layers.remove(removed);
+        if self.active > removed {
+            self.active -= 1;
+        }
+    }
+
+    pub fn move_active(&mut self, up: bool) {
+        let target = if up {
+            self.active + 1
+        } else {
+            match self.active.checked_sub(1) {
+                Some(target) => target,
+                None => return,
+            }
+        };
+        if target < self.layers.len() {
+            self.layers.swap(self.active, target);
+            self.active = target;
+        }
+    }
+
+    pub fn toggle_visible(&mut self, index: usize) {
+        if let Some(layer) = self.layers.get_mut(index) {
+            layer.info.visible = !layer.info.visible;
+        }
+    }
+
+    pub fn toggle_locked(&mut self, index: usize) {
+        if let Some(layer) = self.layers.get_mut(index) {
+            layer.info.locked = !layer.info.locked;
+        }
+    }
+
+    pub fn rename(&mut self, index: usize, name: &str) {
+        if let Some(layer) = self.layers.get_mut(index) {
+            layer.info.name = name.to_string();
+        }
+    }
+
+    pub fn apply(
+        &mut self,
+        action: &LayerAction,
+        grid_list: &mut GridList,
+        shape_list: &mut ShapeList,
+    ) {
+        match action {
+            LayerAction::Add => self.add(grid_list, shape_list),
+            LayerAction::Remove => self.remove(grid_list, shape_list),
+            LayerAction::Select(index) => self.activate(*index, grid_list, shape_list),
+            LayerAction::ToggleVisible(index) => self.toggle_visible(*index),
+            LayerAction::ToggleLocked(index) => self.toggle_locked(*index),
+            LayerAction::MoveUp => self.move_active(true),
+            LayerAction::MoveDown => self.move_active(false),
+            LayerAction::Rename(index, name) => self.rename(*index, name),
+        }
+    }
+
+    // The character showing at a cell: the top most one of the visible layers.
+    // The active layer's own character is read from the canvas.
+    pub fn content_at(&mut self, index: usize, active_content: char) -> char {
+        for (i, layer) in self.layers.iter_mut().enumerate().rev() {
+            if !layer.info.visible {
+                continue;
+            }
+            let content = if i == self.active {
+                active_content
+            } else {
+                layer.grid_list.get(index).content
+            };
+            if !content.is_whitespace() {
+                return content;
+            }
+        }
+        CHAR_SPACE
+    }
+
+    // Stack the layers into a single grid, keeping the selection of the active
+    // layer, so it can be exported or copied like a plain canvas
+    pub fn flatten(&mut self, grid_list: &mut GridList, include_hidden: bool) -> GridList {
+        let (cell_width, cell_height) = grid_list.cell_size;
+        let (rows, cols) = grid_list.grid_size;
+        let mut flat = GridList::new(cell_width, cell_height, rows, cols);
+        for i in 0..rows * cols {
+            let cell = *grid_list.get(i);
+            let flat_cell = flat.get(i);
+            flat_cell.highlighted = cell.highlighted;
+            flat_cell.highlight_index = cell.highlight_index;
+            for (index, layer) in self.layers.iter_mut().enumerate() {
+                if !(layer.info.visible || include_hidden) {
+                    continue;
+                }
+                let content = if index == self.active {
+                    cell.content
+                } else {
+                    layer.grid_list.get(i).content
+                };
+                if !content.is_whitespace() {
+                    flat_cell.content = content;
+                }
+            }
+        }
+        flat.current_selection = grid_list.current_selection;
+        flat
+    }
+}
@@ -3,18 +3,40 @@ use std::collections::HashMap;
 
 use crate::tools::DrawingTools;
 
+use self::{layers::LayerInfo, settings::SETTINGS};
+
+pub mod align;
+pub mod clipboard;
+pub mod config;
+pub mod export;
 pub mod grid_cell;
 pub mod grid_list;
 pub mod history;
+pub mod keymap;
+pub mod layers;
+pub mod overlap;
+pub mod recent;
 pub mod selection;
+pub mod settings;
 pub mod shape_list;
-
-mod overlap;
+pub mod stencil;
+pub mod transform;
+pub mod vim;
 
 #[derive(Clone, PartialEq, Data, Debug)]
 pub struct WindowData {
     pub mode: DrawingTools,
     pub current_file: Option<String>,
+    pub trim_whitespace: bool,
+    pub show_library: bool,
+    pub show_layers: bool,
+    pub show_grid: bool,
+    // Shown at the bottom of the window until it's dismissed, like a failed save
+    pub message: Option<String>,
+    // Mirrors the layers of the canvas, bottom to top, for the layers panel
+    #[data(eq)]
+    pub layers: Vec<LayerInfo>,
+    pub active_layer: usize,
 }
 
 impl WindowData {
@@ -22,6 +44,13 @@ impl WindowData {
         Self {
             mode: DrawingTools::Select,
             current_file: None,
+            trim_whitespace: SETTINGS.trim_whitespace,
+            show_library: false,
+            show_layers: false,
+            show_grid: true,
+            message: None,
+            layers: vec![LayerInfo::new("Layer 1")],
+            active_layer: 0,
         }
     }
 }
@@ -30,4 +59,7 @@ impl WindowData {
 pub struct ApplicationState {
     #[data(eq)]
     pub windows: HashMap<WindowId, WindowData>,
+    // Shared by the File menus of all the windows
+    #[data(eq)]
+    pub recent_files: Vec<String>,
 }
@@ -289,3 +289,77 @@ pub fn calculate_cell_content(
     }
     incoming
 }
+
+pub const ARM_UP: u8 = 1;
+pub const ARM_DOWN: u8 = 2;
+pub const ARM_LEFT: u8 = 4;
+pub const ARM_RIGHT: u8 = 8;
+
+// Which sides of the cell a line glyph connects to
+pub fn arms(glyph: char) -> Option<u8> {
+    match glyph {
+        CHAR_HOR_L => Some(ARM_LEFT | ARM_RIGHT),
+        CHAR_VER_L => Some(ARM_UP | ARM_DOWN),
+        CHAR_CORNER_TL_L => Some(ARM_RIGHT | ARM_DOWN),
+        CHAR_CORNER_TR_L => Some(ARM_LEFT | ARM_DOWN),
+        CHAR_CORNER_BL_L => Some(ARM_RIGHT | ARM_UP),
+        CHAR_CORNER_BR_L => Some(ARM_LEFT | ARM_UP),
+        CHAR_HOR_UP_L => Some(ARM_LEFT | ARM_RIGHT | ARM_UP),
+        CHAR_HOR_DOWN_L => Some(ARM_LEFT | ARM_RIGHT | ARM_DOWN),
+        CHAR_VER_RIGHT_L => Some(ARM_UP | ARM_DOWN | ARM_RIGHT),
+        CHAR_VER_LEFT_L => Some(ARM_UP | ARM_DOWN | ARM_LEFT),
+        CHAR_CROSS => Some(ARM_UP | ARM_DOWN | ARM_LEFT | ARM_RIGHT),
+        _ => None,
+    }
+}
+
+fn glyph_from_arms(arms: u8) -> char {
+    let vertical = arms & (ARM_UP | ARM_DOWN);
+    let horizontal = arms & (ARM_LEFT | ARM_RIGHT);
+    match (vertical, horizontal) {
+        (0, 0) => CHAR_SPACE,
+        (_, 0) => CHAR_VER_L,
+        (0, _) => CHAR_HOR_L,
+        (ARM_DOWN, ARM_RIGHT) => CHAR_CORNER_TL_L,
+        (ARM_DOWN, ARM_LEFT) => CHAR_CORNER_TR_L,
+        (ARM_UP, ARM_RIGHT) => CHAR_CORNER_BL_L,
+        (ARM_UP, ARM_LEFT) => CHAR_CORNER_BR_L,
+        (ARM_UP, _) => CHAR_HOR_UP_L,
+        (ARM_DOWN, _) => CHAR_HOR_DOWN_L,
+        (_, ARM_RIGHT) => CHAR_VER_RIGHT_L,
+        (_, ARM_LEFT) => CHAR_VER_LEFT_L,
+        _ => CHAR_CROSS,
+    }
+}
+
+pub fn is_line_glyph(glyph: char) -> bool {
+    arms(glyph).is_some()
+}
+
+// Move the arms of a line glyph to other sides, `sides` tells where the up, down,
+// left and right arms go, so a ┌ mirrored horizontally becomes a ┐
+pub fn remap_line_glyph(glyph: char, sides: [u8; 4]) -> Option<char> {
+    let arms = arms(glyph)?;
+    let remapped = [ARM_UP, ARM_DOWN, ARM_LEFT, ARM_RIGHT]
+        .iter()
+        .zip(sides.iter())
+        .filter(|(arm, _)| arms & **arm != 0)
+        .fold(0, |remapped, (_, side)| remapped | side);
+    Some(glyph_from_arms(remapped))
+}
+
+// The opposite of calculate_cell_content: take a glyph that was drawn on this cell
+// out of it, so a ┼ goes back to ─ or │ when one of the crossing lines is removed.
+// Anything that isn't part of the removed glyph, like text typed over it, is kept.
+pub fn remove_cell_content(current: char, removed: char) -> char {
+    match (arms(current), arms(removed)) {
+        (Some(current_arms), Some(removed_arms)) => glyph_from_arms(current_arms & !removed_arms),
+        _ => {
+            if current == removed {
+                CHAR_SPACE
+            } else {
+                current
+            }
+        }
+    }
+}
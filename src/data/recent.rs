@@ -0,0 +1,53 @@
+use std::{
+    fs,
+    io::{self, ErrorKind},
+    path::PathBuf,
+};
+
+use super::config::config_dir;
+
+const MAX_RECENT_FILES: usize = 10;
+
+fn recent_files_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("recent.txt"))
+}
+
+// The files opened or saved lately, most recent first, one path per line
+pub fn load_recent_files() -> Vec<String> {
+    let path = match recent_files_path() {
+        Some(path) => path,
+        None => return vec![],
+    };
+    match fs::read_to_string(path) {
+        Ok(content) => content
+            .lines()
+            .filter(|line| !line.is_empty())
+            .take(MAX_RECENT_FILES)
+            .map(String::from)
+            .collect(),
+        Err(e) => {
+            if e.kind() != ErrorKind::NotFound {
+                println!("Error loading recent files: {e}");
+            }
+            vec![]
+        }
+    }
+}
+
+pub fn add_recent_file(files: &mut Vec<String>, file: String) {
+    files.retain(|other| *other != file);
+    files.insert(0, file);
+    files.truncate(MAX_RECENT_FILES);
+    if let Err(e) = save_recent_files(files) {
+        println!("Error saving recent files: {e}");
+    }
+}
+
+fn save_recent_files(files: &[String]) -> io::Result<()> {
+    let path = recent_files_path()
+        .ok_or_else(|| io::Error::new(ErrorKind::NotFound, "No config directory"))?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, files.join("\n"))
+}
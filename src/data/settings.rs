@@ -0,0 +1,304 @@
+use once_cell::sync::Lazy;
+use std::{fs, io::ErrorKind, iter::Peekable, str::Chars};
+
+use super::config::config_dir;
+
+// Preferences kept in settings.toml in the config directory, one key = value
+// per line:
+//
+//   # Vim-style modal editing on the canvas
+//   vim_mode = true
+//   # Put before every line when copying or exporting as a comment
+//   comment_prefix = "# "
+//   # Remove the spaces at the end of the lines when copying or exporting
+//   trim_whitespace = false
+//
+//   [keybindings]
+//   undo = "Ctrl+Z"
+//   line_tool = ["1", "L"]
+//
+// Only a subset of TOML is read, it's listed in the README: bare or quoted keys,
+// booleans, strings with the TOML escapes and lists of strings that can go over
+// several lines. Anything else, like inline tables, dotted keys or a key set
+// twice, is reported as an error with its line instead of being read
+// differently.
+pub struct Settings {
+    pub vim_mode: bool,
+    pub comment_prefix: String,
+    pub trim_whitespace: bool,
+    // The keys of every action found in the keybindings table, in file order
+    pub keybindings: Vec<(String, Vec<String>)>,
+    // What couldn't be read from the file, it's shown when a window opens
+    pub errors: Vec<String>,
+}
+
+impl Settings {
+    pub fn load() -> Self {
+        let mut settings = Settings {
+            vim_mode: false,
+            comment_prefix: "// ".to_string(),
+            trim_whitespace: true,
+            keybindings: vec![],
+            errors: vec![],
+        };
+        let path = match config_dir() {
+            Some(dir) => dir.join("settings.toml"),
+            None => return settings,
+        };
+        match fs::read_to_string(&path) {
+            Ok(source) => settings.read(&source),
+            Err(e) => {
+                if e.kind() != ErrorKind::NotFound {
+                    settings.errors.push(e.to_string());
+                }
+            }
+        }
+        for e in settings.errors.iter() {
+            println!("Error reading settings: {e}");
+        }
+        settings
+    }
+
+    // Anything outside the supported part of TOML is reported with its line number
+    // rather than read differently
+    fn read(&mut self, source: &str) {
+        // None in a table that isn't known, its keys were already reported
+        let mut table = Some(String::new());
+        let mut seen_tables: Vec<String> = vec![];
+        let mut seen_keys: Vec<(String, String)> = vec![];
+        let mut lines = source.lines().enumerate();
+        while let Some((number, line)) = lines.next() {
+            let line = strip_comment(line).trim();
+            if line.is_empty() {
+                continue;
+            }
+            let error = |message: String| format!("line {}: {message}", number + 1);
+            if line.starts_with("[[") {
+                self.errors
+                    .push(error(format!("arrays of tables aren't supported: {line}")));
+                table = None;
+                continue;
+            }
+            if let Some(header) = line.strip_prefix('[') {
+                table = None;
+                match header.strip_suffix(']').map(|name| name.trim()) {
+                    Some(name) if seen_tables.iter().any(|seen| seen == name) => {
+                        self.errors.push(error(format!("[{name}] is set twice")))
+                    }
+                    Some("keybindings") => {
+                        seen_tables.push("keybindings".to_string());
+                        table = Some("keybindings".to_string());
+                    }
+                    Some(name) => self.errors.push(error(format!("unknown table [{name}]"))),
+                    None => self.errors.push(error(format!("invalid table {line}"))),
+                }
+                continue;
+            }
+            let (key, value) = match split_key(line) {
+                Ok(key_value) => key_value,
+                Err(e) => {
+                    self.errors.push(error(e));
+                    continue;
+                }
+            };
+            // An array can go on over the next lines, up to its closing bracket
+            let mut value = value.to_string();
+            while value.starts_with('[') && !is_closed(&value) {
+                match lines.next() {
+                    Some((_, next)) => {
+                        value.push(' ');
+                        value.push_str(strip_comment(next).trim());
+                    }
+                    None => break,
+                }
+            }
+            let table = match table.as_ref() {
+                Some(table) => table,
+                None => continue,
+            };
+            if seen_keys.contains(&(table.to_owned(), key.to_owned())) {
+                self.errors.push(error(format!("{key} is set twice")));
+                continue;
+            }
+            seen_keys.push((table.to_owned(), key.to_owned()));
+            let key = key.as_str();
+            match (table.as_str(), key) {
+                ("", "vim_mode") => match value.parse::<bool>() {
+                    Ok(value) => self.vim_mode = value,
+                    Err(_) => self
+                        .errors
+                        .push(error("vim_mode: expected true or false".to_string())),
+                },
+                ("", "trim_whitespace") => match value.parse::<bool>() {
+                    Ok(value) => self.trim_whitespace = value,
+                    Err(_) => self
+                        .errors
+                        .push(error("trim_whitespace: expected true or false".to_string())),
+                },
+                ("", "comment_prefix") => match parse_strings(&value).as_deref() {
+                    Ok([prefix]) if !prefix.trim().is_empty() => {
+                        self.comment_prefix = prefix.to_owned()
+                    }
+                    Ok(_) => self
+                        .errors
+                        .push(error("comment_prefix: expected a string".to_string())),
+                    Err(e) => self.errors.push(error(format!("comment_prefix: {e}"))),
+                },
+                ("keybindings", action) => match parse_strings(&value) {
+                    Ok(keys) => self.keybindings.push((action.to_string(), keys)),
+                    Err(e) => self.errors.push(error(format!("{action}: {e}"))),
+                },
+                (_, key) => self.errors.push(error(format!("unknown setting {key}"))),
+            }
+        }
+    }
+}
+
+pub static SETTINGS: Lazy<Settings> = Lazy::new(Settings::load);
+
+// A # starts a comment, unless it's inside a string
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match (c, quote) {
+            _ if escaped => escaped = false,
+            ('\\', Some('"')) => escaped = true,
+            ('"', None) | ('\'', None) => quote = Some(c),
+            (c, Some(q)) if c == q => quote = None,
+            ('#', None) => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+// A bare or quoted key, and the value after its =
+fn split_key(line: &str) -> Result<(String, &str), String> {
+    let (key, rest) = match line.chars().next() {
+        Some(quote) if quote == '"' || quote == '\'' => {
+            let mut chars = line[1..].chars().peekable();
+            let key = parse_string(&mut chars, quote)?;
+            let rest: usize = chars.map(char::len_utf8).sum();
+            (key, &line[line.len() - rest..])
+        }
+        _ => {
+            let end = line
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '-'))
+                .unwrap_or(line.len());
+            if end == 0 {
+                return Err(format!("expected key = value: {line}"));
+            }
+            (line[..end].to_string(), &line[end..])
+        }
+    };
+    let rest = rest.trim_start();
+    if rest.starts_with('.') {
+        return Err(format!("dotted keys aren't supported: {line}"));
+    }
+    match rest.strip_prefix('=') {
+        Some(value) => Ok((key, value.trim())),
+        None => Err(format!("expected key = value: {line}")),
+    }
+}
+
+// Whether the brackets of an array opened on the first line are all closed
+fn is_closed(value: &str) -> bool {
+    let mut depth = 0;
+    let mut quote = None;
+    let mut escaped = false;
+    for c in value.chars() {
+        match (c, quote) {
+            _ if escaped => escaped = false,
+            ('\\', Some('"')) => escaped = true,
+            ('"', None) | ('\'', None) => quote = Some(c),
+            (c, Some(q)) if c == q => quote = None,
+            ('[', None) => depth += 1,
+            (']', None) => depth -= 1,
+            _ => {}
+        }
+    }
+    depth <= 0
+}
+
+// A string, or an array of strings. Basic strings take the TOML escapes, literal
+// strings in single quotes are taken as they are.
+fn parse_strings(value: &str) -> Result<Vec<String>, String> {
+    let expected = || "expected a string or a list of strings".to_string();
+    if value.starts_with('{') {
+        return Err("inline tables aren't supported".to_string());
+    }
+    if value.contains("\"\"\"") || value.contains("'''") {
+        return Err("multi-line strings aren't supported".to_string());
+    }
+    let (items, is_array) = match value.strip_prefix('[') {
+        Some(rest) => (rest.strip_suffix(']').ok_or("the list isn't closed")?, true),
+        None => (value, false),
+    };
+    let mut strings = vec![];
+    let mut chars = items.chars().peekable();
+    loop {
+        while chars.peek().is_some_and(|c| c.is_whitespace()) {
+            chars.next();
+        }
+        match chars.next() {
+            Some(quote) if quote == '"' || quote == '\'' => {
+                strings.push(parse_string(&mut chars, quote)?);
+            }
+            // A trailing comma is fine
+            None if is_array => break,
+            Some('[') | Some('{') => {
+                return Err("nested lists and tables aren't supported".to_string())
+            }
+            _ => return Err(expected()),
+        }
+        while chars.peek().is_some_and(|c| c.is_whitespace()) {
+            chars.next();
+        }
+        match chars.next() {
+            Some(',') if is_array => {}
+            None => break,
+            _ => return Err(expected()),
+        }
+    }
+    if !is_array && strings.len() != 1 {
+        return Err(expected());
+    }
+    Ok(strings)
+}
+
+// The rest of a string after its opening quote
+fn parse_string(chars: &mut Peekable<Chars>, quote: char) -> Result<String, String> {
+    let mut string = String::new();
+    loop {
+        match chars.next().ok_or("the string isn't closed")? {
+            c if c == quote => return Ok(string),
+            '\\' if quote == '"' => string.push(parse_escape(chars)?),
+            c => string.push(c),
+        }
+    }
+}
+
+fn parse_escape(chars: &mut Peekable<Chars>) -> Result<char, String> {
+    let c = chars.next().ok_or("the string isn't closed")?;
+    let escaped = match c {
+        'b' => '\u{8}',
+        't' => '\t',
+        'n' => '\n',
+        'f' => '\u{c}',
+        'r' => '\r',
+        '"' => '"',
+        '\\' => '\\',
+        'u' | 'U' => {
+            let length = if c == 'u' { 4 } else { 8 };
+            let hex: String = chars.take(length).collect();
+            u32::from_str_radix(&hex, 16)
+                .ok()
+                .filter(|_| hex.len() == length)
+                .and_then(char::from_u32)
+                .ok_or_else(|| format!("invalid escape \\{c}{hex}"))?
+        }
+        _ => return Err(format!("invalid escape \\{c}")),
+    };
+    Ok(escaped)
+}
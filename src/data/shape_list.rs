@@ -1,16 +1,57 @@
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Range,
+};
+
 use druid::{Point, Rect};
 
-use crate::shapes::Shape;
+use crate::{
+    consts::CHAR_SPACE,
+    shapes::{
+        bounds,
+        group::{draw_merged, GroupShape},
+        Shape,
+    },
+};
+
+use super::{
+    align::{self, Arrange, Bounds, Guide},
+    grid_list::GridList,
+    history::{Version, HISTORY_MANAGER},
+    overlap,
+    transform::{Area, Transform},
+};
 
-use super::grid_list::GridList;
+pub enum ZOrder {
+    Forward,
+    Backward,
+    Front,
+    Back,
+}
+
+// Shapes of a part of the list before a change, so the change can be saved
+struct Snapshot {
+    at: usize,
+    shapes: Vec<Box<dyn Shape>>,
+    // How many shapes were on the canvas
+    count: usize,
+}
 
 pub struct ShapeList {
     pub data: Vec<Box<dyn Shape>>,
+    // Indices of the shapes selected with the select tool
+    pub selected: Vec<usize>,
+    // Shown while the selected shapes are dragged around
+    pub guides: Vec<Guide>,
 }
 
 impl ShapeList {
     pub fn new() -> Self {
-        Self { data: vec![] }
+        Self {
+            data: vec![],
+            selected: vec![],
+            guides: vec![],
+        }
     }
 
     pub fn draw(&mut self, grid_list: &mut GridList) {
@@ -22,23 +63,706 @@ impl ShapeList {
     }
 
     pub fn commit(&mut self, grid_list: &mut GridList) {
+        if !self
+            .data
+            .iter()
+            .any(|shape| shape.is_preview() && !shape.is_manual_commit())
+        {
+            return;
+        }
+        let from = self.snapshot_end();
+        unsafe {
+            HISTORY_MANAGER.begin_group();
+        }
         for shape in self.data.iter_mut() {
             if shape.is_preview() && !shape.is_manual_commit() {
                 shape.commit(grid_list);
             }
         }
+        self.save_shapes(from);
+        unsafe {
+            HISTORY_MANAGER.end_group();
+        }
     }
 
     pub fn commit_all(&mut self, grid_list: &mut GridList) {
+        let has_previews = self.data.iter().any(|shape| shape.is_preview());
+        let from = self.snapshot_end();
+        unsafe {
+            HISTORY_MANAGER.begin_group();
+        }
         for shape in self.data.iter_mut() {
             shape.commit(grid_list);
         }
+        if has_previews {
+            self.save_shapes(from);
+        }
+        unsafe {
+            HISTORY_MANAGER.end_group();
+        }
+    }
+
+    // The shapes on the canvas, the ones still drawn as previews aren't there yet
+    fn committed(&self) -> impl Iterator<Item = &Box<dyn Shape>> {
+        self.data.iter().filter(|shape| !shape.is_preview())
+    }
+
+    // Copies of the shapes on the canvas in the given part of the list
+    fn snapshot(&self, range: Range<usize>) -> Snapshot {
+        Snapshot {
+            at: range.start,
+            shapes: self
+                .committed()
+                .skip(range.start)
+                .take(range.end.saturating_sub(range.start))
+                .map(|shape| shape.duplicate())
+                .collect(),
+            count: self.committed().count(),
+        }
+    }
+
+    // Nothing yet after the shapes on the canvas, for the ones about to be added
+    fn snapshot_end(&self) -> Snapshot {
+        let count = self.committed().count();
+        self.snapshot(count..count)
+    }
+
+    // Keep the change to the part of the list in the snapshot in the history,
+    // along with the cells changed in the current group
+    fn save_shapes(&self, from: Snapshot) {
+        let count = self.committed().count();
+        let end = (from.at + from.shapes.len() + count).saturating_sub(from.count);
+        let to = self.snapshot(from.at..end.max(from.at)).shapes;
+        if from.shapes.is_empty() && to.is_empty() {
+            return;
+        }
+        unsafe {
+            HISTORY_MANAGER.save_shapes(from.at, from.shapes, to);
+        }
+    }
+
+    // Put back the shapes of an undone or redone change in place of the `count`
+    // shapes from `at`, the previews being drawn stay on top
+    pub fn restore(&mut self, at: usize, count: usize, shapes: Vec<Box<dyn Shape>>) {
+        let (previews, mut data): (Vec<_>, Vec<_>) =
+            self.data.drain(..).partition(|shape| shape.is_preview());
+        let end = (at + count).min(data.len());
+        data.splice(at.min(end)..end, shapes);
+        data.extend(previews);
+        self.data = data;
+        self.selected.clear();
+        self.guides.clear();
+    }
+
+    // The part of the list from the first to the last selected shape
+    fn selected_range(&self) -> Range<usize> {
+        match (self.selected.iter().min(), self.selected.iter().max()) {
+            (Some(&first), Some(&last)) => first..last + 1,
+            _ => 0..0,
+        }
     }
 
     pub fn add_shape(&mut self, shape: Box<dyn Shape>) {
         self.data.push(shape);
     }
 
+    pub fn insert_shapes(&mut self, shapes: Vec<Box<dyn Shape>>, grid_list: &mut GridList) {
+        let from = self.snapshot_end();
+        unsafe {
+            HISTORY_MANAGER.begin_group();
+        }
+        for mut shape in shapes {
+            shape.draw(grid_list);
+            shape.commit(grid_list);
+            self.data.push(shape);
+        }
+        self.save_shapes(from);
+        unsafe {
+            HISTORY_MANAGER.end_group();
+        }
+    }
+
+    // Redraw a committed shape after changing it. Only the cells it was drawn on
+    // are cleared first, so nothing is left behind when the shape shrinks and the
+    // shapes and text around it stay as they are.
+    pub fn update_shape<F>(&mut self, index: usize, grid_list: &mut GridList, update: F)
+    where
+        F: FnOnce(&mut Box<dyn Shape>),
+    {
+        if index >= self.data.len() {
+            return;
+        }
+        let from = self.snapshot(index..index + 1);
+        unsafe {
+            HISTORY_MANAGER.begin_group();
+        }
+        self.erase_shapes(&[index], grid_list);
+        let shape = &mut self.data[index];
+        update(shape);
+        shape.draw(grid_list);
+        shape.commit(grid_list);
+        self.save_shapes(from);
+        unsafe {
+            HISTORY_MANAGER.end_group();
+        }
+    }
+
+    // The top most shape drawing something on the given cell
+    pub fn find_shape_at_cell(
+        &mut self,
+        row: usize,
+        col: usize,
+        grid_list: &mut GridList,
+    ) -> Option<usize> {
+        let (rows, cols) = grid_list.grid_size;
+        if row >= rows || col >= cols {
+            return None;
+        }
+        let i = row * cols + col;
+        let mut found = None;
+        for (index, shape) in self.data.iter_mut().enumerate().rev() {
+            shape.draw(grid_list);
+            let hit = grid_list.get(i).preview.is_some();
+            grid_list.discard_all();
+            if hit {
+                found = Some(index);
+                break;
+            }
+        }
+        found
+    }
+
+    // Take shapes off the canvas without removing them from the list. Only the
+    // cells they were drawn on are touched, the other shapes crossing them are
+    // drawn again there so their junctions are repaired.
+    fn erase_shapes(&mut self, indices: &[usize], grid_list: &mut GridList) {
+        grid_list.discard_all();
+        let mut cells: HashMap<usize, char> = HashMap::new();
+        let mut area: Option<Bounds> = None;
+        for &index in indices {
+            if let Some(shape) = self.data.get_mut(index) {
+                let (top_left, bottom_right) = bounds(shape.as_ref());
+                shape.draw(grid_list);
+                for (i, glyph, _) in grid_list.take_previews_in(top_left, bottom_right) {
+                    let content = match cells.get(&i) {
+                        Some(content) => *content,
+                        None => grid_list.get(i).content,
+                    };
+                    cells.insert(i, overlap::remove_cell_content(content, glyph));
+                }
+                area = Some(union(area, (top_left, bottom_right)));
+            }
+        }
+        let (start, end) = match area {
+            Some(area) => area,
+            None => return,
+        };
+
+        for (index, other) in self.data.iter_mut().enumerate() {
+            let (top_left, bottom_right) = bounds(other.as_ref());
+            if indices.contains(&index) || !crosses(&(top_left, bottom_right), &(start, end)) {
+                continue;
+            }
+            other.draw(grid_list);
+            for (i, glyph, direction) in grid_list.take_previews_in(top_left, bottom_right) {
+                if let Some(content) = cells.get_mut(&i) {
+                    // Text written over the removed shape stays on top
+                    if *content == CHAR_SPACE || overlap::is_line_glyph(*content) {
+                        *content = overlap::calculate_cell_content(direction, *content, glyph);
+                    }
+                }
+            }
+        }
+        grid_list.discard_all();
+
+        let mut cells: Vec<(usize, char)> = cells.into_iter().collect();
+        cells.sort();
+        let mut version = Version::new();
+        for (i, content) in cells {
+            let cell = grid_list.get(i);
+            if cell.content != content {
+                version.push(i, cell.content, content);
+                cell.set_content(content);
+            }
+        }
+        unsafe {
+            HISTORY_MANAGER.save_version(version);
+        }
+    }
+
+    pub fn remove_shape(&mut self, index: usize, grid_list: &mut GridList) {
+        if index >= self.data.len() {
+            return;
+        }
+        let from = self.snapshot(index..index + 1);
+        unsafe {
+            HISTORY_MANAGER.begin_group();
+        }
+        self.erase_shapes(&[index], grid_list);
+        self.data.remove(index);
+        self.save_shapes(from);
+        unsafe {
+            HISTORY_MANAGER.end_group();
+        }
+        self.unselect_removed(index);
+    }
+
+    fn unselect_removed(&mut self, index: usize) {
+        self.selected.retain(|selected| *selected != index);
+        for selected in self.selected.iter_mut() {
+            if *selected > index {
+                *selected -= 1;
+            }
+        }
+    }
+
+    // Cells written by hand no longer show the shapes drawn on them, so every
+    // shape covering one of them is taken off the list. What's left of it stays
+    // on the canvas as text.
+    pub fn detach_shapes(&mut self, cells: &[usize], grid_list: &mut GridList) {
+        self.detach_shapes_except(cells, &[], grid_list);
+    }
+
+    // Same as detach_shapes(), the given shapes are left alone
+    pub fn detach_shapes_except(
+        &mut self,
+        cells: &[usize],
+        keep: &[usize],
+        grid_list: &mut GridList,
+    ) {
+        let (_, cols) = grid_list.grid_size;
+        let mut area = None;
+        for i in cells {
+            let cell = (i / cols, i % cols);
+            area = Some(union(area, (cell, cell)));
+        }
+        let area = match area {
+            Some(area) => area,
+            None => return,
+        };
+        let cells: HashSet<usize> = cells.iter().copied().collect();
+        grid_list.discard_all();
+        let mut detached = vec![];
+        for (index, shape) in self.data.iter_mut().enumerate() {
+            let shape_bounds = bounds(shape.as_ref());
+            if shape.is_preview() || keep.contains(&index) || !crosses(&shape_bounds, &area) {
+                continue;
+            }
+            shape.draw(grid_list);
+            if grid_list
+                .take_previews_in(shape_bounds.0, shape_bounds.1)
+                .iter()
+                .any(|(i, _, _)| cells.contains(i))
+            {
+                detached.push(index);
+            }
+        }
+        grid_list.discard_all();
+        if detached.is_empty() {
+            return;
+        }
+
+        unsafe {
+            HISTORY_MANAGER.begin_group();
+        }
+        for index in detached.into_iter().rev() {
+            let from = self.snapshot(index..index + 1);
+            self.data.remove(index);
+            self.save_shapes(from);
+            self.unselect_removed(index);
+        }
+        unsafe {
+            HISTORY_MANAGER.end_group();
+        }
+    }
+
+    pub fn select(&mut self, index: usize, add: bool) {
+        if add {
+            match self.selected.iter().position(|selected| *selected == index) {
+                Some(i) => {
+                    self.selected.remove(i);
+                }
+                None => self.selected.push(index),
+            }
+        } else if !self.selected.contains(&index) {
+            self.selected = vec![index];
+        }
+    }
+
+    // Top left and bottom right cells of every selected shape
+    pub fn selected_bounds(&self) -> Vec<((usize, usize), (usize, usize))> {
+        self.selected
+            .iter()
+            .filter_map(|index| self.data.get(*index))
+            .map(|shape| bounds(shape.as_ref()))
+            .collect()
+    }
+
+    // Change where the selected shapes are in the list, later shapes are drawn on
+    // top of earlier ones
+    pub fn reorder_selected(&mut self, order: ZOrder, grid_list: &mut GridList) {
+        let mut selected = self.selected.clone();
+        selected.sort();
+        if selected.is_empty() {
+            return;
+        }
+        // Shapes only move next to the selected ones, or to either end
+        let start = match order {
+            ZOrder::Back => 0,
+            _ => selected[0].saturating_sub(1),
+        };
+        let end = match order {
+            ZOrder::Front => self.data.len(),
+            _ => selected[selected.len() - 1] + 2,
+        };
+        let from = self.snapshot(start..end);
+        unsafe {
+            HISTORY_MANAGER.begin_group();
+        }
+        match order {
+            ZOrder::Forward => {
+                for &index in selected.iter().rev() {
+                    if index + 1 < self.data.len() && !self.selected.contains(&(index + 1)) {
+                        self.data.swap(index, index + 1);
+                        self.selected = replace(&self.selected, index, index + 1);
+                    }
+                }
+            }
+            ZOrder::Backward => {
+                for &index in selected.iter() {
+                    if index > 0 && !self.selected.contains(&(index - 1)) {
+                        self.data.swap(index, index - 1);
+                        self.selected = replace(&self.selected, index, index - 1);
+                    }
+                }
+            }
+            ZOrder::Front | ZOrder::Back => {
+                let shapes: Vec<Box<dyn Shape>> = selected
+                    .iter()
+                    .rev()
+                    .map(|&index| self.data.remove(index))
+                    .collect();
+                let count = shapes.len();
+                if let ZOrder::Front = order {
+                    self.data.extend(shapes.into_iter().rev());
+                    self.selected = (self.data.len() - count..self.data.len()).collect();
+                } else {
+                    for shape in shapes {
+                        self.data.insert(0, shape);
+                    }
+                    self.selected = (0..count).collect();
+                }
+            }
+        }
+        let selected = self.selected.clone();
+        self.redraw_shapes(&selected, grid_list);
+        self.save_shapes(from);
+        unsafe {
+            HISTORY_MANAGER.end_group();
+        }
+    }
+
+    // Draw the cells covered by the given shapes again, with every shape on them
+    // drawn in list order
+    fn redraw_shapes(&mut self, indices: &[usize], grid_list: &mut GridList) {
+        grid_list.discard_all();
+        let mut previews = vec![];
+        for shape in self.data.iter_mut() {
+            let (top_left, bottom_right) = bounds(shape.as_ref());
+            shape.draw(grid_list);
+            previews.push(grid_list.take_previews_in(top_left, bottom_right));
+        }
+        grid_list.discard_all();
+        let mut cells: Vec<usize> = indices
+            .iter()
+            .filter_map(|index| previews.get(*index))
+            .flat_map(|shape_previews| shape_previews.iter().map(|(i, _, _)| *i))
+            .collect();
+        cells.sort();
+        cells.dedup();
+
+        let mut contents: Vec<char> = cells.iter().map(|i| grid_list.get(*i).content).collect();
+        for shape_previews in previews.iter() {
+            for (i, glyph, _) in shape_previews {
+                if let Ok(j) = cells.binary_search(i) {
+                    contents[j] = overlap::remove_cell_content(contents[j], *glyph);
+                }
+            }
+        }
+        for shape_previews in previews.iter() {
+            for (i, glyph, direction) in shape_previews {
+                if let Ok(j) = cells.binary_search(i) {
+                    contents[j] = overlap::calculate_cell_content(*direction, contents[j], *glyph);
+                }
+            }
+        }
+
+        let mut version = Version::new();
+        for (i, content) in cells.into_iter().zip(contents) {
+            let cell = grid_list.get(i);
+            if cell.content != content {
+                version.push(i, cell.content, content);
+                cell.set_content(content);
+            }
+        }
+        unsafe {
+            HISTORY_MANAGER.save_version(version);
+        }
+    }
+
+    // Clear the selected shapes from the canvas, so they can be moved around as
+    // previews until drop_selected() is called
+    pub fn lift_selected(&mut self, grid_list: &mut GridList) {
+        let selected = self.selected.clone();
+        self.erase_shapes(&selected, grid_list);
+    }
+
+    // Move the lifted shapes, as far as the canvas edges allow. Returns how far
+    // they actually moved.
+    pub fn move_selected(
+        &mut self,
+        offset: (isize, isize),
+        grid_list: &mut GridList,
+    ) -> (isize, isize) {
+        let (rows, cols) = grid_list.grid_size;
+        let mut offset = offset;
+        for ((top, left), (bottom, right)) in self.selected_bounds() {
+            let max_down = (rows - 1).saturating_sub(bottom) as isize;
+            let max_right = (cols - 1).saturating_sub(right) as isize;
+            offset.0 = offset.0.clamp(-(top as isize), max_down);
+            offset.1 = offset.1.clamp(-(left as isize), max_right);
+        }
+        let from = self.snapshot(self.selected_range());
+        let selected = self.selected.clone();
+        for (index, shape) in self.data.iter_mut().enumerate() {
+            if selected.contains(&index) {
+                shape.translate(offset);
+            }
+        }
+        self.save_shapes(from);
+        draw_merged(
+            self.data
+                .iter_mut()
+                .enumerate()
+                .filter(|(index, _)| selected.contains(index))
+                .map(|(_, shape)| shape),
+            grid_list,
+        );
+        offset
+    }
+
+    // The area covered by all the selected shapes, and the bounds of the others
+    fn split_bounds(&self) -> Option<(Bounds, Vec<Bounds>)> {
+        let moving = self.selected_bounds().into_iter().reduce(
+            |(start, end), (top_left, bottom_right)| {
+                (
+                    (start.0.min(top_left.0), start.1.min(top_left.1)),
+                    (end.0.max(bottom_right.0), end.1.max(bottom_right.1)),
+                )
+            },
+        )?;
+        let others = self
+            .data
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| !self.selected.contains(index))
+            .map(|(_, shape)| bounds(shape.as_ref()))
+            .collect();
+        Some((moving, others))
+    }
+
+    // Adjust how far the selected shapes are about to move, so they snap to the
+    // shapes they are close to lining up with
+    pub fn snap_selected(&self, offset: (isize, isize)) -> (isize, isize) {
+        match self.split_bounds() {
+            Some((moving, others)) => align::snap(&moving, offset, &others),
+            None => offset,
+        }
+    }
+
+    pub fn update_guides(&mut self) {
+        self.guides = match self.split_bounds() {
+            Some((moving, others)) => align::guides(&moving, &others),
+            None => vec![],
+        };
+    }
+
+    // Align or distribute the selected shapes
+    pub fn arrange_selected(&mut self, arrange: Arrange, grid_list: &mut GridList) {
+        let selected = self.selected.clone();
+        let offsets = align::arrange(arrange, &self.selected_bounds());
+        if offsets.iter().all(|offset| *offset == (0, 0)) {
+            return;
+        }
+        unsafe {
+            HISTORY_MANAGER.begin_group();
+        }
+        let from = self.snapshot(self.selected_range());
+        self.lift_selected(grid_list);
+        for (&index, offset) in selected.iter().zip(offsets) {
+            self.data[index].translate(offset);
+        }
+        self.save_shapes(from);
+        draw_merged(
+            self.data
+                .iter_mut()
+                .enumerate()
+                .filter(|(index, _)| selected.contains(index))
+                .map(|(_, shape)| shape),
+            grid_list,
+        );
+        self.drop_selected(grid_list);
+        unsafe {
+            HISTORY_MANAGER.end_group();
+        }
+    }
+
+    // Flip or rotate the lifted shapes within the area, they're drawn as previews
+    // until drop_selected() is called
+    pub fn transform_selected(
+        &mut self,
+        transform: Transform,
+        area: Area,
+        grid_list: &mut GridList,
+    ) {
+        let from = self.snapshot(self.selected_range());
+        let selected = self.selected.clone();
+        for (index, shape) in self.data.iter_mut().enumerate() {
+            if selected.contains(&index) {
+                shape.transform(transform, area);
+            }
+        }
+        self.save_shapes(from);
+        draw_merged(
+            self.data
+                .iter_mut()
+                .enumerate()
+                .filter(|(index, _)| selected.contains(index))
+                .map(|(_, shape)| shape),
+            grid_list,
+        );
+    }
+
+    pub fn drop_selected(&mut self, grid_list: &mut GridList) {
+        grid_list.commit_all();
+    }
+
+    pub fn nudge_selected(&mut self, offset: (isize, isize), grid_list: &mut GridList) {
+        if self.selected.is_empty() {
+            return;
+        }
+        unsafe {
+            HISTORY_MANAGER.begin_group();
+        }
+        self.lift_selected(grid_list);
+        self.move_selected(offset, grid_list);
+        self.drop_selected(grid_list);
+        unsafe {
+            HISTORY_MANAGER.end_group();
+        }
+    }
+
+    pub fn remove_selected(&mut self, grid_list: &mut GridList) {
+        let mut selected = self.selected.clone();
+        selected.sort();
+        unsafe {
+            HISTORY_MANAGER.begin_group();
+        }
+        for index in selected.into_iter().rev() {
+            self.remove_shape(index, grid_list);
+        }
+        unsafe {
+            HISTORY_MANAGER.end_group();
+        }
+    }
+
+    // Copies of the selected shapes, in the order they are drawn
+    pub fn selected_shapes(&self) -> Vec<Box<dyn Shape>> {
+        let mut selected = self.selected.clone();
+        selected.sort();
+        selected
+            .iter()
+            .filter_map(|index| self.data.get(*index))
+            .map(|shape| shape.duplicate())
+            .collect()
+    }
+
+    // Copies are placed one row and one column away, and become the selection
+    pub fn duplicate_selected(&mut self, grid_list: &mut GridList) {
+        let mut selected = self.selected.clone();
+        selected.sort();
+        let copies: Vec<Box<dyn Shape>> = selected
+            .iter()
+            .filter_map(|index| self.data.get(*index))
+            .map(|shape| {
+                let mut copy = shape.duplicate();
+                copy.translate((1, 1));
+                copy
+            })
+            .collect();
+        let first = self.data.len();
+        self.insert_shapes(copies, grid_list);
+        self.selected = (first..self.data.len()).collect();
+    }
+
+    // Grouping and ungrouping only changes the shape list, the canvas stays the same
+    pub fn group_selected(&mut self) {
+        if self.selected.len() < 2 {
+            return;
+        }
+        let from = self.snapshot(self.selected_range().start..self.data.len());
+        let mut selected = self.selected.clone();
+        selected.sort();
+        let mut shapes: Vec<Box<dyn Shape>> = selected
+            .into_iter()
+            .rev()
+            .map(|index| self.data.remove(index))
+            .collect();
+        shapes.reverse();
+        self.data.push(Box::new(GroupShape::new(shapes)));
+        self.selected = vec![self.data.len() - 1];
+        self.save_shapes(from);
+    }
+
+    pub fn ungroup_selected(&mut self) {
+        let from = self.snapshot(self.selected_range().start..self.data.len());
+        let mut selected = self.selected.clone();
+        selected.sort();
+        let mut ungrouped: Vec<Vec<Box<dyn Shape>>> = vec![];
+        for index in selected.into_iter().rev() {
+            let shape = self.data.remove(index);
+            match shape.as_any().downcast_ref::<GroupShape>() {
+                Some(group) => {
+                    ungrouped.push(group.shapes.iter().map(|shape| shape.duplicate()).collect())
+                }
+                None => ungrouped.push(vec![shape]),
+            }
+        }
+        let first = self.data.len();
+        self.data.extend(ungrouped.into_iter().rev().flatten());
+        self.selected = (first..self.data.len()).collect();
+        self.save_shapes(from);
+    }
+
+    // Drop the block moved with the select tool. The selected shapes came along
+    // with its cells, only where they are changes.
+    pub fn drop_block(&mut self, offset: (isize, isize), grid_list: &mut GridList) {
+        let from = self.snapshot(self.selected_range());
+        unsafe {
+            HISTORY_MANAGER.begin_group();
+        }
+        if let Some(mut block) = self.data.pop() {
+            block.commit(grid_list);
+        }
+        for &index in self.selected.iter() {
+            self.data[index].translate(offset);
+        }
+        self.save_shapes(from);
+        unsafe {
+            HISTORY_MANAGER.end_group();
+        }
+    }
+
     pub fn find_shape_in_point(
         &mut self,
         point: Point,
@@ -61,11 +785,7 @@ impl ShapeList {
         None
     }
 
-    pub fn find_shape_in_rect(
-        &mut self,
-        rect: Rect,
-        grid_list: &mut GridList,
-    ) -> Vec<&mut Box<dyn Shape>> {
+    pub fn find_shape_in_rect(&mut self, rect: Rect, grid_list: &mut GridList) -> Vec<usize> {
         let mut result = vec![];
         let (cell_width, cell_height) = grid_list.cell_size;
         let start_row = rect.y0 / cell_height;
@@ -74,13 +794,107 @@ impl ShapeList {
         let end_col = rect.x1 / cell_width;
         let selection_rect = Rect::new(start_row, start_col, end_row, end_col);
 
-        for shape in self.data.iter_mut() {
+        for (index, shape) in self.data.iter().enumerate() {
             let (start_point, end_point) = shape.get_points();
             if selection_rect.contains(start_point) && selection_rect.contains(end_point) {
-                result.push(shape);
+                result.push(index);
             }
         }
 
         result
     }
 }
+
+// The smallest area covering both
+fn union(area: Option<Bounds>, other: Bounds) -> Bounds {
+    match area {
+        Some((start, end)) => (
+            (start.0.min(other.0 .0), start.1.min(other.0 .1)),
+            (end.0.max(other.1 .0), end.1.max(other.1 .1)),
+        ),
+        None => other,
+    }
+}
+
+// Whether two areas have a cell in common
+fn crosses(a: &Bounds, b: &Bounds) -> bool {
+    a.0 .0 <= b.1 .0 && a.0 .1 <= b.1 .1 && a.1 .0 >= b.0 .0 && a.1 .1 >= b.0 .1
+}
+
+fn replace(indices: &[usize], from: usize, to: usize) -> Vec<usize> {
+    indices
+        .iter()
+        .map(|&index| if index == from { to } else { index })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{data::history::HISTORY_LOCK, shapes::rect::RectShape};
+
+    fn content(grid_list: &mut GridList) -> String {
+        let (rows, cols) = grid_list.grid_size;
+        (0..rows * cols).map(|i| grid_list.get(i).content).collect()
+    }
+
+    #[test]
+    fn erased_shapes_are_not_drawn_again_when_moving_the_selection() {
+        let _lock = HISTORY_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        unsafe {
+            HISTORY_MANAGER.reset();
+        }
+        let mut grid_list = GridList::new(10.0, 10.0, 10, 30);
+        let mut shape_list = ShapeList::new();
+        shape_list.insert_shapes(
+            vec![
+                Box::new(RectShape::from_points((1, 1), (4, 8))),
+                Box::new(RectShape::from_points((6, 1), (8, 5))),
+            ],
+            &mut grid_list,
+        );
+        let (_, cols) = grid_list.grid_size;
+
+        unsafe {
+            HISTORY_MANAGER.begin_group();
+        }
+        let cells = grid_list.erase_area((1, 1), (2, 4));
+        shape_list.detach_shapes(&cells, &mut grid_list);
+        unsafe {
+            HISTORY_MANAGER.end_group();
+        }
+        assert_eq!(shape_list.data.len(), 1);
+        let erased = content(&mut grid_list);
+
+        // Nothing is left to select where the shape was, what's left of it is text
+        for (row, col) in [(1, 1), (2, 1), (4, 8)] {
+            assert_eq!(
+                shape_list.find_shape_at_cell(row, col, &mut grid_list),
+                None
+            );
+        }
+        let index = shape_list.find_shape_at_cell(6, 1, &mut grid_list).unwrap();
+        shape_list.select(index, false);
+        shape_list.nudge_selected((0, 10), &mut grid_list);
+        shape_list.select(index, false);
+        shape_list.nudge_selected((-5, 0), &mut grid_list);
+
+        let moved = content(&mut grid_list);
+        for row in 1..=4 {
+            for col in 1..=8 {
+                let i = row * cols + col;
+                assert_eq!(moved.chars().nth(i), erased.chars().nth(i));
+            }
+        }
+        for col in 1..=4 {
+            assert_eq!(grid_list.get(cols + col).content, ' ');
+        }
+
+        unsafe {
+            HISTORY_MANAGER.undo(&mut grid_list, &mut shape_list);
+            HISTORY_MANAGER.undo(&mut grid_list, &mut shape_list);
+            HISTORY_MANAGER.undo(&mut grid_list, &mut shape_list);
+        }
+        assert_eq!(shape_list.find_shape_at_cell(1, 1, &mut grid_list), Some(0));
+    }
+}
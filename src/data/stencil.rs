@@ -0,0 +1,221 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use super::config::config_dir;
+
+const BUILTIN_STENCILS: &[(&str, &str)] = &[
+    (
+        "Database",
+        r#"
+ .---------.
+(           )
+|`---------'|
+|           |
+|           |
+ `---------'
+"#,
+    ),
+    (
+        "Cloud",
+        r#"
+      .--.   .-.
+   .-(    )-(   )-.
+  (                )
+ (                  )
+  `-.____.--.____.-'
+"#,
+    ),
+    (
+        "Server",
+        r#"
+┌─────────────┐
+│ ▪ ═══════ ○ │
+├─────────────┤
+│ ▪ ═══════ ○ │
+├─────────────┤
+│ ▪ ═══════ ○ │
+└─────────────┘
+"#,
+    ),
+    (
+        "Person",
+        r#"
+  O
+ /|\
+ / \
+"#,
+    ),
+    (
+        "Decision",
+        r#"
+   /\
+  /  \
+ /    \
+/      \
+\      /
+ \    /
+  \  /
+   \/
+"#,
+    ),
+    (
+        "Note",
+        r#"
+┌─────────┐
+│         │\
+│         └─┐
+│           │
+│           │
+└───────────┘
+"#,
+    ),
+    (
+        "Browser",
+        r#"
+┌────────────────┐
+│ ● ● ●          │
+├────────────────┤
+│                │
+│                │
+└────────────────┘
+"#,
+    ),
+    (
+        "Queue",
+        r#"
+┌───┬───┬───┬───┐
+│   │   │   │   │
+└───┴───┴───┴───┘
+"#,
+    ),
+    (
+        "Rounded box",
+        r#"
+╭────────────╮
+│            │
+╰────────────╯
+"#,
+    ),
+];
+
+#[derive(Clone, Debug)]
+pub struct Stencil {
+    pub name: String,
+    pub content: String,
+    // Only user stencils live on disk
+    pub path: Option<PathBuf>,
+}
+
+pub fn user_stencil_dir() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("stencils"))
+}
+
+pub fn builtin_stencils() -> Vec<Stencil> {
+    BUILTIN_STENCILS
+        .iter()
+        .map(|(name, content)| Stencil {
+            name: name.to_string(),
+            content: content.trim_start_matches('\n').trim_end().to_string(),
+            path: None,
+        })
+        .collect()
+}
+
+// Every .txt file in the user stencil folder is a stencil, named after the file
+pub fn user_stencils() -> Vec<Stencil> {
+    let mut stencils = vec![];
+    if let Some(entries) = user_stencil_dir().and_then(|dir| fs::read_dir(dir).ok()) {
+        for path in entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+        {
+            if path.extension().filter(|ext| *ext == "txt").is_none() {
+                continue;
+            }
+            match fs::read_to_string(&path) {
+                Ok(content) => {
+                    let name = path
+                        .file_stem()
+                        .map(|name| name.to_string_lossy().to_string())
+                        .unwrap_or_default();
+                    stencils.push(Stencil {
+                        name,
+                        content: content.trim_end().to_string(),
+                        path: Some(path),
+                    });
+                }
+                Err(e) => {
+                    println!("Error loading stencil: {e}");
+                }
+            }
+        }
+    }
+    stencils.sort_by_key(|stencil| stencil.name.to_lowercase());
+    stencils
+}
+
+pub fn all_stencils() -> Vec<Stencil> {
+    let mut stencils = builtin_stencils();
+    stencils.extend(user_stencils());
+    stencils
+}
+
+pub fn is_valid_name(name: &str) -> bool {
+    !name.trim().is_empty()
+        && !name
+            .chars()
+            .any(|c| matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|'))
+}
+
+// Add a number after the name if there's already a stencil with that name
+fn available_path(dir: &Path, name: &str) -> PathBuf {
+    let mut path = dir.join(format!("{}.txt", name));
+    let mut i = 2;
+    while path.exists() {
+        path = dir.join(format!("{} {}.txt", name, i));
+        i += 1;
+    }
+    path
+}
+
+pub fn save_user_stencil(name: &str, content: &str) -> io::Result<PathBuf> {
+    let dir = user_stencil_dir()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "No config directory"))?;
+    fs::create_dir_all(&dir)?;
+    let path = available_path(&dir, name.trim());
+    fs::write(&path, content)?;
+    Ok(path)
+}
+
+pub fn rename_user_stencil(stencil: &Stencil, name: &str) -> io::Result<PathBuf> {
+    let path = stencil
+        .path
+        .as_ref()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::PermissionDenied, "Built-in stencil"))?;
+    let name = name.trim();
+    if !is_valid_name(name) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Invalid stencil name",
+        ));
+    }
+    if name == stencil.name {
+        return Ok(path.to_owned());
+    }
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let new_path = available_path(dir, name);
+    fs::rename(path, &new_path)?;
+    Ok(new_path)
+}
+
+pub fn delete_user_stencil(stencil: &Stencil) -> io::Result<()> {
+    match &stencil.path {
+        Some(path) => fs::remove_file(path),
+        None => Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "Built-in stencil",
+        )),
+    }
+}
@@ -0,0 +1,140 @@
+use crate::consts::{
+    CHAR_ARROW_DOWN, CHAR_ARROW_LEFT, CHAR_ARROW_RIGHT, CHAR_ARROW_UP, CHAR_DIAGONAL_DOWN,
+    CHAR_DIAGONAL_UP, CHAR_SPACE,
+};
+
+use super::overlap::{self, ARM_DOWN, ARM_LEFT, ARM_RIGHT, ARM_UP};
+
+// Top left and bottom right cells of the area being transformed
+pub type Area = ((usize, usize), (usize, usize));
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Transform {
+    FlipHorizontal,
+    FlipVertical,
+    RotateClockwise,
+    RotateCounterClockwise,
+}
+
+impl Transform {
+    // The area keeps its top left corner, rotating it swaps its width and height
+    pub fn map_area(&self, area: Area) -> Area {
+        let ((top, left), (bottom, right)) = area;
+        match self {
+            Transform::FlipHorizontal | Transform::FlipVertical => area,
+            Transform::RotateClockwise | Transform::RotateCounterClockwise => {
+                ((top, left), (top + right - left, left + bottom - top))
+            }
+        }
+    }
+
+    // Where a cell of the area ends up
+    pub fn map_point(&self, point: (usize, usize), area: Area) -> (usize, usize) {
+        let ((top, left), (bottom, right)) = area;
+        let (height, width) = (bottom - top, right - left);
+        let (row, col) = (
+            point.0.clamp(top, bottom) - top,
+            point.1.clamp(left, right) - left,
+        );
+        match self {
+            Transform::FlipHorizontal => (top + row, left + width - col),
+            Transform::FlipVertical => (top + height - row, left + col),
+            Transform::RotateClockwise => (top + col, left + height - row),
+            Transform::RotateCounterClockwise => (top + width - col, left + row),
+        }
+    }
+
+    // Directional glyphs have to be turned around too, for the result to still
+    // connect the same way
+    pub fn map_glyph(&self, glyph: char) -> char {
+        let sides = match self {
+            Transform::FlipHorizontal => [ARM_UP, ARM_DOWN, ARM_RIGHT, ARM_LEFT],
+            Transform::FlipVertical => [ARM_DOWN, ARM_UP, ARM_LEFT, ARM_RIGHT],
+            Transform::RotateClockwise => [ARM_RIGHT, ARM_LEFT, ARM_UP, ARM_DOWN],
+            Transform::RotateCounterClockwise => [ARM_LEFT, ARM_RIGHT, ARM_DOWN, ARM_UP],
+        };
+        if let Some(glyph) = overlap::remap_line_glyph(glyph, sides) {
+            return glyph;
+        }
+        match (self, glyph) {
+            (_, CHAR_DIAGONAL_UP) => CHAR_DIAGONAL_DOWN,
+            (_, CHAR_DIAGONAL_DOWN) => CHAR_DIAGONAL_UP,
+            (Transform::FlipHorizontal, CHAR_ARROW_LEFT) => CHAR_ARROW_RIGHT,
+            (Transform::FlipHorizontal, CHAR_ARROW_RIGHT) => CHAR_ARROW_LEFT,
+            (Transform::FlipHorizontal, '<') => '>',
+            (Transform::FlipHorizontal, '>') => '<',
+            (Transform::FlipHorizontal, '(') => ')',
+            (Transform::FlipHorizontal, ')') => '(',
+            (Transform::FlipHorizontal, '[') => ']',
+            (Transform::FlipHorizontal, ']') => '[',
+            (Transform::FlipHorizontal, '{') => '}',
+            (Transform::FlipHorizontal, '}') => '{',
+            (Transform::FlipVertical, CHAR_ARROW_UP) => CHAR_ARROW_DOWN,
+            (Transform::FlipVertical, CHAR_ARROW_DOWN) => CHAR_ARROW_UP,
+            (Transform::RotateClockwise, CHAR_ARROW_UP) => CHAR_ARROW_RIGHT,
+            (Transform::RotateClockwise, CHAR_ARROW_RIGHT) => CHAR_ARROW_DOWN,
+            (Transform::RotateClockwise, CHAR_ARROW_DOWN) => CHAR_ARROW_LEFT,
+            (Transform::RotateClockwise, CHAR_ARROW_LEFT) => CHAR_ARROW_UP,
+            (Transform::RotateCounterClockwise, CHAR_ARROW_UP) => CHAR_ARROW_LEFT,
+            (Transform::RotateCounterClockwise, CHAR_ARROW_LEFT) => CHAR_ARROW_DOWN,
+            (Transform::RotateCounterClockwise, CHAR_ARROW_DOWN) => CHAR_ARROW_RIGHT,
+            (Transform::RotateCounterClockwise, CHAR_ARROW_RIGHT) => CHAR_ARROW_UP,
+            (Transform::RotateClockwise | Transform::RotateCounterClockwise, '-') => '|',
+            (Transform::RotateClockwise | Transform::RotateCounterClockwise, '|') => '-',
+            _ => glyph,
+        }
+    }
+
+    // Transform the rows of characters of an area
+    pub fn apply(&self, rows: &[Vec<char>]) -> Vec<Vec<char>> {
+        let height = rows.len();
+        let width = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+        if height == 0 || width == 0 {
+            return vec![];
+        }
+        let area = ((0, 0), (height - 1, width - 1));
+        let ((_, _), (bottom, right)) = self.map_area(area);
+        let mut result = vec![vec![CHAR_SPACE; right + 1]; bottom + 1];
+        for (row, chars) in rows.iter().enumerate() {
+            for (col, c) in chars.iter().enumerate() {
+                let (to_row, to_col) = self.map_point((row, col), area);
+                result[to_row][to_col] = self.map_glyph(*c);
+            }
+        }
+        if *self == Transform::FlipHorizontal {
+            for chars in result.iter_mut() {
+                keep_text_readable(chars);
+            }
+        }
+        result
+    }
+}
+
+fn is_text(c: char) -> bool {
+    c.is_alphanumeric() || ".,:;!?'\"_#@&*%$".contains(c)
+}
+
+// Mirroring a row reverses the labels in it too, put the words of every label
+// back in reading order
+fn keep_text_readable(chars: &mut [char]) {
+    let mut col = 0;
+    while col < chars.len() {
+        if !is_text(chars[col]) {
+            col += 1;
+            continue;
+        }
+        // A label is a run of words separated by single spaces
+        let start = col;
+        let mut end = col;
+        while end + 1 < chars.len()
+            && (is_text(chars[end + 1])
+                || (chars[end + 1] == CHAR_SPACE
+                    && end + 2 < chars.len()
+                    && is_text(chars[end + 2])))
+        {
+            end += 1;
+        }
+        chars[start..=end].reverse();
+        col = end + 1;
+    }
+}
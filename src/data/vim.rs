@@ -0,0 +1,598 @@
+use druid::{Code, KbKey, KeyEvent};
+
+use crate::consts::CHAR_SPACE;
+
+use super::grid_list::GridList;
+
+type Cell = (usize, usize);
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum VimMode {
+    Normal,
+    // Typing with the text tool
+    Insert,
+    Visual,
+    VisualBlock,
+}
+
+// What the canvas has to do after a key went through the Vim layer
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum VimAction {
+    // Not a Vim command, the key goes on to the shortcuts and the tools
+    Ignored,
+    Handled,
+    MoveTo(Cell),
+    // Start typing at the cell with the text tool
+    Insert(Cell),
+    // Leave the text tool, the cursor goes back on the last typed character
+    StopInsert(Cell),
+    Undo(usize),
+    Redo(usize),
+}
+
+// Text put back by p, whole rows go below the cursor row and anything else
+// after the cursor
+#[derive(Clone)]
+struct Register {
+    text: String,
+    linewise: bool,
+}
+
+// The last change, made again at the cursor by .
+#[derive(Clone)]
+enum Change {
+    Delete(usize),
+    DeleteRows(usize),
+    Replace(char, usize),
+    Put(usize, bool),
+    Insert(String),
+    // Changes of the visual modes, the extent goes from the first cell of the
+    // selection to the last one, so it can be made again on the same size
+    DeleteArea((usize, isize), bool),
+    ReplaceArea((usize, isize), bool, char),
+}
+
+// Vim-style modal editing over the cells of the canvas. Counts apply to the
+// motions and the changes, x and dd clear the cells instead of pulling the rest
+// of the row or the rows below, the canvas doesn't shift its content.
+pub struct Vim {
+    pub mode: VimMode,
+    count: Option<usize>,
+    // Operator waiting for its second key: d, y or r
+    pending: Option<char>,
+    // Where the visual selection started
+    anchor: Cell,
+    register: Option<Register>,
+    last_change: Option<Change>,
+    // What was typed in insert mode, and where the caret of the text tool is.
+    // The caret goes back below the start of the line on Enter, unless it was
+    // moved with the arrows, like in the text tool.
+    inserted: String,
+    insert_position: Cell,
+    insert_line: Option<Cell>,
+    // Cells written by the last change, the shapes drawn on them are detached by
+    // the canvas
+    pub changed: Vec<usize>,
+}
+
+impl Vim {
+    pub fn new() -> Self {
+        Self {
+            mode: VimMode::Normal,
+            count: None,
+            pending: None,
+            anchor: (0, 0),
+            register: None,
+            last_change: None,
+            inserted: String::new(),
+            insert_position: (0, 0),
+            insert_line: None,
+            changed: vec![],
+        }
+    }
+
+    fn is_visual(&self) -> bool {
+        self.mode == VimMode::Visual || self.mode == VimMode::VisualBlock
+    }
+
+    // Keys typed into the text, the table cell being edited or the brush of the
+    // freehand and fill tools are left alone, except in insert mode
+    pub fn handle_key(
+        &mut self,
+        event: &KeyEvent,
+        cursor: Cell,
+        is_typing: bool,
+        takes_characters: bool,
+        editable: bool,
+        grid_list: &mut GridList,
+    ) -> VimAction {
+        if self.mode == VimMode::Insert {
+            if is_typing {
+                return self.insert_key(event, grid_list.grid_size);
+            }
+            // The text tool was left some other way
+            self.mode = VimMode::Normal;
+        }
+        if takes_characters || event.mods.alt() || event.mods.meta() {
+            return VimAction::Ignored;
+        }
+        if event.mods.ctrl() {
+            // Ctrl+Shift+V still pastes from the clipboard
+            return match event.code {
+                Code::KeyV if !event.mods.shift() => {
+                    self.toggle_visual(VimMode::VisualBlock, cursor, grid_list);
+                    VimAction::Handled
+                }
+                Code::KeyR => VimAction::Redo(self.count.take().unwrap_or(1)),
+                _ => VimAction::Ignored,
+            };
+        }
+        let c = match &event.key {
+            KbKey::Character(s) => match s.chars().next() {
+                Some(c) => c,
+                None => return VimAction::Ignored,
+            },
+            KbKey::ArrowLeft | KbKey::ArrowDown | KbKey::ArrowUp | KbKey::ArrowRight => {
+                // Like hjkl, with shift five cells at a time
+                let motion = match event.key {
+                    KbKey::ArrowLeft => 'h',
+                    KbKey::ArrowDown => 'j',
+                    KbKey::ArrowUp => 'k',
+                    _ => 'l',
+                };
+                let step = if event.mods.shift() { 5 } else { 1 };
+                let count = self.count.take().unwrap_or(1) * step;
+                self.pending = None;
+                return match motion_target(motion, count, cursor, grid_list) {
+                    Some(target) => VimAction::MoveTo(target),
+                    None => VimAction::Handled,
+                };
+            }
+            KbKey::Escape => {
+                self.count = None;
+                self.pending = None;
+                if self.is_visual() {
+                    self.leave_visual(grid_list);
+                    return VimAction::Handled;
+                }
+                return VimAction::Ignored;
+            }
+            _ => {
+                self.count = None;
+                self.pending = None;
+                return VimAction::Ignored;
+            }
+        };
+
+        if let Some(operator) = self.pending.take() {
+            let count = self.count.take().unwrap_or(1);
+            if operator == 'r' && self.is_visual() {
+                let block = self.mode == VimMode::VisualBlock;
+                let (start, _) = corners(self.anchor, cursor, block);
+                let change = Change::ReplaceArea(self.extent(cursor), block, c);
+                self.leave_visual(grid_list);
+                return self.change(change, start, editable, grid_list);
+            }
+            let change = match (operator, c) {
+                ('r', c) => Some(Change::Replace(c, count)),
+                ('d', 'd') => Some(Change::DeleteRows(count)),
+                ('y', 'y') => {
+                    self.yank_rows(cursor.0, count, grid_list);
+                    None
+                }
+                _ => None,
+            };
+            return match change {
+                Some(change) => self.change(change, cursor, editable, grid_list),
+                None => VimAction::Handled,
+            };
+        }
+
+        if let Some(digit) = c.to_digit(10) {
+            // A 0 on its own goes to the start of the row
+            if digit != 0 || self.count.is_some() {
+                let count = self.count.unwrap_or(0) * 10 + digit as usize;
+                self.count = Some(count.min(9999));
+                return VimAction::Handled;
+            }
+        }
+        let count = self.count.take();
+        let n = count.unwrap_or(1);
+        if let Some(target) = motion_target(c, n, cursor, grid_list) {
+            return VimAction::MoveTo(target);
+        }
+
+        if self.is_visual() {
+            let block = self.mode == VimMode::VisualBlock;
+            let (start, end) = corners(self.anchor, cursor, block);
+            return match c {
+                'v' => {
+                    self.toggle_visual(VimMode::Visual, cursor, grid_list);
+                    VimAction::Handled
+                }
+                'o' => {
+                    let anchor = self.anchor;
+                    self.anchor = cursor;
+                    VimAction::MoveTo(anchor)
+                }
+                'y' => {
+                    let area = area(start, end, block, grid_list.grid_size);
+                    self.register = Some(Register {
+                        text: area_text(&area, grid_list),
+                        linewise: false,
+                    });
+                    self.leave_visual(grid_list);
+                    VimAction::MoveTo(start)
+                }
+                'd' | 'x' => {
+                    let change = Change::DeleteArea(self.extent(cursor), block);
+                    self.leave_visual(grid_list);
+                    self.change(change, start, editable, grid_list)
+                }
+                'r' => {
+                    self.pending = Some(c);
+                    VimAction::Handled
+                }
+                _ => VimAction::Handled,
+            };
+        }
+
+        match c {
+            'x' => self.change(Change::Delete(n), cursor, editable, grid_list),
+            'd' | 'y' | 'r' => {
+                self.pending = Some(c);
+                self.count = count;
+                VimAction::Handled
+            }
+            'p' | 'P' => self.change(Change::Put(n, c == 'P'), cursor, editable, grid_list),
+            '.' => {
+                let change = match (self.last_change.clone(), count) {
+                    (Some(Change::Delete(_)), Some(n)) => Change::Delete(n),
+                    (Some(Change::DeleteRows(_)), Some(n)) => Change::DeleteRows(n),
+                    (Some(Change::Replace(c, _)), Some(n)) => Change::Replace(c, n),
+                    (Some(Change::Put(_, before)), Some(n)) => Change::Put(n, before),
+                    (Some(change), _) => change,
+                    (None, _) => return VimAction::Handled,
+                };
+                self.change(change, cursor, editable, grid_list)
+            }
+            'u' => VimAction::Undo(n),
+            'i' | 'a' if editable => {
+                let (_, cols) = grid_list.grid_size;
+                let position = if c == 'a' {
+                    (cursor.0, (cursor.1 + 1).min(cols - 1))
+                } else {
+                    cursor
+                };
+                self.mode = VimMode::Insert;
+                self.inserted.clear();
+                self.insert_position = position;
+                self.insert_line = Some(position);
+                VimAction::Insert(position)
+            }
+            'v' => {
+                self.toggle_visual(VimMode::Visual, cursor, grid_list);
+                VimAction::Handled
+            }
+            _ => VimAction::Ignored,
+        }
+    }
+
+    // Follow the caret of the text tool, to know where it is once insert mode is
+    // left and what was typed
+    fn insert_key(&mut self, event: &KeyEvent, grid_size: (usize, usize)) -> VimAction {
+        let (rows, cols) = grid_size;
+        let (row, col) = self.insert_position;
+        let forward = if col >= cols - 1 {
+            (
+                (row + 1).min(rows - 1),
+                if row >= rows - 1 { col } else { 0 },
+            )
+        } else {
+            (row, col + 1)
+        };
+        let backward = match (row, col) {
+            (0, 0) => (0, 0),
+            (_, 0) => (row - 1, cols - 1),
+            _ => (row, col - 1),
+        };
+        match &event.key {
+            KbKey::Escape => {
+                self.mode = VimMode::Normal;
+                if !self.inserted.is_empty() {
+                    self.last_change = Some(Change::Insert(std::mem::take(&mut self.inserted)));
+                }
+                return VimAction::StopInsert(backward);
+            }
+            KbKey::Character(s) => {
+                if let Some(c) = s.chars().next() {
+                    if self.insert_line.is_none() {
+                        self.insert_line = Some(self.insert_position);
+                    }
+                    self.inserted.push(c);
+                    self.insert_position = forward;
+                }
+            }
+            KbKey::Backspace => {
+                self.inserted.pop();
+                self.insert_position = backward;
+            }
+            KbKey::Enter => {
+                if let Some((line_row, line_col)) = self.insert_line {
+                    if line_row < rows - 1 {
+                        self.insert_position = (line_row + 1, line_col);
+                        self.insert_line = Some(self.insert_position);
+                        self.inserted.push('\n');
+                    }
+                }
+            }
+            KbKey::ArrowLeft | KbKey::ArrowRight | KbKey::ArrowUp | KbKey::ArrowDown => {
+                // The text typed after moving is a new insert, the one before it
+                // can't be repeated in one go
+                self.insert_position = match event.key {
+                    KbKey::ArrowLeft => backward,
+                    KbKey::ArrowRight => forward,
+                    KbKey::ArrowUp => (row.saturating_sub(1), col),
+                    _ => ((row + 1).min(rows - 1), col),
+                };
+                self.insert_line = None;
+                self.inserted.clear();
+            }
+            _ => {}
+        }
+        VimAction::Ignored
+    }
+
+    pub fn leave_visual(&mut self, grid_list: &mut GridList) {
+        if self.is_visual() {
+            self.mode = VimMode::Normal;
+            grid_list.clear_all_highlight();
+        }
+    }
+
+    fn toggle_visual(&mut self, mode: VimMode, cursor: Cell, grid_list: &mut GridList) {
+        if self.mode == mode {
+            self.leave_visual(grid_list);
+            return;
+        }
+        if !self.is_visual() {
+            self.anchor = cursor;
+        }
+        self.mode = mode;
+        self.highlight(cursor, grid_list);
+    }
+
+    // Highlight the visual selection, a block is also a selection the other
+    // commands of the canvas work on
+    pub fn highlight(&self, cursor: Cell, grid_list: &mut GridList) {
+        if !self.is_visual() {
+            return;
+        }
+        let block = self.mode == VimMode::VisualBlock;
+        let (start, end) = corners(self.anchor, cursor, block);
+        let cells: Vec<usize> = area(start, end, block, grid_list.grid_size)
+            .into_iter()
+            .flatten()
+            .collect();
+        grid_list.highlight_cells(&cells);
+        if block {
+            grid_list.current_selection = Some((start, (end.0 + 1, end.1 + 1)));
+        }
+    }
+
+    fn extent(&self, cursor: Cell) -> (usize, isize) {
+        let (start, end) = corners(self.anchor, cursor, self.mode == VimMode::VisualBlock);
+        (end.0 - start.0, end.1 as isize - start.1 as isize)
+    }
+
+    fn yank_rows(&mut self, row: usize, count: usize, grid_list: &mut GridList) {
+        let (rows, cols) = grid_list.grid_size;
+        let area: Vec<Vec<usize>> = (row..(row + count).min(rows))
+            .map(|row| (row * cols..(row + 1) * cols).collect())
+            .collect();
+        let text = area_text(&area, grid_list)
+            .lines()
+            .map(|line| line.trim_end())
+            .collect::<Vec<&str>>()
+            .join("\n");
+        self.register = Some(Register {
+            text,
+            linewise: true,
+        });
+    }
+
+    fn change(
+        &mut self,
+        change: Change,
+        cursor: Cell,
+        editable: bool,
+        grid_list: &mut GridList,
+    ) -> VimAction {
+        if !editable {
+            return VimAction::Handled;
+        }
+        let action = self.apply(&change, cursor, grid_list);
+        self.last_change = Some(change);
+        action
+    }
+
+    fn apply(&mut self, change: &Change, cursor: Cell, grid_list: &mut GridList) -> VimAction {
+        let (rows, cols) = grid_list.grid_size;
+        let (row, col) = cursor;
+        match change {
+            Change::Delete(count) => {
+                let area = vec![(col..(col + count).min(cols))
+                    .map(|col| row * cols + col)
+                    .collect()];
+                self.register = Some(Register {
+                    text: area_text(&area, grid_list),
+                    linewise: false,
+                });
+                self.changed = fill_area(&area, CHAR_SPACE, grid_list);
+                VimAction::Handled
+            }
+            Change::DeleteRows(count) => {
+                self.yank_rows(row, *count, grid_list);
+                let area: Vec<Vec<usize>> = (row..(row + count).min(rows))
+                    .map(|row| (row * cols..(row + 1) * cols).collect())
+                    .collect();
+                self.changed = fill_area(&area, CHAR_SPACE, grid_list);
+                VimAction::Handled
+            }
+            Change::Replace(c, count) => {
+                let last = (col + count).min(cols) - 1;
+                let area = vec![(col..=last).map(|col| row * cols + col).collect()];
+                self.changed = fill_area(&area, *c, grid_list);
+                VimAction::MoveTo((row, last))
+            }
+            Change::Put(count, before) => {
+                let register = match self.register.clone() {
+                    Some(register) => register,
+                    None => return VimAction::Handled,
+                };
+                let lines: Vec<&str> = register.text.lines().collect();
+                let height = lines.len().max(1);
+                let width = lines
+                    .iter()
+                    .map(|line| line.chars().count())
+                    .max()
+                    .unwrap_or(0)
+                    .max(1);
+                let (origin, step) = match (register.linewise, before) {
+                    (true, false) => ((row + 1, 0), (height, 0)),
+                    (true, true) => ((row, 0), (height, 0)),
+                    (false, false) => ((row, col + 1), (0, width)),
+                    (false, true) => ((row, col), (0, width)),
+                };
+                // Spaces let what's underneath show, like pasting on the canvas
+                let mut cells = vec![];
+                for n in 0..*count {
+                    for (i, line) in lines.iter().enumerate() {
+                        for (j, c) in line.chars().enumerate() {
+                            let r = origin.0 + n * step.0 + i;
+                            let c_col = origin.1 + n * step.1 + j;
+                            if r < rows && c_col < cols && !c.is_whitespace() {
+                                cells.push((r * cols + c_col, c));
+                            }
+                        }
+                    }
+                }
+                self.changed = grid_list.write_cells(&cells);
+                let target = if register.linewise {
+                    (origin.0, col)
+                } else {
+                    origin
+                };
+                VimAction::MoveTo((target.0.min(rows - 1), target.1.min(cols - 1)))
+            }
+            Change::Insert(text) => {
+                let mut cells = vec![];
+                let mut last = cursor;
+                for (i, line) in text.split('\n').enumerate() {
+                    for (j, c) in line.chars().enumerate() {
+                        if row + i < rows && col + j < cols {
+                            cells.push(((row + i) * cols + col + j, c));
+                            last = (row + i, col + j);
+                        }
+                    }
+                }
+                self.changed = grid_list.write_cells(&cells);
+                VimAction::MoveTo(last)
+            }
+            Change::DeleteArea(extent, block) => {
+                let area = area(cursor, extent_end(cursor, *extent), *block, (rows, cols));
+                self.register = Some(Register {
+                    text: area_text(&area, grid_list),
+                    linewise: false,
+                });
+                self.changed = fill_area(&area, CHAR_SPACE, grid_list);
+                VimAction::MoveTo(cursor)
+            }
+            Change::ReplaceArea(extent, block, c) => {
+                let area = area(cursor, extent_end(cursor, *extent), *block, (rows, cols));
+                self.changed = fill_area(&area, *c, grid_list);
+                VimAction::MoveTo(cursor)
+            }
+        }
+    }
+}
+
+// Where the motion key moves the cursor: hjkl, 0 and $ for the start and end of
+// the row, ^ for its first character
+fn motion_target(c: char, count: usize, cursor: Cell, grid_list: &mut GridList) -> Option<Cell> {
+    let (rows, cols) = grid_list.grid_size;
+    let (row, col) = cursor;
+    let content: Vec<usize> = (0..cols)
+        .filter(|&col| grid_list.get(row * cols + col).content != CHAR_SPACE)
+        .collect();
+    let target = match c {
+        'h' => (row, col.saturating_sub(count)),
+        'l' => (row, (col + count).min(cols - 1)),
+        'k' => (row.saturating_sub(count), col),
+        'j' => ((row + count).min(rows - 1), col),
+        '0' => (row, 0),
+        '^' => (row, content.first().copied().unwrap_or(0)),
+        '$' => (row, content.last().copied().unwrap_or(0)),
+        _ => return None,
+    };
+    Some(target)
+}
+
+// The first and last cells of the selection between two cells. A block is the
+// rectangle between them, otherwise the selection runs from one to the other
+// like text does.
+fn corners(from: Cell, to: Cell, block: bool) -> (Cell, Cell) {
+    if block {
+        (
+            (from.0.min(to.0), from.1.min(to.1)),
+            (from.0.max(to.0), from.1.max(to.1)),
+        )
+    } else {
+        (from.min(to), from.max(to))
+    }
+}
+
+fn extent_end(start: Cell, extent: (usize, isize)) -> Cell {
+    (
+        start.0 + extent.0,
+        (start.1 as isize + extent.1).max(0) as usize,
+    )
+}
+
+// The cells of the selection, row by row
+fn area(start: Cell, end: Cell, block: bool, grid_size: (usize, usize)) -> Vec<Vec<usize>> {
+    let (rows, cols) = grid_size;
+    (start.0..=end.0.min(rows - 1))
+        .map(|row| {
+            let (first, last) = if block || start.0 == end.0 {
+                (start.1, end.1)
+            } else if row == start.0 {
+                (start.1, cols - 1)
+            } else if row == end.0 {
+                (0, end.1)
+            } else {
+                (0, cols - 1)
+            };
+            (first..=last.min(cols - 1))
+                .map(|col| row * cols + col)
+                .collect()
+        })
+        .collect()
+}
+
+fn area_text(area: &[Vec<usize>], grid_list: &mut GridList) -> String {
+    area.iter()
+        .map(|cells| {
+            cells
+                .iter()
+                .map(|&i| grid_list.get(i).content)
+                .collect::<String>()
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+fn fill_area(area: &[Vec<usize>], content: char, grid_list: &mut GridList) -> Vec<usize> {
+    let cells: Vec<(usize, char)> = area.iter().flatten().map(|&i| (i, content)).collect();
+    grid_list.write_cells(&cells)
+}
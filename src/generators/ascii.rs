@@ -0,0 +1,324 @@
+use std::collections::HashSet;
+
+use crate::{
+    consts::{CHAR_ARROW_DOWN, CHAR_ARROW_LEFT, CHAR_ARROW_RIGHT, CHAR_ARROW_UP, CHAR_SPACE},
+    data::overlap::{self, ARM_DOWN, ARM_LEFT, ARM_RIGHT, ARM_UP},
+    shapes::{block::BlockShape, line::LineShape, rect::RectShape, Shape},
+};
+
+use super::polyline;
+
+type Cell = (usize, usize);
+
+const DIRECTIONS: [(u8, u8, isize, isize); 4] = [
+    (ARM_UP, ARM_DOWN, -1, 0),
+    (ARM_DOWN, ARM_UP, 1, 0),
+    (ARM_LEFT, ARM_RIGHT, 0, -1),
+    (ARM_RIGHT, ARM_LEFT, 0, 1),
+];
+
+// A diagram drawn with box drawing characters or with +-| and arrows, like the
+// ones found in READMEs:
+//
+//   +--------+      ┌────────┐
+//   | Client |----->│ Server │
+//   +--------+      └────────┘
+//
+// Closed rectangles become RectShape, connected lines become LineShape, and
+// whatever text is left becomes labels.
+pub struct AsciiDiagram {
+    rects: Vec<(Cell, Cell)>,
+    // The cells of every line from its start to its end, and if it ends with an
+    // arrow head
+    paths: Vec<(Vec<Cell>, bool)>,
+    labels: Vec<(Cell, String)>,
+    size: (usize, usize),
+}
+
+// Which sides of the cell a character connects to
+fn arms(c: char) -> u8 {
+    if let Some(arms) = overlap::arms(c) {
+        return arms;
+    }
+    match c {
+        '-' | '=' => ARM_LEFT | ARM_RIGHT,
+        '|' => ARM_UP | ARM_DOWN,
+        '+' => ARM_UP | ARM_DOWN | ARM_LEFT | ARM_RIGHT,
+        CHAR_ARROW_RIGHT | '>' => ARM_LEFT,
+        CHAR_ARROW_LEFT | '<' => ARM_RIGHT,
+        CHAR_ARROW_UP | '^' => ARM_DOWN,
+        CHAR_ARROW_DOWN | 'v' => ARM_UP,
+        _ => 0,
+    }
+}
+
+fn has(c: char, wanted: u8) -> bool {
+    arms(c) & wanted == wanted
+}
+
+fn is_arrow(c: char) -> bool {
+    matches!(
+        c,
+        CHAR_ARROW_RIGHT
+            | CHAR_ARROW_LEFT
+            | CHAR_ARROW_UP
+            | CHAR_ARROW_DOWN
+            | '>'
+            | '<'
+            | '^'
+            | 'v'
+    )
+}
+
+// Arrows pointing at a box take the place of its border
+fn is_horizontal_edge(c: char) -> bool {
+    has(c, ARM_LEFT | ARM_RIGHT) || matches!(c, CHAR_ARROW_UP | CHAR_ARROW_DOWN | '^' | 'v')
+}
+
+fn is_vertical_edge(c: char) -> bool {
+    has(c, ARM_UP | ARM_DOWN) || matches!(c, CHAR_ARROW_LEFT | CHAR_ARROW_RIGHT | '<' | '>')
+}
+
+impl AsciiDiagram {
+    pub fn parse(source: &str) -> Option<Self> {
+        let chars: Vec<Vec<char>> = source.lines().map(|line| line.chars().collect()).collect();
+        let width = chars.iter().map(|line| line.len()).max().unwrap_or(0);
+        let mut diagram = AsciiDiagram {
+            rects: vec![],
+            paths: vec![],
+            labels: vec![],
+            size: (chars.len(), width),
+        };
+        let mut borders: HashSet<Cell> = HashSet::new();
+        diagram.find_rects(&chars, &mut borders);
+        let mut used = borders.clone();
+        diagram.find_paths(&chars, &borders, &mut used);
+        // Without a box, a few dashes are more likely text than a diagram
+        if diagram.rects.is_empty() && !diagram.paths.iter().any(|(_, head)| *head) {
+            return None;
+        }
+        diagram.find_labels(&chars, &used);
+        Some(diagram)
+    }
+
+    fn find_rects(&mut self, chars: &[Vec<char>], borders: &mut HashSet<Cell>) {
+        let at = |row: usize, col: usize| -> char {
+            chars
+                .get(row)
+                .and_then(|line| line.get(col))
+                .copied()
+                .unwrap_or(CHAR_SPACE)
+        };
+        let (height, width) = self.size;
+        for top in 0..height {
+            for left in 0..width {
+                if !has(at(top, left), ARM_RIGHT | ARM_DOWN) {
+                    continue;
+                }
+                // Walk along the top edge, every corner on the way may close a box
+                for right in (left + 2)..width {
+                    let c = at(top, right);
+                    if has(c, ARM_LEFT | ARM_DOWN) {
+                        if let Some(bottom) = find_bottom(&at, height, top, left, right) {
+                            self.rects.push(((top, left), (bottom, right)));
+                            for col in left..=right {
+                                borders.insert((top, col));
+                                borders.insert((bottom, col));
+                            }
+                            for row in top..=bottom {
+                                borders.insert((row, left));
+                                borders.insert((row, right));
+                            }
+                            break;
+                        }
+                    }
+                    if !is_horizontal_edge(c) {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    fn find_paths(
+        &mut self,
+        chars: &[Vec<char>],
+        borders: &HashSet<Cell>,
+        used: &mut HashSet<Cell>,
+    ) {
+        let at = |(row, col): Cell| -> char {
+            chars
+                .get(row)
+                .and_then(|line| line.get(col))
+                .copied()
+                .unwrap_or(CHAR_SPACE)
+        };
+        // Cells connected to the given one, both of them have to point at each other
+        let neighbours = |cell: Cell| -> Vec<Cell> {
+            DIRECTIONS
+                .iter()
+                .filter_map(|&(arm, opposite, d_row, d_col)| {
+                    let row = cell.0 as isize + d_row;
+                    let col = cell.1 as isize + d_col;
+                    if row < 0 || col < 0 {
+                        return None;
+                    }
+                    let next = (row as usize, col as usize);
+                    if has(at(cell), arm) && has(at(next), opposite) {
+                        Some(next)
+                    } else {
+                        None
+                    }
+                })
+                .collect()
+        };
+        let is_line = |cell: Cell| arms(at(cell)) != 0 && !borders.contains(&cell);
+        let line_neighbours = |cell: Cell| -> Vec<Cell> {
+            neighbours(cell)
+                .into_iter()
+                .filter(|next| is_line(*next))
+                .collect()
+        };
+        let (height, width) = self.size;
+        for row in 0..height {
+            for col in 0..width {
+                let start = (row, col);
+                // Lines are followed from one of their ends, junctions stop them
+                if !is_line(start) || used.contains(&start) || line_neighbours(start).len() != 1 {
+                    continue;
+                }
+                // Lines stop next to the boxes they connect, the box keeps its border
+                let mut path = vec![start];
+                let mut current = start;
+                loop {
+                    let next: Vec<Cell> = line_neighbours(current)
+                        .into_iter()
+                        .filter(|next| !path.contains(next))
+                        .collect();
+                    if next.len() != 1 {
+                        break;
+                    }
+                    current = next[0];
+                    path.push(current);
+                    if line_neighbours(current).len() > 2 {
+                        break;
+                    }
+                }
+                // A couple of dashes in a sentence is not a line
+                let is_plain_ascii = path
+                    .iter()
+                    .all(|cell| matches!(at(*cell), '-' | '|' | '=' | '+'));
+                if path.len() < 2 || (is_plain_ascii && path.len() < 3) {
+                    continue;
+                }
+                for cell in path.iter() {
+                    if line_neighbours(*cell).len() <= 2 {
+                        used.insert(*cell);
+                    }
+                }
+                if is_arrow(at(path[0])) && !is_arrow(at(path[path.len() - 1])) {
+                    path.reverse();
+                }
+                let head = is_arrow(at(path[path.len() - 1]));
+                self.paths.push((path, head));
+            }
+        }
+        for (path, _) in self.paths.iter() {
+            used.extend(path.iter().copied());
+        }
+    }
+
+    // Words separated by a single space belong to the same label
+    fn find_labels(&mut self, chars: &[Vec<char>], used: &HashSet<Cell>) {
+        for (row, line) in chars.iter().enumerate() {
+            let mut label: Option<(usize, String)> = None;
+            let mut spaces = 0;
+            for (col, c) in line.iter().enumerate() {
+                let is_text = !c.is_whitespace() && !used.contains(&(row, col));
+                if is_text {
+                    match label.as_mut() {
+                        Some((_, text)) if spaces <= 1 => {
+                            text.extend((0..spaces).map(|_| CHAR_SPACE));
+                            text.push(*c);
+                        }
+                        _ => {
+                            if let Some((start, text)) = label.take() {
+                                self.labels.push(((row, start), text));
+                            }
+                            label = Some((col, c.to_string()));
+                        }
+                    }
+                    spaces = 0;
+                } else {
+                    spaces += 1;
+                }
+            }
+            if let Some((start, text)) = label {
+                self.labels.push(((row, start), text));
+            }
+        }
+    }
+
+    pub fn to_shapes(&self, row: usize, col: usize) -> Vec<Box<dyn Shape>> {
+        let mut shapes: Vec<Box<dyn Shape>> = vec![];
+        for &((top, left), (bottom, right)) in self.rects.iter() {
+            shapes.push(Box::new(RectShape::from_points(
+                (row + top, col + left),
+                (row + bottom, col + right),
+            )));
+        }
+        for (path, head) in self.paths.iter() {
+            let points: Vec<Cell> = path.iter().map(|&(r, c)| (row + r, col + c)).collect();
+            let mut segments = polyline(&points);
+            if !head {
+                // Every segment ends with an arrow head, the last one shouldn't
+                let end = points[points.len() - 1];
+                let mut start = points.len() - 2;
+                while start > 0
+                    && ((points[start - 1].0 == end.0 && points[start].0 == end.0)
+                        || (points[start - 1].1 == end.1 && points[start].1 == end.1))
+                {
+                    start -= 1;
+                }
+                segments.pop();
+                segments.push(Box::new(
+                    LineShape::from_points(points[start], end).without_head(),
+                ));
+            }
+            shapes.extend(segments);
+        }
+        for ((top, left), text) in self.labels.iter() {
+            shapes.push(Box::new(BlockShape::new(
+                row + top,
+                col + left,
+                text.to_owned(),
+            )));
+        }
+        shapes
+    }
+}
+
+// Walk down both sides of a box until its bottom corners are found
+fn find_bottom(
+    at: &impl Fn(usize, usize) -> char,
+    height: usize,
+    top: usize,
+    left: usize,
+    right: usize,
+) -> Option<usize> {
+    for bottom in (top + 1)..height {
+        let bottom_left = at(bottom, left);
+        let bottom_right = at(bottom, right);
+        if bottom > top + 1
+            && has(bottom_left, ARM_UP | ARM_RIGHT)
+            && has(bottom_right, ARM_UP | ARM_LEFT)
+            && ((left + 1)..right).all(|col| is_horizontal_edge(at(bottom, col)))
+        {
+            return Some(bottom);
+        }
+        if !(is_vertical_edge(bottom_left) && is_vertical_edge(bottom_right)) {
+            return None;
+        }
+    }
+    None
+}
@@ -0,0 +1,732 @@
+use std::collections::HashMap;
+
+use crate::shapes::{block::BlockShape, group::GroupShape, rect::RectShape, Shape};
+
+use super::polyline;
+
+const NODE_HEIGHT: usize = 3;
+const NODE_PADDING: usize = 2;
+const NODE_SPACING: usize = 4;
+const ORDERING_PASSES: usize = 4;
+
+const MERMAID_KEYWORDS: [&str; 10] = [
+    "graph",
+    "flowchart",
+    "subgraph",
+    "end",
+    "direction",
+    "classDef",
+    "class",
+    "style",
+    "linkStyle",
+    "click",
+];
+const MERMAID_DIRECTIONS: [&str; 5] = ["TD", "TB", "BT", "LR", "RL"];
+
+pub struct Graph {
+    labels: Vec<String>,
+    edges: Vec<(usize, usize)>,
+    ids: HashMap<String, usize>,
+}
+
+impl Graph {
+    fn new() -> Self {
+        Self {
+            labels: vec![],
+            edges: vec![],
+            ids: HashMap::new(),
+        }
+    }
+
+    fn node(&mut self, id: &str) -> usize {
+        if let Some(&index) = self.ids.get(id) {
+            return index;
+        }
+        self.labels.push(id.to_string());
+        self.ids.insert(id.to_string(), self.labels.len() - 1);
+        self.labels.len() - 1
+    }
+
+    fn set_label(&mut self, index: usize, label: &str) {
+        if !label.is_empty() {
+            self.labels[index] = label.to_string();
+        }
+    }
+
+    fn add_edge(&mut self, from: usize, to: usize) {
+        // Self loops can't be drawn with straight lines, skip them
+        if from != to {
+            self.edges.push((from, to));
+        }
+    }
+
+    // Returns None if the source is neither a Graphviz DOT nor a Mermaid flowchart
+    pub fn parse(source: &str) -> Option<Self> {
+        let header = source
+            .lines()
+            .map(|line| line.trim())
+            .find(|line| !line.is_empty() && !line.starts_with("%%") && !line.starts_with("//"))?;
+        let mut words = header.split(|c: char| c.is_whitespace() || c == ';' || c == '{');
+        // Only DOT opens the body with a brace, an anonymous undirected graph
+        // starts with just `graph {`
+        let has_body = header.contains('{');
+        let graph = match (words.next(), words.find(|word| !word.is_empty())) {
+            (Some("digraph"), _) | (Some("strict"), _) => parse_dot(source),
+            (Some("graph"), _) if has_body => parse_dot(source),
+            (Some("graph"), None) | (Some("flowchart"), _) => parse_mermaid(source),
+            (Some("graph"), Some(direction)) if MERMAID_DIRECTIONS.contains(&direction) => {
+                parse_mermaid(source)
+            }
+            (Some("graph"), _) => parse_dot(source),
+            _ => return None,
+        };
+        if graph.labels.is_empty() {
+            None
+        } else {
+            Some(graph)
+        }
+    }
+}
+
+fn peek(chars: &[char], pos: usize) -> Option<char> {
+    chars.get(pos).copied()
+}
+
+fn skip_whitespace(chars: &[char], pos: &mut usize) {
+    while peek(chars, *pos).is_some_and(|c| c.is_whitespace()) {
+        *pos += 1;
+    }
+}
+
+fn skip_while(chars: &[char], pos: &mut usize, pattern: &str) {
+    while peek(chars, *pos).is_some_and(|c| pattern.contains(c)) {
+        *pos += 1;
+    }
+}
+
+fn skip_until(chars: &[char], pos: &mut usize, pattern: &str) {
+    while peek(chars, *pos).is_some_and(|c| !pattern.contains(c)) {
+        *pos += 1;
+    }
+}
+
+fn parse_mermaid(source: &str) -> Graph {
+    let mut graph = Graph::new();
+    for line in source.lines() {
+        let line = line.trim();
+        if line.starts_with("%%") {
+            continue;
+        }
+        for statement in line.split(';') {
+            let statement = statement.trim();
+            let keyword = statement.split_whitespace().next().unwrap_or("");
+            if statement.is_empty() || MERMAID_KEYWORDS.contains(&keyword) {
+                continue;
+            }
+            parse_mermaid_statement(statement, &mut graph);
+        }
+    }
+    graph
+}
+
+// A statement is a chain of nodes connected by links: A[Start] --> B{Check} -->|yes| C
+fn parse_mermaid_statement(statement: &str, graph: &mut Graph) {
+    let chars: Vec<char> = statement.chars().collect();
+    let mut pos = 0;
+    let mut previous = None;
+    loop {
+        skip_whitespace(&chars, &mut pos);
+        let index = match parse_mermaid_node(&chars, &mut pos, graph) {
+            Some(index) => index,
+            None => break,
+        };
+        if let Some(from) = previous {
+            graph.add_edge(from, index);
+        }
+        skip_whitespace(&chars, &mut pos);
+        if !parse_mermaid_link(&chars, &mut pos) {
+            break;
+        }
+        previous = Some(index);
+    }
+}
+
+fn parse_mermaid_node(chars: &[char], pos: &mut usize, graph: &mut Graph) -> Option<usize> {
+    let start = *pos;
+    while peek(chars, *pos).is_some_and(|c| c.is_alphanumeric() || c == '_') {
+        *pos += 1;
+    }
+    if *pos == start {
+        return None;
+    }
+    let id: String = chars[start..*pos].iter().collect();
+    let index = graph.node(&id);
+    if peek(chars, *pos).is_some_and(|c| "[({>".contains(c)) {
+        // Any node shape works, the label is whatever sits inside the brackets
+        skip_while(chars, pos, "[({>/\\");
+        let label_start = *pos;
+        skip_until(chars, pos, "])}");
+        let label: String = chars[label_start..*pos].iter().collect();
+        skip_while(chars, pos, "])}");
+        let label = label
+            .trim()
+            .trim_end_matches(&['/', '\\'][..])
+            .trim_matches('"')
+            .trim();
+        graph.set_label(index, label);
+    }
+    if peek(chars, *pos) == Some(':') {
+        // Class shorthand: A:::someclass
+        skip_while(chars, pos, ":");
+        while peek(chars, *pos).is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '-') {
+            *pos += 1;
+        }
+    }
+    Some(index)
+}
+
+fn parse_mermaid_link(chars: &[char], pos: &mut usize) -> bool {
+    let start = *pos;
+    skip_link(chars, pos);
+    if *pos == start {
+        return false;
+    }
+    let link: String = chars[start..*pos].iter().collect();
+    if link == "--" || link == "==" || link == "-." {
+        // Text in the middle of the link: A -- text --> B
+        skip_until(chars, pos, "-=");
+        skip_link(chars, pos);
+    }
+    skip_whitespace(chars, pos);
+    if peek(chars, *pos) == Some('|') {
+        // Text after the link: A -->|text| B
+        *pos += 1;
+        skip_until(chars, pos, "|");
+        *pos = (*pos + 1).min(chars.len());
+    }
+    true
+}
+
+fn skip_link(chars: &[char], pos: &mut usize) {
+    skip_while(chars, pos, "<-=.>~");
+    // Circle and cross arrow heads: A --o B, A --x B
+    let after_line = *pos > 0 && "-=".contains(chars[*pos - 1]);
+    if after_line
+        && peek(chars, *pos).is_some_and(|c| c == 'o' || c == 'x')
+        && peek(chars, *pos + 1)
+            .filter(|c| !c.is_whitespace())
+            .is_none()
+    {
+        *pos += 1;
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+enum DotToken {
+    Id(String),
+    Edge,
+    Symbol(char),
+}
+
+fn tokenize_dot(source: &str) -> Vec<DotToken> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = vec![];
+    let mut pos = 0;
+    while let Some(c) = peek(&chars, pos) {
+        let next = peek(&chars, pos + 1);
+        if c.is_whitespace() {
+            pos += 1;
+        } else if c == '#' || (c == '/' && next == Some('/')) {
+            skip_until(&chars, &mut pos, "\n");
+        } else if c == '/' && next == Some('*') {
+            pos += 2;
+            while pos < chars.len() && !(chars[pos] == '*' && peek(&chars, pos + 1) == Some('/')) {
+                pos += 1;
+            }
+            pos += 2;
+        } else if c == '"' {
+            pos += 1;
+            let mut value = String::new();
+            while let Some(c) = peek(&chars, pos) {
+                if c == '"' {
+                    break;
+                }
+                if c == '\\' {
+                    pos += 1;
+                    match peek(&chars, pos) {
+                        Some('n') | Some('l') | Some('r') => value.push(' '),
+                        Some(escaped) => value.push(escaped),
+                        None => {}
+                    }
+                } else {
+                    value.push(c);
+                }
+                pos += 1;
+            }
+            pos += 1;
+            tokens.push(DotToken::Id(value));
+        } else if c == '<' {
+            // HTML label, keep the text and drop the tags
+            let mut depth = 0;
+            let mut value = String::new();
+            while let Some(c) = peek(&chars, pos) {
+                pos += 1;
+                match c {
+                    '<' => depth += 1,
+                    '>' => {
+                        depth -= 1;
+                        if depth == 0 {
+                            break;
+                        }
+                    }
+                    _ if depth == 1 => value.push(c),
+                    _ => {}
+                }
+            }
+            tokens.push(DotToken::Id(value.trim().to_string()));
+        } else if c == '-' && (next == Some('>') || next == Some('-')) {
+            pos += 2;
+            tokens.push(DotToken::Edge);
+        } else if c.is_alphanumeric() || c == '_' || c == '.' || c == '-' {
+            let start = pos;
+            pos += 1;
+            while peek(&chars, pos).is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '.') {
+                pos += 1;
+            }
+            tokens.push(DotToken::Id(chars[start..pos].iter().collect()));
+        } else {
+            pos += 1;
+            tokens.push(DotToken::Symbol(c));
+        }
+    }
+    tokens
+}
+
+fn parse_dot(source: &str) -> Graph {
+    let tokens = tokenize_dot(source);
+    let mut graph = Graph::new();
+    // Skip the header: [strict] (graph|digraph) [name] {
+    let mut pos = tokens
+        .iter()
+        .position(|token| *token == DotToken::Symbol('{'))
+        .map_or(tokens.len(), |pos| pos + 1);
+    while let Some(token) = tokens.get(pos) {
+        let next = tokens.get(pos + 1);
+        match token {
+            DotToken::Id(keyword) if keyword == "subgraph" => {
+                pos += 1;
+                if let Some(DotToken::Id(_)) = tokens.get(pos) {
+                    pos += 1;
+                }
+            }
+            DotToken::Id(keyword)
+                if ["graph", "node", "edge"].contains(&keyword.as_str())
+                    && next == Some(&DotToken::Symbol('[')) =>
+            {
+                pos += 1;
+                parse_dot_attributes(&tokens, &mut pos);
+            }
+            DotToken::Id(_) if next == Some(&DotToken::Symbol('=')) => {
+                // Graph attribute, like rankdir=LR
+                pos += 3;
+            }
+            DotToken::Id(_) => parse_dot_statement(&tokens, &mut pos, &mut graph),
+            _ => pos += 1,
+        }
+    }
+    graph
+}
+
+fn parse_dot_statement(tokens: &[DotToken], pos: &mut usize, graph: &mut Graph) {
+    let mut chain = vec![];
+    while let Some(DotToken::Id(id)) = tokens.get(*pos) {
+        chain.push(graph.node(id));
+        *pos += 1;
+        if tokens.get(*pos) == Some(&DotToken::Symbol(':')) {
+            // Ignore node ports: a:port -> b
+            *pos += 2;
+        }
+        if tokens.get(*pos) == Some(&DotToken::Edge) {
+            *pos += 1;
+        } else {
+            break;
+        }
+    }
+    if tokens.get(*pos) == Some(&DotToken::Symbol('[')) {
+        let attributes = parse_dot_attributes(tokens, pos);
+        if let (1, Some(label)) = (chain.len(), attributes.get("label")) {
+            graph.set_label(chain[0], label.trim());
+        }
+    }
+    for pair in chain.windows(2) {
+        graph.add_edge(pair[0], pair[1]);
+    }
+}
+
+fn parse_dot_attributes(tokens: &[DotToken], pos: &mut usize) -> HashMap<String, String> {
+    let mut attributes = HashMap::new();
+    *pos += 1;
+    while let Some(token) = tokens.get(*pos) {
+        if *token == DotToken::Symbol(']') {
+            break;
+        }
+        if let (DotToken::Id(key), Some(DotToken::Symbol('=')), Some(DotToken::Id(value))) =
+            (token, tokens.get(*pos + 1), tokens.get(*pos + 2))
+        {
+            attributes.insert(key.to_owned(), value.to_owned());
+            *pos += 3;
+        } else {
+            *pos += 1;
+        }
+    }
+    *pos += 1;
+    attributes
+}
+
+// A vertex is either a graph node, or a dummy point that an edge passes through
+// when it spans across multiple layers
+struct Vertex {
+    layer: usize,
+    node: Option<usize>,
+    width: usize,
+    x: usize,
+}
+
+// The part of an edge between two adjacent layers
+struct Hop {
+    upper: usize,
+    lower: usize,
+    upper_x: usize,
+    lower_x: usize,
+    track: usize,
+}
+
+pub struct Layout {
+    labels: Vec<String>,
+    boxes: Vec<(usize, usize, usize)>,
+    edges: Vec<Vec<(usize, usize)>>,
+}
+
+impl Layout {
+    // A simple layered (Sugiyama style) layout: break cycles, assign layers,
+    // reduce crossings with the barycenter heuristic, then route every edge
+    // orthogonally through its own track in the gap between two layers.
+    pub fn new(graph: &Graph) -> Self {
+        let node_count = graph.labels.len();
+        let reversed = find_back_edges(node_count, &graph.edges);
+        let dag: Vec<(usize, usize)> = graph
+            .edges
+            .iter()
+            .zip(reversed.iter())
+            .map(|(&(from, to), &reversed)| if reversed { (to, from) } else { (from, to) })
+            .collect();
+        let layers = assign_layers(node_count, &dag);
+
+        let mut vertices: Vec<Vertex> = (0..node_count)
+            .map(|node| Vertex {
+                layer: layers[node],
+                node: Some(node),
+                width: 1,
+                x: 0,
+            })
+            .collect();
+        let mut hops: Vec<Hop> = vec![];
+        let mut chains: Vec<Vec<usize>> = vec![];
+        for &(from, to) in dag.iter() {
+            let mut chain = vec![];
+            let mut upper = from;
+            for layer in (layers[from] + 1)..=layers[to] {
+                let lower = if layer == layers[to] {
+                    to
+                } else {
+                    vertices.push(Vertex {
+                        layer,
+                        node: None,
+                        width: 1,
+                        x: 0,
+                    });
+                    vertices.len() - 1
+                };
+                hops.push(Hop {
+                    upper,
+                    lower,
+                    upper_x: 0,
+                    lower_x: 0,
+                    track: 0,
+                });
+                chain.push(hops.len() - 1);
+                upper = lower;
+            }
+            chains.push(chain);
+        }
+
+        let layer_count = layers.iter().max().map_or(1, |max| max + 1);
+        let rows = order_layers(&vertices, &hops, layer_count);
+        let mut position = vec![0; vertices.len()];
+        for row in rows.iter() {
+            for (i, &vertex) in row.iter().enumerate() {
+                position[vertex] = i;
+            }
+        }
+
+        // Spread the ports of each vertex along its border, sorted by where the
+        // other end of the hop is, so the lines don't cross right at the box
+        let mut port_offsets: Vec<(usize, usize)> = vec![(0, 0); hops.len()];
+        for (v, vertex) in vertices.iter_mut().enumerate() {
+            let mut down: Vec<usize> = (0..hops.len()).filter(|&h| hops[h].upper == v).collect();
+            let mut up: Vec<usize> = (0..hops.len()).filter(|&h| hops[h].lower == v).collect();
+            down.sort_by_key(|&h| position[hops[h].lower]);
+            up.sort_by_key(|&h| position[hops[h].upper]);
+            if let Some(node) = vertex.node {
+                let label_width = graph.labels[node].chars().count() + NODE_PADDING * 2;
+                let ports_width = down.len().max(up.len()) * 2 + 1;
+                vertex.width = label_width.max(ports_width);
+                for (i, &h) in down.iter().enumerate() {
+                    port_offsets[h].0 = (i + 1) * (vertex.width - 1) / (down.len() + 1);
+                }
+                for (i, &h) in up.iter().enumerate() {
+                    port_offsets[h].1 = (i + 1) * (vertex.width - 1) / (up.len() + 1);
+                }
+            }
+        }
+
+        let layer_widths: Vec<usize> = rows
+            .iter()
+            .map(|row| {
+                let widths: usize = row.iter().map(|&v| vertices[v].width).sum();
+                widths + row.len().saturating_sub(1) * NODE_SPACING
+            })
+            .collect();
+        let width = layer_widths.iter().copied().max().unwrap_or(0);
+        for (row, layer_width) in rows.iter().zip(layer_widths.iter()) {
+            let mut x = (width - layer_width) / 2;
+            for &v in row.iter() {
+                vertices[v].x = x;
+                x += vertices[v].width + NODE_SPACING;
+            }
+        }
+
+        let mut tracks = vec![0; layer_count];
+        for (h, hop) in hops.iter_mut().enumerate() {
+            hop.upper_x = vertices[hop.upper].x + port_offsets[h].0;
+            hop.lower_x = vertices[hop.lower].x + port_offsets[h].1;
+            if hop.upper_x != hop.lower_x {
+                let gap = vertices[hop.upper].layer;
+                hop.track = tracks[gap];
+                tracks[gap] += 1;
+            }
+        }
+
+        // Each gap needs a blank row under the upper layer, one row per track,
+        // and a row above the lower layer for the arrow heads
+        let mut layer_top = vec![0; layer_count];
+        for layer in 1..layer_count {
+            layer_top[layer] = layer_top[layer - 1] + NODE_HEIGHT + tracks[layer - 1] + 2;
+        }
+
+        let boxes = vertices
+            .iter()
+            .filter(|vertex| vertex.node.is_some())
+            .map(|vertex| (layer_top[vertex.layer], vertex.x, vertex.width))
+            .collect();
+
+        let edges = chains
+            .iter()
+            .zip(reversed.iter())
+            .map(|(chain, &reversed)| {
+                let mut points = vec![];
+                for (k, &h) in chain.iter().enumerate() {
+                    let hop = &hops[h];
+                    let bottom = layer_top[vertices[hop.upper].layer] + NODE_HEIGHT - 1;
+                    let top = layer_top[vertices[hop.lower].layer];
+                    // Lines start right on the border of the source box, and stop
+                    // with the arrow head just outside the border of the target box
+                    let start_row = if k == 0 && reversed {
+                        bottom + 1
+                    } else {
+                        bottom
+                    };
+                    let end_row = if k == chain.len() - 1 && !reversed {
+                        top - 1
+                    } else {
+                        top
+                    };
+                    points.push((start_row, hop.upper_x));
+                    if hop.upper_x != hop.lower_x {
+                        let track_row = bottom + 2 + hop.track;
+                        points.push((track_row, hop.upper_x));
+                        points.push((track_row, hop.lower_x));
+                    }
+                    points.push((end_row, hop.lower_x));
+                }
+                if reversed {
+                    points.reverse();
+                }
+                points
+            })
+            .collect();
+
+        Self {
+            labels: graph.labels.clone(),
+            boxes,
+            edges,
+        }
+    }
+
+    pub fn to_shapes(&self, row: usize, col: usize) -> Vec<Box<dyn Shape>> {
+        let mut shapes: Vec<Box<dyn Shape>> = vec![];
+        // Every node is a box grouped with its label, so they move together
+        for (label, &(top, left, width)) in self.labels.iter().zip(self.boxes.iter()) {
+            let offset = (width - label.chars().count()) / 2;
+            shapes.push(Box::new(GroupShape::new(vec![
+                Box::new(RectShape::from_points(
+                    (row + top, col + left),
+                    (row + top + NODE_HEIGHT - 1, col + left + width - 1),
+                )),
+                Box::new(BlockShape::new(
+                    row + top + 1,
+                    col + left + offset,
+                    label.to_owned(),
+                )),
+            ])));
+        }
+        for points in self.edges.iter() {
+            let points: Vec<(usize, usize)> =
+                points.iter().map(|&(r, c)| (row + r, col + c)).collect();
+            shapes.extend(polyline(&points));
+        }
+        shapes
+    }
+}
+
+fn find_back_edges(node_count: usize, edges: &[(usize, usize)]) -> Vec<bool> {
+    let mut outgoing: Vec<Vec<usize>> = vec![vec![]; node_count];
+    for (i, &(from, _)) in edges.iter().enumerate() {
+        outgoing[from].push(i);
+    }
+    // 0: not visited, 1: on the stack, 2: done
+    let mut state = vec![0; node_count];
+    let mut reversed = vec![false; edges.len()];
+    for root in 0..node_count {
+        if state[root] != 0 {
+            continue;
+        }
+        let mut stack = vec![(root, 0)];
+        state[root] = 1;
+        while let Some(&mut (node, ref mut next)) = stack.last_mut() {
+            if let Some(&edge) = outgoing[node].get(*next) {
+                *next += 1;
+                let to = edges[edge].1;
+                match state[to] {
+                    0 => {
+                        state[to] = 1;
+                        stack.push((to, 0));
+                    }
+                    1 => reversed[edge] = true,
+                    _ => {}
+                }
+            } else {
+                state[node] = 2;
+                stack.pop();
+            }
+        }
+    }
+    reversed
+}
+
+// Longest path layering, every node sits one layer below its lowest parent
+fn assign_layers(node_count: usize, dag: &[(usize, usize)]) -> Vec<usize> {
+    let mut incoming = vec![0; node_count];
+    for &(_, to) in dag.iter() {
+        incoming[to] += 1;
+    }
+    let mut layers = vec![0; node_count];
+    let mut queue: Vec<usize> = (0..node_count).filter(|&n| incoming[n] == 0).collect();
+    while let Some(node) = queue.pop() {
+        for &(from, to) in dag.iter().filter(|(from, _)| *from == node) {
+            layers[to] = layers[to].max(layers[from] + 1);
+            incoming[to] -= 1;
+            if incoming[to] == 0 {
+                queue.push(to);
+            }
+        }
+    }
+    layers
+}
+
+fn order_layers(vertices: &[Vertex], hops: &[Hop], layer_count: usize) -> Vec<Vec<usize>> {
+    let mut rows: Vec<Vec<usize>> = vec![vec![]; layer_count];
+    for (v, vertex) in vertices.iter().enumerate() {
+        rows[vertex.layer].push(v);
+    }
+    let mut position = vec![0.0; vertices.len()];
+    for row in rows.iter() {
+        for (i, &v) in row.iter().enumerate() {
+            position[v] = i as f64;
+        }
+    }
+
+    for pass in 0..ORDERING_PASSES {
+        let downward = pass % 2 == 0;
+        let layers: Vec<usize> = if downward {
+            (1..layer_count).collect()
+        } else {
+            (0..layer_count.saturating_sub(1)).rev().collect()
+        };
+        for layer in layers {
+            let barycenter = |v: usize| {
+                let neighbors: Vec<f64> = hops
+                    .iter()
+                    .filter_map(|hop| match downward {
+                        true if hop.lower == v => Some(position[hop.upper]),
+                        false if hop.upper == v => Some(position[hop.lower]),
+                        _ => None,
+                    })
+                    .collect();
+                if neighbors.is_empty() {
+                    position[v]
+                } else {
+                    neighbors.iter().sum::<f64>() / neighbors.len() as f64
+                }
+            };
+            let mut keyed: Vec<(f64, usize)> =
+                rows[layer].iter().map(|&v| (barycenter(v), v)).collect();
+            keyed.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+            rows[layer] = keyed.into_iter().map(|(_, v)| v).collect();
+            for (i, &v) in rows[layer].iter().enumerate() {
+                position[v] = i as f64;
+            }
+        }
+    }
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn anonymous_undirected_dot_graph_keeps_its_edges() {
+        let graph = Graph::parse("graph { a -- b }").unwrap();
+        assert_eq!(graph.labels, vec!["a", "b"]);
+        assert_eq!(graph.edges, vec![(0, 1)]);
+    }
+
+    #[test]
+    fn mermaid_graph_with_a_direction_is_not_dot() {
+        let graph = Graph::parse("graph LR\n  A[Start] --> B[End]").unwrap();
+        assert_eq!(graph.labels, vec!["Start", "End"]);
+        assert_eq!(graph.edges, vec![(0, 1)]);
+    }
+
+    #[test]
+    fn labels_are_grouped_with_their_boxes() {
+        let graph = Graph::parse("digraph { a -> b }").unwrap();
+        let shapes = Layout::new(&graph).to_shapes(0, 0);
+        let groups = shapes
+            .iter()
+            .filter_map(|shape| shape.as_any().downcast_ref::<GroupShape>())
+            .count();
+        assert_eq!(groups, 2);
+    }
+}
@@ -0,0 +1,46 @@
+use crate::shapes::{line::LineShape, Shape};
+
+pub mod ascii;
+pub mod flowchart;
+pub mod sequence;
+pub mod table;
+pub mod tree;
+
+// Move the generated diagram so it fits inside the canvas, or give up if the
+// diagram is bigger than the canvas itself
+pub fn fit_in_grid(
+    size: (usize, usize),
+    position: (usize, usize),
+    grid_size: (usize, usize),
+) -> Option<(usize, usize)> {
+    let (height, width) = size;
+    let (rows, cols) = grid_size;
+    if height > rows || width > cols {
+        return None;
+    }
+    Some((position.0.min(rows - height), position.1.min(cols - width)))
+}
+
+// Turn a path of orthogonal turning points into connected line segments. Each
+// segment ends with an arrow head, and the overlap engine turns that arrow into
+// the right corner once the next segment is drawn on top of it.
+pub fn polyline(points: &[(usize, usize)]) -> Vec<Box<dyn Shape>> {
+    let mut turns: Vec<(usize, usize)> = vec![];
+    for &point in points {
+        if turns.last() == Some(&point) {
+            continue;
+        }
+        if turns.len() >= 2 {
+            let a = turns[turns.len() - 2];
+            let b = turns[turns.len() - 1];
+            if (a.0 == b.0 && b.0 == point.0) || (a.1 == b.1 && b.1 == point.1) {
+                turns.pop();
+            }
+        }
+        turns.push(point);
+    }
+    turns
+        .windows(2)
+        .map(|segment| Box::new(LineShape::from_points(segment[0], segment[1])) as Box<dyn Shape>)
+        .collect()
+}
@@ -0,0 +1,214 @@
+use crate::shapes::{block::BlockShape, line::LineShape, rect::RectShape, Shape};
+
+use super::polyline;
+
+const BOX_HEIGHT: usize = 3;
+const BOX_SPACING: usize = 3;
+// Every message takes a label row, an arrow row and an empty row after it
+const MESSAGE_HEIGHT: usize = 3;
+
+struct Message {
+    from: usize,
+    to: usize,
+    label: String,
+}
+
+// A sequence diagram written as one message per line, optionally after a
+// Mermaid `sequenceDiagram` or PlantUML `@startuml` header:
+//
+//   Client -> Server: GET /
+//   Server --> Client: 200 OK
+//
+// Participants are placed in the order they first appear, `participant Name`
+// lines can be used to change that order. Mermaid's `->>` / `-->>` arrows are
+// accepted too. Lines that aren't messages are skipped and kept in `skipped`.
+pub struct SequenceDiagram {
+    participants: Vec<String>,
+    messages: Vec<Message>,
+    // Line numbers, counting from 1
+    pub skipped: Vec<usize>,
+}
+
+impl SequenceDiagram {
+    pub fn parse(source: &str) -> Option<Self> {
+        let is_comment = |line: &str| {
+            line.is_empty()
+                || line.starts_with('#')
+                || line.starts_with("//")
+                || line.starts_with("%%")
+                || line.starts_with('\'')
+        };
+        let mut diagram = SequenceDiagram {
+            participants: vec![],
+            messages: vec![],
+            skipped: vec![],
+        };
+        for (number, line) in source.lines().enumerate() {
+            let line = line.trim();
+            if is_comment(line) || line == "@enduml" {
+                continue;
+            }
+            let is_first = diagram.participants.is_empty() && diagram.skipped.is_empty();
+            if is_first && (line == "sequenceDiagram" || line == "@startuml") {
+                continue;
+            }
+            if let Some(name) = line
+                .strip_prefix("participant ")
+                .or_else(|| line.strip_prefix("actor "))
+            {
+                diagram.participant(name.trim());
+                continue;
+            }
+            let (from, to, label) = match parse_message(line) {
+                Some(message) => message,
+                None => {
+                    diagram.skipped.push(number + 1);
+                    continue;
+                }
+            };
+            let from = diagram.participant(from);
+            let to = diagram.participant(to);
+            diagram.messages.push(Message { from, to, label });
+        }
+        if diagram.messages.is_empty() {
+            return None;
+        }
+        Some(diagram)
+    }
+
+    fn participant(&mut self, name: &str) -> usize {
+        match self.participants.iter().position(|p| p == name) {
+            Some(i) => i,
+            None => {
+                self.participants.push(name.to_string());
+                self.participants.len() - 1
+            }
+        }
+    }
+
+    fn box_width(&self, participant: usize) -> usize {
+        self.participants[participant].chars().count() + 4
+    }
+
+    // Column of each lifeline, far enough from each other for the boxes and
+    // for every message label to fit between them
+    fn lifelines(&self) -> Vec<usize> {
+        let mut lifelines: Vec<usize> = vec![];
+        for i in 0..self.participants.len() {
+            let half = self.box_width(i) / 2;
+            let mut col = half;
+            if i > 0 {
+                let previous = lifelines[i - 1];
+                let previous_right = previous + self.box_width(i - 1) - self.box_width(i - 1) / 2;
+                col = col.max(previous_right + BOX_SPACING + half);
+            }
+            for message in self.messages.iter() {
+                let label_width = message.label.chars().count();
+                if message.from == message.to && message.from + 1 == i {
+                    // Self messages loop to the right, with the label after the loop
+                    col = col.max(lifelines[i - 1] + label_width + 7);
+                }
+                let (left, right) = (message.from.min(message.to), message.from.max(message.to));
+                if right == i && left < i {
+                    col = col.max(lifelines[left] + label_width + 4);
+                }
+            }
+            lifelines.push(col);
+        }
+        lifelines
+    }
+
+    pub fn to_shapes(&self, row: usize, col: usize) -> Vec<Box<dyn Shape>> {
+        let mut shapes: Vec<Box<dyn Shape>> = vec![];
+        let lifelines: Vec<usize> = self.lifelines().iter().map(|c| col + c).collect();
+        let bottom = row + BOX_HEIGHT + self.messages.len() * MESSAGE_HEIGHT;
+
+        for (i, name) in self.participants.iter().enumerate() {
+            let width = self.box_width(i);
+            let left = lifelines[i] - width / 2;
+            shapes.push(Box::new(RectShape::from_points(
+                (row, left),
+                (row + BOX_HEIGHT - 1, left + width - 1),
+            )));
+            shapes.push(Box::new(BlockShape::new(
+                row + 1,
+                left + 2,
+                name.to_owned(),
+            )));
+        }
+
+        // Lifelines start on the bottom border of the boxes, so they get a ┬ joint
+        for &lifeline in lifelines.iter() {
+            shapes.push(Box::new(
+                LineShape::from_points((row + BOX_HEIGHT - 1, lifeline), (bottom, lifeline))
+                    .without_head(),
+            ));
+        }
+
+        for (i, message) in self.messages.iter().enumerate() {
+            let label_row = row + BOX_HEIGHT + 1 + i * MESSAGE_HEIGHT;
+            let from = lifelines[message.from];
+            let to = lifelines[message.to];
+            let label_width = message.label.chars().count();
+            if from == to {
+                shapes.extend(polyline(&[
+                    (label_row, from),
+                    (label_row, from + 3),
+                    (label_row + 1, from + 3),
+                    (label_row + 1, from + 1),
+                ]));
+                if label_width > 0 {
+                    shapes.push(Box::new(BlockShape::new(
+                        label_row,
+                        from + 5,
+                        message.label.to_owned(),
+                    )));
+                }
+                continue;
+            }
+            // Stop the arrow right before the target lifeline
+            let head = if to > from { to - 1 } else { to + 1 };
+            shapes.push(Box::new(LineShape::from_points(
+                (label_row + 1, from),
+                (label_row + 1, head),
+            )));
+            if label_width > 0 {
+                let (left, right) = (from.min(to), from.max(to));
+                let label_col = left + (right - left + 1).saturating_sub(label_width) / 2;
+                shapes.push(Box::new(BlockShape::new(
+                    label_row,
+                    label_col,
+                    message.label.to_owned(),
+                )));
+            }
+        }
+        shapes
+    }
+}
+
+// Split `From -> To: label` into its parts, `To <- From` is also accepted
+fn parse_message(line: &str) -> Option<(&str, &str, String)> {
+    let (names, label) = match line.split_once(':') {
+        Some((names, label)) => (names, label.trim().to_string()),
+        None => (line, String::new()),
+    };
+    let (from, to) = if let Some((from, to)) = names.split_once("->") {
+        (from.trim_end_matches('-'), to.trim_start_matches('>'))
+    } else if let Some((to, from)) = names.split_once("<-") {
+        (from.trim_start_matches('-'), to)
+    } else {
+        return None;
+    };
+    let (from, to) = (from.trim(), to.trim());
+    // Names are words, so the arrows of a drawing like |A|--->|B| aren't messages
+    let is_name = |name: &str| {
+        !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == ' ' || "_.-()'\"".contains(c))
+    };
+    if !is_name(from) || !is_name(to) {
+        return None;
+    }
+    Some((from, to, label))
+}
@@ -0,0 +1,63 @@
+// Split CSV or TSV text into table cells. Tab separated content is what
+// spreadsheets put on the clipboard, anything else is read as CSV with
+// support for quoted fields.
+pub fn parse_delimited(content: &str) -> Option<Vec<Vec<String>>> {
+    let content = content.trim_end_matches(['\n', '\r']);
+    if content.trim().is_empty() {
+        return None;
+    }
+    let rows = if content.contains('\t') {
+        content
+            .lines()
+            .map(|line| {
+                line.split('\t')
+                    .map(|cell| cell.trim().to_string())
+                    .collect()
+            })
+            .collect()
+    } else {
+        parse_csv(content)
+    };
+    Some(rows)
+}
+
+fn parse_csv(content: &str) -> Vec<Vec<String>> {
+    let mut rows = vec![];
+    let mut row = vec![];
+    let mut cell = String::new();
+    let mut in_quotes = false;
+    let mut chars = content.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    cell.push('"');
+                }
+                '"' => in_quotes = false,
+                // Table cells only hold a single line of text
+                '\r' => {}
+                '\n' => cell.push(' '),
+                _ => cell.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' if cell.trim().is_empty() => {
+                cell.clear();
+                in_quotes = true;
+            }
+            ',' => row.push(std::mem::take(&mut cell).trim().to_string()),
+            '\r' => {}
+            '\n' => {
+                row.push(std::mem::take(&mut cell).trim().to_string());
+                rows.push(std::mem::take(&mut row));
+            }
+            _ => cell.push(c),
+        }
+    }
+    row.push(cell.trim().to_string());
+    rows.push(row);
+    rows
+}
@@ -0,0 +1,134 @@
+use std::{fs, io, path::Path};
+
+use crate::consts::{CHAR_CORNER_BL_L, CHAR_HOR_L, CHAR_VER_L, CHAR_VER_RIGHT_L};
+
+// Stop reading a directory after this many entries, so picking a huge folder
+// doesn't freeze the app
+const MAX_DIRECTORY_ENTRIES: usize = 1000;
+
+pub struct TreeNode {
+    pub label: String,
+    pub children: Vec<TreeNode>,
+}
+
+impl TreeNode {
+    fn new(label: String) -> Self {
+        Self {
+            label,
+            children: vec![],
+        }
+    }
+}
+
+// Build a tree from an indented outline, list markers like `-` or `*` are
+// dropped. Every line without indentation starts a new tree.
+pub fn parse_outline(source: &str) -> Vec<TreeNode> {
+    let mut roots: Vec<TreeNode> = vec![];
+    // Indentation and child index of every node on the path to the last line
+    let mut path: Vec<(usize, usize)> = vec![];
+    for line in source.lines() {
+        let label = line.trim();
+        if label.is_empty() {
+            continue;
+        }
+        let indent = line
+            .chars()
+            .take_while(|c| c.is_whitespace())
+            .map(|c| if c == '\t' { 4 } else { 1 })
+            .sum::<usize>();
+        let label = label
+            .strip_prefix("- ")
+            .or_else(|| label.strip_prefix("* "))
+            .or_else(|| label.strip_prefix("+ "))
+            .unwrap_or(label)
+            .to_string();
+
+        while path
+            .last()
+            .is_some_and(|&(parent_indent, _)| parent_indent >= indent)
+        {
+            path.pop();
+        }
+        let siblings = match path.first() {
+            None => &mut roots,
+            Some(&(_, root)) => {
+                let mut node = &mut roots[root];
+                for &(_, child) in path[1..].iter() {
+                    node = &mut node.children[child];
+                }
+                &mut node.children
+            }
+        };
+        siblings.push(TreeNode::new(label));
+        path.push((indent, siblings.len() - 1));
+    }
+    roots
+}
+
+pub fn read_directory(path: &Path) -> io::Result<TreeNode> {
+    let label = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string_lossy().to_string());
+    let mut root = TreeNode::new(format!("{}/", label));
+    let mut entries = 0;
+    read_children(path, &mut root, &mut entries)?;
+    Ok(root)
+}
+
+fn read_children(path: &Path, node: &mut TreeNode, entries: &mut usize) -> io::Result<()> {
+    let mut children: Vec<(String, bool)> = fs::read_dir(path)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| {
+            let is_dir = entry.file_type().is_ok_and(|t| t.is_dir());
+            (entry.file_name().to_string_lossy().to_string(), is_dir)
+        })
+        .filter(|(name, _)| !name.starts_with('.'))
+        .collect();
+    children.sort();
+    for (name, is_dir) in children {
+        if *entries >= MAX_DIRECTORY_ENTRIES {
+            break;
+        }
+        *entries += 1;
+        if is_dir {
+            let mut child = TreeNode::new(format!("{}/", name));
+            // Folders we can't read are still listed, just without their content
+            let _ = read_children(&path.join(&name), &mut child, entries);
+            node.children.push(child);
+        } else {
+            node.children.push(TreeNode::new(name));
+        }
+    }
+    Ok(())
+}
+
+pub fn render(roots: &[TreeNode]) -> String {
+    let mut lines = vec![];
+    for root in roots {
+        lines.push(root.label.to_owned());
+        render_children(&root.children, "", &mut lines);
+    }
+    lines.join("\n")
+}
+
+fn render_children(children: &[TreeNode], prefix: &str, lines: &mut Vec<String>) {
+    for (i, child) in children.iter().enumerate() {
+        let is_last = i == children.len() - 1;
+        let branch = if is_last {
+            CHAR_CORNER_BL_L
+        } else {
+            CHAR_VER_RIGHT_L
+        };
+        lines.push(format!(
+            "{}{}{}{} {}",
+            prefix, branch, CHAR_HOR_L, CHAR_HOR_L, child.label
+        ));
+        let child_prefix = if is_last {
+            format!("{}    ", prefix)
+        } else {
+            format!("{}{}   ", prefix, CHAR_VER_L)
+        };
+        render_children(&child.children, &child_prefix, lines);
+    }
+}
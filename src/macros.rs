@@ -10,6 +10,10 @@ macro_rules! impl_shape_for {
                 self
             }
 
+            fn duplicate(&self) -> Box<dyn Shape> {
+                Box::new(self.clone())
+            }
+
             fn get_points(&self) -> (Point, Point) {
                 (
                     Point::from((self.start.0 as f64, self.start.1 as f64)),
@@ -11,13 +11,18 @@ use std::collections::HashMap;
 mod macros;
 mod consts;
 mod data;
+mod generators;
+mod menu;
 mod shapes;
 mod tools;
 mod widgets;
 
-use crate::data::WindowData;
+use crate::data::{history::HISTORY_MANAGER, recent::load_recent_files, WindowData};
 use data::ApplicationState;
-use widgets::{grid::CanvasGrid, layout::StackLayout, toolbar::ToolBarWidget};
+use widgets::{
+    grid::CanvasGrid, layers::LayersPanel, layout::StackLayout, library::LibraryPanel,
+    palette::CommandPalette, status::StatusMessage, toolbar::ToolBarWidget,
+};
 
 struct MainWindow {
     content: WidgetPod<ApplicationState, Box<dyn Widget<ApplicationState>>>,
@@ -53,6 +58,10 @@ impl Widget<ApplicationState> for MainWindow {
             let mut ui = StackLayout::new();
             ui.add_child(Scroll::new(CanvasGrid::new(ctx)));
             ui.add_child(ToolBarWidget::new());
+            ui.add_child(LibraryPanel::new());
+            ui.add_child(LayersPanel::new());
+            ui.add_child(StatusMessage::new());
+            ui.add_child(CommandPalette::new());
             self.content = WidgetPod::new(Box::new(ui));
         }
         if let LifeCycle::HotChanged(is_hot) = event {
@@ -132,7 +141,14 @@ impl AppDelegate<ApplicationState> for Delegate {
         _env: &Env,
     ) -> Handled {
         if cmd.is(NEW_FILE) {
-            let new_win = WindowDesc::new(MainWindow::new()).title("ASCII-d");
+            // The history is shared, the edits of the other document can't be
+            // undone on the new one
+            unsafe {
+                HISTORY_MANAGER.reset();
+            }
+            let new_win = WindowDesc::new(MainWindow::new())
+                .title("ASCII-d")
+                .menu(menu::build);
             ctx.new_window(new_win);
             return Handled::Yes;
         }
@@ -145,7 +161,8 @@ fn main() -> Result<(), PlatformError> {
     // Follow the above PR for transparent title bar status
     let window = WindowDesc::new(MainWindow::new())
         .title("ASCII-d")
-        .window_size((640.0, 480.0));
+        .window_size((640.0, 480.0))
+        .menu(menu::build);
     let win_id = window.id.to_owned();
     let app = AppLauncher::with_window(window);
     let mut window_map = HashMap::new();
@@ -155,6 +172,7 @@ fn main() -> Result<(), PlatformError> {
     })
     .launch(ApplicationState {
         windows: window_map,
+        recent_files: load_recent_files(),
     })?;
     Ok(())
 }
@@ -0,0 +1,144 @@
+use std::path::PathBuf;
+
+use druid::{commands::OPEN_FILE, Env, FileInfo, Menu, MenuItem, WindowId};
+
+use crate::{
+    consts::{ACTION_COMMAND, COPY_AS_COMMAND, EXPORT_AS_COMMAND},
+    data::{export::ExportFormat, keymap::Action, ApplicationState, WindowData},
+    tools::DrawingTools,
+};
+
+// The menu items send the same commands as the shortcuts and the toolbar. The
+// shortcuts aren't set as menu hotkeys, the keymap keeps handling the keys so
+// the custom bindings and the typing modes work the same with a menu.
+pub fn build(
+    window: Option<WindowId>,
+    data: &ApplicationState,
+    _env: &Env,
+) -> Menu<ApplicationState> {
+    #[cfg(target_os = "macos")]
+    let menu = Menu::empty().entry(druid::platform_menus::mac::application::default());
+    #[cfg(not(target_os = "macos"))]
+    let menu = Menu::empty();
+    menu.entry(file_menu(window, data))
+        .entry(edit_menu())
+        .entry(view_menu(window))
+        .entry(tools_menu(window))
+        // The recent files are listed in the File menu
+        .rebuild_on(|old_data, data, _env| old_data.recent_files != data.recent_files)
+}
+
+fn action_item(action: Action) -> MenuItem<ApplicationState> {
+    MenuItem::new(action.label()).command(ACTION_COMMAND.with(action))
+}
+
+fn window_data(window: Option<WindowId>, data: &ApplicationState) -> Option<&WindowData> {
+    window.and_then(|id| data.windows.get(&id))
+}
+
+fn file_menu(window: Option<WindowId>, data: &ApplicationState) -> Menu<ApplicationState> {
+    let mut recent = Menu::new("Open Recent");
+    for file in data.recent_files.iter() {
+        recent = recent.entry(
+            MenuItem::new(file.to_owned()).command(OPEN_FILE.with(FileInfo {
+                path: PathBuf::from(file),
+                format: None,
+            })),
+        );
+    }
+    if data.recent_files.is_empty() {
+        recent = recent.entry(MenuItem::new("No Recent Files").enabled(false));
+    }
+
+    let mut export = Menu::new("Export…");
+    for format in ExportFormat::all() {
+        let title = format.to_string();
+        export = export.entry(MenuItem::new(title).command(EXPORT_AS_COMMAND.with(format)));
+    }
+    export = export.separator().entry(
+        MenuItem::new("Trim Trailing Whitespace")
+            .command(ACTION_COMMAND.with(Action::ToggleTrimWhitespace))
+            .selected_if(move |data, _env| {
+                window_data(window, data).is_some_and(|win_data| win_data.trim_whitespace)
+            }),
+    );
+
+    Menu::new("File")
+        .entry(action_item(Action::NewFile))
+        .entry(action_item(Action::Open))
+        .entry(recent)
+        .separator()
+        .entry(action_item(Action::Save))
+        .entry(action_item(Action::SaveAs))
+        .entry(export)
+}
+
+fn edit_menu() -> Menu<ApplicationState> {
+    let mut copy_as = Menu::new("Copy as…");
+    for format in ExportFormat::all() {
+        let title = format.to_string();
+        copy_as = copy_as.entry(MenuItem::new(title).command(COPY_AS_COMMAND.with(format)));
+    }
+
+    Menu::new("Edit")
+        .entry(action_item(Action::Undo))
+        .entry(action_item(Action::Redo))
+        .separator()
+        .entry(action_item(Action::Cut))
+        .entry(action_item(Action::Copy))
+        .entry(copy_as)
+        .entry(action_item(Action::Paste))
+        .entry(action_item(Action::PasteAsShapes))
+        .separator()
+        .entry(action_item(Action::SelectAll))
+}
+
+fn view_menu(window: Option<WindowId>) -> Menu<ApplicationState> {
+    Menu::new("View")
+        .entry(action_item(Action::ZoomIn))
+        .entry(action_item(Action::ZoomOut))
+        .entry(action_item(Action::ResetZoom))
+        .separator()
+        .entry(action_item(Action::ToggleTheme))
+        .entry(
+            MenuItem::new("Grid Lines")
+                .command(ACTION_COMMAND.with(Action::ToggleGrid))
+                .selected_if(move |data, _env| {
+                    window_data(window, data).is_some_and(|win_data| win_data.show_grid)
+                }),
+        )
+        .entry(
+            MenuItem::new("Library")
+                .command(ACTION_COMMAND.with(Action::ToggleLibrary))
+                .selected_if(move |data, _env| {
+                    window_data(window, data).is_some_and(|win_data| win_data.show_library)
+                }),
+        )
+        .entry(
+            MenuItem::new("Layers")
+                .command(ACTION_COMMAND.with(Action::ToggleLayers))
+                .selected_if(move |data, _env| {
+                    window_data(window, data).is_some_and(|win_data| win_data.show_layers)
+                }),
+        )
+}
+
+fn tools_menu(window: Option<WindowId>) -> Menu<ApplicationState> {
+    let tools = [
+        (Action::SelectTool, DrawingTools::Select),
+        (Action::LineTool, DrawingTools::Line),
+        (Action::RectTool, DrawingTools::Rect),
+        (Action::TextTool, DrawingTools::Text),
+        (Action::EraserTool, DrawingTools::Eraser),
+        (Action::TableTool, DrawingTools::Table),
+        (Action::FreehandTool, DrawingTools::Freehand),
+        (Action::FillTool, DrawingTools::Fill),
+    ];
+    let mut menu = Menu::new("Tools");
+    for (action, tool) in tools {
+        menu = menu.entry(action_item(action).selected_if(move |data, _env| {
+            window_data(window, data).is_some_and(|win_data| win_data.mode == tool)
+        }));
+    }
+    menu
+}
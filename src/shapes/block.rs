@@ -5,36 +5,86 @@ use crate::{
         CHAR_CORNER_BL_L, CHAR_CORNER_BR_L, CHAR_CORNER_TL_L, CHAR_CORNER_TR_L, CHAR_HOR_L,
         CHAR_VER_L,
     },
-    data::grid_list::GridList,
+    data::{
+        grid_list::GridList,
+        transform::{Area, Transform},
+    },
 };
 
-use super::ShapeRender;
+use super::{offset_point, transform_rect, ShapeRender, ShapeTransform};
 
+#[derive(Clone)]
 pub struct BlockShape {
     pub start: (usize, usize),
     pub end: (usize, usize),
     pub preview: bool,
     pub content: String,
+    // The spaces of an opaque block cover what's under it
+    pub opaque: bool,
 }
 
 impl BlockShape {
     pub fn new(row: usize, col: usize, content: String) -> Self {
-        Self {
+        let mut block = Self {
             start: (row, col),
             end: (row, col),
             preview: true,
             content: content,
-        }
+            opaque: false,
+        };
+        block.set_position(row, col);
+        block
+    }
+
+    pub fn with_opaque(mut self, opaque: bool) -> Self {
+        self.opaque = opaque;
+        self
+    }
+
+    pub fn set_position(&mut self, row: usize, col: usize) {
+        let rows = self.content.lines().count().max(1);
+        let cols = self
+            .content
+            .lines()
+            .map(|line| line.chars().count())
+            .max()
+            .unwrap_or(0)
+            .max(1);
+        self.start = (row, col);
+        self.end = (row + rows - 1, col + cols - 1);
     }
 }
 
 impl_shape_for!(BlockShape);
 
+impl ShapeTransform for BlockShape {
+    fn translate(&mut self, offset: (isize, isize)) {
+        let (row, col) = offset_point(self.start, offset);
+        self.set_position(row, col);
+    }
+
+    fn transform(&mut self, transform: Transform, area: Area) {
+        let (start, _) = transform_rect(self.start, self.end, transform, area);
+        let lines: Vec<Vec<char>> = self
+            .content
+            .lines()
+            .map(|line| line.chars().collect())
+            .collect();
+        self.content = transform
+            .apply(&lines)
+            .into_iter()
+            .map(|line| line.into_iter().collect::<String>())
+            .collect::<Vec<String>>()
+            .join("\n");
+        self.set_position(start.0, start.1);
+    }
+}
+
 impl ShapeRender for BlockShape {
     fn draw(&mut self, grid_buffer: &mut GridList) {
         let (row, col) = self.start;
         grid_buffer.discard_all();
-        grid_buffer.put_preview_at(&self.content, row, col)
+        grid_buffer.put_preview_at(&self.content, row, col, self.opaque)
     }
 
     fn commit(&mut self, grid_buffer: &mut GridList) {
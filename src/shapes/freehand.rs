@@ -0,0 +1,217 @@
+use std::collections::HashMap;
+
+use druid::Point;
+
+use crate::{
+    consts::{
+        CHAR_CORNER_BL_L, CHAR_CORNER_BR_L, CHAR_CORNER_TL_L, CHAR_CORNER_TR_L, CHAR_DIAGONAL_DOWN,
+        CHAR_DIAGONAL_UP, CHAR_HOR_L, CHAR_VER_L,
+    },
+    data::{
+        grid_list::GridList,
+        overlap,
+        transform::{Area, Transform},
+    },
+};
+
+use super::{offset_point, ShapeRender, ShapeTransform};
+
+// A path following the mouse cell by cell. Without a brush, every cell gets the
+// line glyph that connects it to the cells before and after it.
+#[derive(Clone)]
+pub struct FreehandShape {
+    pub start: (usize, usize),
+    pub end: (usize, usize),
+    pub points: Vec<(usize, usize)>,
+    pub brush: Option<char>,
+    pub preview: bool,
+}
+
+impl FreehandShape {
+    pub fn new(row: usize, col: usize, brush: Option<char>) -> Self {
+        Self {
+            start: (row, col),
+            end: (row, col),
+            points: vec![(row, col)],
+            brush,
+            preview: true,
+        }
+    }
+
+    pub fn from_points(points: Vec<(usize, usize)>, brush: Option<char>) -> Self {
+        let mut freehand = Self {
+            start: (0, 0),
+            end: (0, 0),
+            points,
+            brush,
+            preview: true,
+        };
+        freehand.update_bounds();
+        freehand
+    }
+
+    // Walk from the last point to the given cell, one cell at a time. Moves close to
+    // 45 degrees become diagonals, anything else is straightened into horizontal
+    // and vertical runs.
+    pub fn extend_to(&mut self, to: (usize, usize)) {
+        let (mut row, mut col) = match self.points.last() {
+            Some(&last) => last,
+            None => {
+                self.push_point(to);
+                return;
+            }
+        };
+        let d_row = to.0 as isize - row as isize;
+        let d_col = to.1 as isize - col as isize;
+        let diagonal = d_row != 0
+            && d_col != 0
+            && d_row.abs() * 2 > d_col.abs()
+            && d_col.abs() * 2 > d_row.abs();
+        while (row, col) != to {
+            let rows_left = to.0 as isize - row as isize;
+            let cols_left = to.1 as isize - col as isize;
+            if diagonal && rows_left != 0 && cols_left != 0 {
+                row = step(row, rows_left);
+                col = step(col, cols_left);
+            } else if cols_left.abs() >= rows_left.abs() {
+                col = step(col, cols_left);
+            } else {
+                row = step(row, rows_left);
+            }
+            self.push_point((row, col));
+        }
+    }
+
+    fn push_point(&mut self, point: (usize, usize)) {
+        let len = self.points.len();
+        if len >= 2 && self.points[len - 2] == point {
+            // Going back over the last cell takes it off the path
+            self.points.pop();
+        } else {
+            self.points.push(point);
+        }
+        self.update_bounds();
+    }
+
+    fn update_bounds(&mut self) {
+        if let Some(&first) = self.points.first() {
+            let (mut top_left, mut bottom_right) = (first, first);
+            for &(row, col) in self.points.iter() {
+                top_left = (top_left.0.min(row), top_left.1.min(col));
+                bottom_right = (bottom_right.0.max(row), bottom_right.1.max(col));
+            }
+            self.start = top_left;
+            self.end = bottom_right;
+        }
+    }
+
+    fn glyphs(&self) -> HashMap<(usize, usize), char> {
+        let mut glyphs: HashMap<(usize, usize), char> = HashMap::new();
+        if let Some(brush) = self.brush {
+            for &point in self.points.iter() {
+                glyphs.insert(point, brush);
+            }
+            return glyphs;
+        }
+        if self.points.len() < 2 {
+            return glyphs;
+        }
+        for (i, &point) in self.points.iter().enumerate() {
+            let mut links = vec![];
+            if i > 0 {
+                links.push(offset(point, self.points[i - 1]));
+            }
+            if i + 1 < self.points.len() {
+                links.push(offset(point, self.points[i + 1]));
+            }
+            let glyph = glyph_for(&links);
+            // The path may cross itself, merge it like any other overlapping line
+            let glyph = match glyphs.get(&point) {
+                Some(&current) => overlap::calculate_cell_content(None, current, glyph),
+                None => glyph,
+            };
+            glyphs.insert(point, glyph);
+        }
+        glyphs
+    }
+}
+
+fn step(value: usize, delta: isize) -> usize {
+    if delta > 0 {
+        value + 1
+    } else {
+        value - 1
+    }
+}
+
+fn offset(from: (usize, usize), to: (usize, usize)) -> (isize, isize) {
+    (
+        to.0 as isize - from.0 as isize,
+        to.1 as isize - from.1 as isize,
+    )
+}
+
+fn glyph_for(links: &[(isize, isize)]) -> char {
+    for &link in links {
+        match link {
+            (-1, 1) | (1, -1) => return CHAR_DIAGONAL_UP,
+            (-1, -1) | (1, 1) => return CHAR_DIAGONAL_DOWN,
+            _ => {}
+        }
+    }
+    let left = links.contains(&(0, -1));
+    let right = links.contains(&(0, 1));
+    let up = links.contains(&(-1, 0));
+    let down = links.contains(&(1, 0));
+    match (left, right, up, down) {
+        (false, true, false, true) => CHAR_CORNER_TL_L,
+        (true, false, false, true) => CHAR_CORNER_TR_L,
+        (false, true, true, false) => CHAR_CORNER_BL_L,
+        (true, false, true, false) => CHAR_CORNER_BR_L,
+        (false, false, _, _) => CHAR_VER_L,
+        _ => CHAR_HOR_L,
+    }
+}
+
+impl_shape_for!(FreehandShape);
+
+impl ShapeTransform for FreehandShape {
+    fn translate(&mut self, offset: (isize, isize)) {
+        for point in self.points.iter_mut() {
+            *point = offset_point(*point, offset);
+        }
+        self.update_bounds();
+    }
+
+    fn transform(&mut self, transform: Transform, area: Area) {
+        for point in self.points.iter_mut() {
+            *point = transform.map_point(*point, area);
+        }
+        self.update_bounds();
+    }
+}
+
+impl ShapeRender for FreehandShape {
+    fn draw(&mut self, grid_buffer: &mut GridList) {
+        let (rows, cols) = grid_buffer.grid_size;
+        grid_buffer.discard_all();
+        for ((row, col), glyph) in self.glyphs() {
+            if row < rows && col < cols {
+                grid_buffer.get(row * cols + col).set_preview(glyph);
+            }
+        }
+    }
+
+    fn commit(&mut self, grid_buffer: &mut GridList) {
+        grid_buffer.commit_all();
+        self.preview = false;
+    }
+
+    fn is_preview(&self) -> bool {
+        self.preview
+    }
+
+    fn is_manual_commit(&self) -> bool {
+        false
+    }
+}
@@ -0,0 +1,104 @@
+use druid::Point;
+
+use crate::data::{
+    grid_list::GridList,
+    transform::{Area, Transform},
+};
+
+use super::{bounds, ShapeRender, ShapeTransform};
+
+// Several shapes moved, copied and selected as one
+pub struct GroupShape {
+    pub start: (usize, usize),
+    pub end: (usize, usize),
+    pub preview: bool,
+    pub shapes: Vec<Box<dyn Shape>>,
+}
+
+impl GroupShape {
+    pub fn new(shapes: Vec<Box<dyn Shape>>) -> Self {
+        let mut group = Self {
+            start: (0, 0),
+            end: (0, 0),
+            preview: false,
+            shapes,
+        };
+        group.update_bounds();
+        group
+    }
+
+    fn update_bounds(&mut self) {
+        let mut shapes = self.shapes.iter().map(|shape| bounds(shape.as_ref()));
+        if let Some((mut start, mut end)) = shapes.next() {
+            for (top_left, bottom_right) in shapes {
+                start = (start.0.min(top_left.0), start.1.min(top_left.1));
+                end = (end.0.max(bottom_right.0), end.1.max(bottom_right.1));
+            }
+            self.start = start;
+            self.end = end;
+        }
+    }
+}
+
+impl Clone for GroupShape {
+    fn clone(&self) -> Self {
+        Self {
+            start: self.start,
+            end: self.end,
+            preview: self.preview,
+            shapes: self.shapes.iter().map(|shape| shape.duplicate()).collect(),
+        }
+    }
+}
+
+// Every shape clears the previews before drawing, so they are drawn one by one
+// and merged together afterward
+pub fn draw_merged<'a>(
+    shapes: impl Iterator<Item = &'a mut Box<dyn Shape>>,
+    grid_buffer: &mut GridList,
+) {
+    grid_buffer.discard_all();
+    let mut previews = vec![];
+    for shape in shapes {
+        shape.draw(grid_buffer);
+        previews.extend(grid_buffer.take_previews());
+    }
+    grid_buffer.put_previews(previews);
+}
+
+impl_shape_for!(GroupShape);
+
+impl ShapeTransform for GroupShape {
+    fn translate(&mut self, offset: (isize, isize)) {
+        for shape in self.shapes.iter_mut() {
+            shape.translate(offset);
+        }
+        self.update_bounds();
+    }
+
+    fn transform(&mut self, transform: Transform, area: Area) {
+        for shape in self.shapes.iter_mut() {
+            shape.transform(transform, area);
+        }
+        self.update_bounds();
+    }
+}
+
+impl ShapeRender for GroupShape {
+    fn draw(&mut self, grid_buffer: &mut GridList) {
+        draw_merged(self.shapes.iter_mut(), grid_buffer);
+    }
+
+    fn commit(&mut self, grid_buffer: &mut GridList) {
+        grid_buffer.commit_all();
+        self.preview = false;
+    }
+
+    fn is_preview(&self) -> bool {
+        self.preview
+    }
+
+    fn is_manual_commit(&self) -> bool {
+        false
+    }
+}
@@ -4,10 +4,13 @@ use crate::{
     consts::{
         CHAR_ARROW_DOWN, CHAR_ARROW_LEFT, CHAR_ARROW_RIGHT, CHAR_ARROW_UP, CHAR_HOR_L, CHAR_VER_L,
     },
-    data::grid_list::GridList,
+    data::{
+        grid_list::GridList,
+        transform::{Area, Transform},
+    },
 };
 
-use super::ShapeRender;
+use super::{offset_point, ShapeRender, ShapeTransform};
 
 #[derive(Clone, Copy, Eq, PartialEq, Debug)]
 pub enum LineDirection {
@@ -17,15 +20,44 @@ pub enum LineDirection {
     DownToUp,
 }
 
+#[derive(Clone)]
 pub struct LineShape {
     pub start: (usize, usize),
     pub end: (usize, usize),
     pub direction: LineDirection,
     pub preview: bool,
+    pub head: bool,
+}
+
+fn direction_between(start: (usize, usize), end: (usize, usize)) -> LineDirection {
+    if start.0 == end.0 {
+        if end.1 >= start.1 {
+            LineDirection::LeftToRight
+        } else {
+            LineDirection::RightToLeft
+        }
+    } else if end.0 > start.0 {
+        LineDirection::UpToDown
+    } else {
+        LineDirection::DownToUp
+    }
 }
 
 impl_shape_for!(LineShape);
 
+impl ShapeTransform for LineShape {
+    fn translate(&mut self, offset: (isize, isize)) {
+        self.start = offset_point(self.start, offset);
+        self.end = offset_point(self.end, offset);
+    }
+
+    fn transform(&mut self, transform: Transform, area: Area) {
+        self.start = transform.map_point(self.start, area);
+        self.end = transform.map_point(self.end, area);
+        self.direction = direction_between(self.start, self.end);
+    }
+}
+
 impl ShapeRender for LineShape {
     fn draw(&mut self, grid_buffer: &mut GridList) {
         let (_rows, cols) = grid_buffer.grid_size;
@@ -44,7 +76,9 @@ impl ShapeRender for LineShape {
                     grid_buffer.get(i).set_preview(CHAR_VER_L);
                 }
                 let head_i = to_row * cols + from_col;
-                grid_buffer.get(head_i).set_preview(CHAR_ARROW_DOWN);
+                if self.head {
+                    grid_buffer.get(head_i).set_preview(CHAR_ARROW_DOWN);
+                }
             }
             LineDirection::DownToUp => {
                 for row in to_row..=from_row {
@@ -52,7 +86,9 @@ impl ShapeRender for LineShape {
                     grid_buffer.get(i).set_preview(CHAR_VER_L);
                 }
                 let head_i = to_row * cols + from_col;
-                grid_buffer.get(head_i).set_preview(CHAR_ARROW_UP);
+                if self.head {
+                    grid_buffer.get(head_i).set_preview(CHAR_ARROW_UP);
+                }
             }
             LineDirection::LeftToRight => {
                 for col in from_col..=to_col {
@@ -60,7 +96,9 @@ impl ShapeRender for LineShape {
                     grid_buffer.get(i).set_preview(CHAR_HOR_L);
                 }
                 let head_i = from_row * cols + to_col;
-                grid_buffer.get(head_i).set_preview(CHAR_ARROW_RIGHT);
+                if self.head {
+                    grid_buffer.get(head_i).set_preview(CHAR_ARROW_RIGHT);
+                }
             }
             LineDirection::RightToLeft => {
                 for col in to_col..=from_col {
@@ -68,7 +106,9 @@ impl ShapeRender for LineShape {
                     grid_buffer.get(i).set_preview(CHAR_HOR_L);
                 }
                 let head_i = from_row * cols + to_col;
-                grid_buffer.get(head_i).set_preview(CHAR_ARROW_LEFT);
+                if self.head {
+                    grid_buffer.get(head_i).set_preview(CHAR_ARROW_LEFT);
+                }
             }
         }
     }
@@ -94,6 +134,22 @@ impl LineShape {
             end: (row, col),
             direction: LineDirection::RightToLeft,
             preview: true,
+            head: true,
+        }
+    }
+
+    pub fn from_points(start: (usize, usize), end: (usize, usize)) -> Self {
+        Self {
+            start,
+            end,
+            direction: direction_between(start, end),
+            preview: true,
+            head: true,
         }
     }
+
+    pub fn without_head(mut self) -> Self {
+        self.head = false;
+        self
+    }
 }
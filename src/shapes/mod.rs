@@ -2,11 +2,17 @@ use std::any::Any;
 
 use druid::Point;
 
-use crate::data::grid_list::GridList;
+use crate::data::{
+    grid_list::GridList,
+    transform::{Area, Transform},
+};
 
 pub mod block;
+pub mod freehand;
+pub mod group;
 pub mod line;
 pub mod rect;
+pub mod table;
 
 pub trait ShapeRender {
     fn draw(&mut self, grid_buffer: &mut GridList);
@@ -15,8 +21,47 @@ pub trait ShapeRender {
     fn is_manual_commit(&self) -> bool;
 }
 
-pub trait Shape: ShapeRender {
+pub trait ShapeTransform {
+    // Move the shape by (rows, cols), it never goes past the top or left edge
+    fn translate(&mut self, offset: (isize, isize));
+    // Flip or rotate the shape within the given area
+    fn transform(&mut self, transform: Transform, area: Area);
+}
+
+pub trait Shape: ShapeRender + ShapeTransform {
     fn get_points(&self) -> (Point, Point);
+    fn duplicate(&self) -> Box<dyn Shape>;
     fn as_any(&self) -> &dyn Any;
     fn as_any_mut(&mut self) -> &mut dyn Any;
 }
+
+pub fn offset_point(point: (usize, usize), offset: (isize, isize)) -> (usize, usize) {
+    (
+        (point.0 as isize + offset.0).max(0) as usize,
+        (point.1 as isize + offset.1).max(0) as usize,
+    )
+}
+
+// Top left and bottom right corners of a rectangle once it's transformed
+pub fn transform_rect(
+    start: (usize, usize),
+    end: (usize, usize),
+    transform: Transform,
+    area: Area,
+) -> ((usize, usize), (usize, usize)) {
+    let start = transform.map_point(start, area);
+    let end = transform.map_point(end, area);
+    (
+        (start.0.min(end.0), start.1.min(end.1)),
+        (start.0.max(end.0), start.1.max(end.1)),
+    )
+}
+
+// Top left and bottom right cells of the area a shape covers
+pub fn bounds(shape: &dyn Shape) -> ((usize, usize), (usize, usize)) {
+    let (start, end) = shape.get_points();
+    (
+        (start.x.min(end.x) as usize, start.y.min(end.y) as usize),
+        (start.x.max(end.x) as usize, start.y.max(end.y) as usize),
+    )
+}
@@ -5,11 +5,15 @@ use crate::{
         CHAR_CORNER_BL_L, CHAR_CORNER_BR_L, CHAR_CORNER_TL_L, CHAR_CORNER_TR_L, CHAR_HOR_L,
         CHAR_VER_L,
     },
-    data::grid_list::GridList,
+    data::{
+        grid_list::GridList,
+        transform::{Area, Transform},
+    },
 };
 
-use super::ShapeRender;
+use super::{offset_point, ShapeRender, ShapeTransform};
 
+#[derive(Clone)]
 pub struct RectShape {
     pub start: (usize, usize),
     pub end: (usize, usize),
@@ -24,10 +28,30 @@ impl RectShape {
             preview: true,
         }
     }
+
+    pub fn from_points(start: (usize, usize), end: (usize, usize)) -> Self {
+        Self {
+            start,
+            end,
+            preview: true,
+        }
+    }
 }
 
 impl_shape_for!(RectShape);
 
+impl ShapeTransform for RectShape {
+    fn translate(&mut self, offset: (isize, isize)) {
+        self.start = offset_point(self.start, offset);
+        self.end = offset_point(self.end, offset);
+    }
+
+    fn transform(&mut self, transform: Transform, area: Area) {
+        self.start = transform.map_point(self.start, area);
+        self.end = transform.map_point(self.end, area);
+    }
+}
+
 impl ShapeRender for RectShape {
     fn draw(&mut self, grid_buffer: &mut GridList) {
         let (_rows, cols) = grid_buffer.grid_size;
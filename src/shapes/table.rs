@@ -0,0 +1,271 @@
+use std::collections::HashMap;
+
+use druid::Point;
+
+use crate::{
+    consts::{
+        CHAR_CORNER_BL_L, CHAR_CORNER_BR_L, CHAR_CORNER_TL_L, CHAR_CORNER_TR_L, CHAR_HOR_L,
+        CHAR_VER_L,
+    },
+    data::{
+        grid_list::GridList,
+        overlap,
+        transform::{Area, Transform},
+    },
+};
+
+use super::{offset_point, transform_rect, ShapeRender, ShapeTransform};
+
+// Empty cells are this wide, including one space of padding on each side
+pub const TABLE_MIN_CELL_WIDTH: usize = 3;
+
+#[derive(Clone)]
+pub struct TableShape {
+    pub start: (usize, usize),
+    pub end: (usize, usize),
+    pub preview: bool,
+    pub cells: Vec<Vec<String>>,
+}
+
+impl TableShape {
+    pub fn new(row: usize, col: usize, rows: usize, cols: usize) -> Self {
+        Self::from_rows(
+            row,
+            col,
+            vec![vec![String::new(); cols.max(1)]; rows.max(1)],
+        )
+    }
+
+    pub fn from_rows(row: usize, col: usize, cells: Vec<Vec<String>>) -> Self {
+        let cols = cells
+            .iter()
+            .map(|cells| cells.len())
+            .max()
+            .unwrap_or(0)
+            .max(1);
+        let mut cells: Vec<Vec<String>> = cells
+            .into_iter()
+            .map(|mut cells| {
+                cells.resize(cols, String::new());
+                cells
+            })
+            .collect();
+        if cells.is_empty() {
+            cells.push(vec![String::new(); cols]);
+        }
+        let mut table = Self {
+            start: (row, col),
+            end: (row, col),
+            preview: true,
+            cells,
+        };
+        table.update_bounds();
+        table
+    }
+
+    pub fn rows(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cells[0].len()
+    }
+
+    pub fn resize(&mut self, rows: usize, cols: usize) {
+        let cols = cols.max(1);
+        self.cells.resize(rows.max(1), vec![String::new(); cols]);
+        for cells in self.cells.iter_mut() {
+            cells.resize(cols, String::new());
+        }
+        self.update_bounds();
+    }
+
+    pub fn insert_row(&mut self, at: usize) {
+        let at = at.min(self.rows());
+        self.cells.insert(at, vec![String::new(); self.cols()]);
+        self.update_bounds();
+    }
+
+    pub fn remove_row(&mut self, at: usize) {
+        if self.rows() > 1 && at < self.rows() {
+            self.cells.remove(at);
+            self.update_bounds();
+        }
+    }
+
+    pub fn insert_column(&mut self, at: usize) {
+        let at = at.min(self.cols());
+        for cells in self.cells.iter_mut() {
+            cells.insert(at, String::new());
+        }
+        self.update_bounds();
+    }
+
+    pub fn remove_column(&mut self, at: usize) {
+        if self.cols() > 1 && at < self.cols() {
+            for cells in self.cells.iter_mut() {
+                cells.remove(at);
+            }
+            self.update_bounds();
+        }
+    }
+
+    // Each column is as wide as its longest text, plus the padding
+    pub fn column_widths(&self) -> Vec<usize> {
+        (0..self.cols())
+            .map(|col| {
+                self.cells
+                    .iter()
+                    .map(|cells| cells[col].chars().count() + 2)
+                    .max()
+                    .unwrap_or(0)
+                    .max(TABLE_MIN_CELL_WIDTH)
+            })
+            .collect()
+    }
+
+    // The grid column of every vertical border, from left to right
+    fn borders(&self) -> Vec<usize> {
+        let mut borders = vec![self.start.1];
+        for width in self.column_widths() {
+            borders.push(borders[borders.len() - 1] + width + 1);
+        }
+        borders
+    }
+
+    pub fn update_bounds(&mut self) {
+        let borders = self.borders();
+        self.end = (self.start.0 + self.rows() * 2, borders[borders.len() - 1]);
+    }
+
+    // Find the table cell under a grid position. Borders belong to the cell on
+    // their right or below them.
+    pub fn cell_at(&self, row: usize, col: usize) -> Option<(usize, usize)> {
+        let (from_row, from_col) = self.start;
+        let (to_row, to_col) = self.end;
+        if row < from_row || row > to_row || col < from_col || col > to_col {
+            return None;
+        }
+        let table_row = ((row - from_row) / 2).min(self.rows() - 1);
+        let borders = self.borders();
+        let table_col = borders[1..]
+            .iter()
+            .position(|border| col < *border)
+            .unwrap_or(self.cols() - 1);
+        Some((table_row, table_col))
+    }
+
+    // Grid position right after the text of a cell, where the next character goes
+    pub fn text_cursor(&self, table_row: usize, table_col: usize) -> (usize, usize) {
+        let borders = self.borders();
+        (
+            self.start.0 + table_row * 2 + 1,
+            borders[table_col] + 2 + self.cells[table_row][table_col].chars().count(),
+        )
+    }
+}
+
+impl_shape_for!(TableShape);
+
+impl ShapeTransform for TableShape {
+    fn translate(&mut self, offset: (isize, isize)) {
+        self.start = offset_point(self.start, offset);
+        self.end = offset_point(self.end, offset);
+    }
+
+    // The text of the cells can't be mirrored, the rows and columns are moved
+    // around instead
+    fn transform(&mut self, transform: Transform, area: Area) {
+        let (start, _) = transform_rect(self.start, self.end, transform, area);
+        let (rows, cols) = (self.rows(), self.cols());
+        self.cells = match transform {
+            Transform::FlipHorizontal => self
+                .cells
+                .iter()
+                .map(|cells| cells.iter().rev().cloned().collect())
+                .collect(),
+            Transform::FlipVertical => self.cells.iter().rev().cloned().collect(),
+            Transform::RotateClockwise => (0..cols)
+                .map(|col| {
+                    (0..rows)
+                        .rev()
+                        .map(|row| self.cells[row][col].to_owned())
+                        .collect()
+                })
+                .collect(),
+            Transform::RotateCounterClockwise => (0..cols)
+                .rev()
+                .map(|col| {
+                    (0..rows)
+                        .map(|row| self.cells[row][col].to_owned())
+                        .collect()
+                })
+                .collect(),
+        };
+        self.start = start;
+        self.update_bounds();
+    }
+}
+
+// Every cell is drawn as its own rectangle, the overlap engine then joins the
+// shared corners into ┬ ┴ ├ ┤ ┼
+fn stamp(glyphs: &mut HashMap<(usize, usize), char>, position: (usize, usize), incoming: char) {
+    let current = glyphs.get(&position).copied().unwrap_or(' ');
+    glyphs.insert(
+        position,
+        overlap::calculate_cell_content(None, current, incoming),
+    );
+}
+
+impl ShapeRender for TableShape {
+    fn draw(&mut self, grid_buffer: &mut GridList) {
+        let (rows, cols) = grid_buffer.grid_size;
+        let borders = self.borders();
+        let mut glyphs: HashMap<(usize, usize), char> = HashMap::new();
+
+        grid_buffer.discard_all();
+
+        for table_row in 0..self.rows() {
+            let top = self.start.0 + table_row * 2;
+            let bottom = top + 2;
+            for table_col in 0..self.cols() {
+                let left = borders[table_col];
+                let right = borders[table_col + 1];
+                stamp(&mut glyphs, (top, left), CHAR_CORNER_TL_L);
+                stamp(&mut glyphs, (top, right), CHAR_CORNER_TR_L);
+                stamp(&mut glyphs, (bottom, left), CHAR_CORNER_BL_L);
+                stamp(&mut glyphs, (bottom, right), CHAR_CORNER_BR_L);
+                for col in (left + 1)..right {
+                    stamp(&mut glyphs, (top, col), CHAR_HOR_L);
+                    stamp(&mut glyphs, (bottom, col), CHAR_HOR_L);
+                }
+                for row in (top + 1)..bottom {
+                    stamp(&mut glyphs, (row, left), CHAR_VER_L);
+                    stamp(&mut glyphs, (row, right), CHAR_VER_L);
+                }
+                for (i, c) in self.cells[table_row][table_col].chars().enumerate() {
+                    glyphs.insert((top + 1, left + 2 + i), c);
+                }
+            }
+        }
+
+        for ((row, col), c) in glyphs {
+            if row < rows && col < cols && !c.is_whitespace() {
+                grid_buffer.get(row * cols + col).set_preview(c);
+            }
+        }
+    }
+
+    fn commit(&mut self, grid_buffer: &mut GridList) {
+        grid_buffer.commit_all();
+        self.preview = false;
+    }
+
+    fn is_preview(&self) -> bool {
+        self.preview
+    }
+
+    fn is_manual_commit(&self) -> bool {
+        false
+    }
+}
@@ -1,4 +1,4 @@
-use druid::EventCtx;
+use druid::{EventCtx, KbKey};
 
 use crate::{
     consts::CHAR_SPACE,
@@ -11,74 +11,168 @@ use crate::{
 
 use super::ToolControl;
 
+const BRUSH_SIZES: [usize; 3] = [1, 3, 5];
+
 pub struct EraserTool {
     version: Version,
-    last_cursor_position: Option<usize>,
+    // Index in BRUSH_SIZES, changed with the [ and ] keys
+    brush_size: usize,
+    // Holding shift while erasing removes whole shapes instead of single cells
+    is_erasing_shapes: bool,
+    // Holding alt while erasing clears the whole rectangle being dragged
+    area_start: Option<(usize, usize)>,
 }
 
 impl EraserTool {
     pub fn new() -> Self {
         Self {
             version: Version::new(),
-            last_cursor_position: None,
+            brush_size: 0,
+            is_erasing_shapes: false,
+            area_start: None,
+        }
+    }
+
+    fn erase_shape_at(
+        &mut self,
+        event: &druid::MouseEvent,
+        shape_list: &mut ShapeList,
+        grid_list: &mut GridList,
+    ) {
+        let (row, col) = mouse_cell(event, grid_list);
+        if let Some(index) = shape_list.find_shape_at_cell(row, col, grid_list) {
+            shape_list.remove_shape(index, grid_list);
+        }
+    }
+
+    fn erase_brush_at(&mut self, event: &druid::MouseEvent, grid_list: &mut GridList) {
+        let (row, col) = mouse_cell(event, grid_list);
+        let (rows, cols) = grid_list.grid_size;
+        let radius = BRUSH_SIZES[self.brush_size] / 2;
+        for row in row.saturating_sub(radius)..=(row + radius).min(rows - 1) {
+            for col in col.saturating_sub(radius)..=(col + radius).min(cols - 1) {
+                let i = row * cols + col;
+                let cell = grid_list.get(i);
+                if !cell.content.eq(&CHAR_SPACE) {
+                    self.version.push(i, cell.content, CHAR_SPACE);
+                    cell.clear();
+                }
+            }
         }
     }
 }
 
+fn mouse_cell(event: &druid::MouseEvent, grid_list: &GridList) -> (usize, usize) {
+    let (cell_width, cell_height) = grid_list.cell_size;
+    let (rows, cols) = grid_list.grid_size;
+    let row = ((event.pos.y.max(0.0) / cell_height) as usize).min(rows - 1);
+    let col = ((event.pos.x.max(0.0) / cell_width) as usize).min(cols - 1);
+    (row, col)
+}
+
 impl ToolControl for EraserTool {
     fn start(
         &mut self,
         _ctx: &mut EventCtx,
-        _event: &druid::MouseEvent,
-        _shape_list: &mut ShapeList,
-        _grid_list: &mut GridList,
+        event: &druid::MouseEvent,
+        shape_list: &mut ShapeList,
+        grid_list: &mut GridList,
     ) {
-        self.last_cursor_position = None;
+        self.is_erasing_shapes = event.mods.shift();
+        self.area_start = None;
+        if self.is_erasing_shapes {
+            unsafe {
+                HISTORY_MANAGER.begin_group();
+            }
+            self.erase_shape_at(event, shape_list, grid_list);
+        } else if event.mods.alt() {
+            let cell = mouse_cell(event, grid_list);
+            self.area_start = Some(cell);
+            grid_list.highlight_area(cell, cell);
+        } else {
+            self.erase_brush_at(event, grid_list);
+        }
     }
 
     fn draw(
         &mut self,
         _ctx: &mut EventCtx,
         event: &druid::MouseEvent,
-        _shape_list: &mut ShapeList,
+        shape_list: &mut ShapeList,
         grid_list: &mut GridList,
     ) {
-        let (cell_width, cell_height) = grid_list.cell_size;
-        let row = (event.pos.y / cell_height) as usize;
-        let col = (event.pos.x / cell_width) as usize;
-        let (_rows, cols) = grid_list.grid_size;
-        let i = row * cols + col;
-        if let Some(last_cursor_pos) = self.last_cursor_position {
-            let from_content = grid_list.get(i).read_content();
-            if i == last_cursor_pos || from_content.eq(&CHAR_SPACE) {
-                return;
-            }
+        if self.is_erasing_shapes {
+            self.erase_shape_at(event, shape_list, grid_list);
+        } else if let Some(area_start) = self.area_start {
+            grid_list.highlight_area(area_start, mouse_cell(event, grid_list));
+        } else {
+            self.erase_brush_at(event, grid_list);
         }
-        self.last_cursor_position = Some(i);
-        let cell = grid_list.get(i);
-        self.version.push(i, cell.content, CHAR_SPACE);
-        cell.clear();
     }
 
     fn input(
         &mut self,
-        _ctx: &mut EventCtx,
-        _event: &druid::KeyEvent,
+        ctx: &mut EventCtx,
+        event: &druid::KeyEvent,
         _shape_list: &mut ShapeList,
         _grid_list: &mut GridList,
     ) {
+        match &event.key {
+            KbKey::Character(c) if c == "[" => {
+                self.brush_size = self.brush_size.saturating_sub(1);
+                ctx.request_paint();
+            }
+            KbKey::Character(c) if c == "]" => {
+                self.brush_size = (self.brush_size + 1).min(BRUSH_SIZES.len() - 1);
+                ctx.request_paint();
+            }
+            _ => {}
+        }
     }
 
     fn end(
         &mut self,
         _ctx: &mut EventCtx,
-        _event: &druid::MouseEvent,
-        _shape_list: &mut ShapeList,
-        _grid_list: &mut GridList,
+        event: &druid::MouseEvent,
+        shape_list: &mut ShapeList,
+        grid_list: &mut GridList,
     ) {
+        if self.is_erasing_shapes {
+            self.is_erasing_shapes = false;
+            unsafe {
+                HISTORY_MANAGER.end_group();
+            }
+            return;
+        }
+        // The shapes that lost some of their cells are left as text
         unsafe {
-            HISTORY_MANAGER.save_version(self.version.clone());
-            self.version.clear();
+            HISTORY_MANAGER.begin_group();
         }
+        let cells = match self.area_start.take() {
+            Some(area_start) => {
+                let area_end = mouse_cell(event, grid_list);
+                grid_list.clear_all_highlight();
+                grid_list.erase_area(
+                    (area_start.0.min(area_end.0), area_start.1.min(area_end.1)),
+                    (area_start.0.max(area_end.0), area_start.1.max(area_end.1)),
+                )
+            }
+            None => {
+                let cells = self.version.cells();
+                unsafe {
+                    HISTORY_MANAGER.save_version(self.version.clone());
+                }
+                self.version.clear();
+                cells
+            }
+        };
+        shape_list.detach_shapes(&cells, grid_list);
+        unsafe {
+            HISTORY_MANAGER.end_group();
+        }
+    }
+
+    fn brush_size(&self) -> usize {
+        BRUSH_SIZES[self.brush_size]
     }
 }
@@ -0,0 +1,97 @@
+use druid::{EventCtx, KbKey};
+
+use crate::data::{grid_list::GridList, history::HISTORY_MANAGER, overlap, shape_list::ShapeList};
+
+use super::ToolControl;
+
+// Clicking outside of every shape would otherwise fill the whole canvas
+const MAX_FILL_AREA: usize = 20000;
+
+pub struct FillTool {
+    // Typing a character changes what the region is filled with
+    content: char,
+    // Tab switches between 4 and 8-connected regions
+    diagonal: bool,
+}
+
+impl FillTool {
+    pub fn new() -> Self {
+        Self {
+            content: '░',
+            diagonal: false,
+        }
+    }
+}
+
+impl ToolControl for FillTool {
+    fn start(
+        &mut self,
+        _ctx: &mut EventCtx,
+        event: &druid::MouseEvent,
+        shape_list: &mut ShapeList,
+        grid_list: &mut GridList,
+    ) {
+        let (cell_width, cell_height) = grid_list.cell_size;
+        let (rows, cols) = grid_list.grid_size;
+        let row = (event.pos.y / cell_height) as usize;
+        let col = (event.pos.x / cell_width) as usize;
+        if row >= rows || col >= cols {
+            return;
+        }
+        // Borders are never filled, only what's inside them
+        if overlap::is_line_glyph(grid_list.get(row * cols + col).content) {
+            return;
+        }
+        unsafe {
+            HISTORY_MANAGER.begin_group();
+        }
+        match grid_list.flood_fill((row, col), self.content, self.diagonal, MAX_FILL_AREA) {
+            Some(cells) => shape_list.detach_shapes(&cells, grid_list),
+            None => println!("Fill area is too large or already filled"),
+        }
+        unsafe {
+            HISTORY_MANAGER.end_group();
+        }
+    }
+
+    fn draw(
+        &mut self,
+        _ctx: &mut EventCtx,
+        _event: &druid::MouseEvent,
+        _shape_list: &mut ShapeList,
+        _grid_list: &mut GridList,
+    ) {
+    }
+
+    fn input(
+        &mut self,
+        _ctx: &mut EventCtx,
+        event: &druid::KeyEvent,
+        _shape_list: &mut ShapeList,
+        _grid_list: &mut GridList,
+    ) {
+        if event.mods.ctrl() || event.mods.meta() {
+            return;
+        }
+        match &event.key {
+            KbKey::Character(c) => {
+                if let Some(c) = c.chars().next() {
+                    self.content = c;
+                }
+            }
+            KbKey::Tab => {
+                self.diagonal = !self.diagonal;
+            }
+            _ => {}
+        }
+    }
+
+    fn end(
+        &mut self,
+        _ctx: &mut EventCtx,
+        _event: &druid::MouseEvent,
+        _shape_list: &mut ShapeList,
+        _grid_list: &mut GridList,
+    ) {
+    }
+}
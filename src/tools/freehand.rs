@@ -0,0 +1,85 @@
+use druid::{EventCtx, KbKey};
+
+use crate::{
+    data::{grid_list::GridList, shape_list::ShapeList},
+    shapes::freehand::FreehandShape,
+};
+
+use super::ToolControl;
+
+pub struct FreehandTool {
+    // Typing a character turns the tool into a brush that stamps it on every cell,
+    // backspace goes back to drawing lines
+    brush: Option<char>,
+}
+
+impl FreehandTool {
+    pub fn new() -> Self {
+        Self { brush: None }
+    }
+}
+
+impl ToolControl for FreehandTool {
+    fn start(
+        &mut self,
+        _ctx: &mut EventCtx,
+        event: &druid::MouseEvent,
+        shape_list: &mut ShapeList,
+        grid_list: &mut GridList,
+    ) {
+        let (cell_width, cell_height) = grid_list.cell_size;
+        let mouse_row = (event.pos.y / cell_height) as usize;
+        let mouse_col = (event.pos.x / cell_width) as usize;
+        shape_list.add_shape(Box::new(FreehandShape::new(
+            mouse_row, mouse_col, self.brush,
+        )));
+    }
+
+    fn draw(
+        &mut self,
+        _ctx: &mut EventCtx,
+        event: &druid::MouseEvent,
+        shape_list: &mut ShapeList,
+        grid_list: &mut GridList,
+    ) {
+        if let Some(shape) = shape_list.data.last_mut() {
+            if let Some(path) = shape.as_any_mut().downcast_mut::<FreehandShape>() {
+                let (cell_width, cell_height) = grid_list.cell_size;
+                let (rows, cols) = grid_list.grid_size;
+                let mouse_row = ((event.pos.y.max(0.0) / cell_height) as usize).min(rows - 1);
+                let mouse_col = ((event.pos.x.max(0.0) / cell_width) as usize).min(cols - 1);
+                path.extend_to((mouse_row, mouse_col));
+            }
+        }
+    }
+
+    fn input(
+        &mut self,
+        _ctx: &mut EventCtx,
+        event: &druid::KeyEvent,
+        _shape_list: &mut ShapeList,
+        _grid_list: &mut GridList,
+    ) {
+        if event.mods.ctrl() || event.mods.meta() {
+            return;
+        }
+        match &event.key {
+            KbKey::Character(c) => {
+                self.brush = c.chars().next();
+            }
+            KbKey::Backspace | KbKey::Delete => {
+                self.brush = None;
+            }
+            _ => {}
+        }
+    }
+
+    fn end(
+        &mut self,
+        _ctx: &mut EventCtx,
+        _event: &druid::MouseEvent,
+        _shape_list: &mut ShapeList,
+        _grid_list: &mut GridList,
+    ) {
+    }
+}
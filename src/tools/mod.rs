@@ -10,12 +10,18 @@ use crate::{
     tools::{line::LineTool, text::TextTool},
 };
 
-use self::{eraser::EraserTool, rect::RectTool, select::SelectTool};
+use self::{
+    eraser::EraserTool, fill::FillTool, freehand::FreehandTool, rect::RectTool, select::SelectTool,
+    table::TableTool,
+};
 
 pub mod eraser;
+pub mod fill;
+pub mod freehand;
 pub mod line;
 pub mod rect;
 pub mod select;
+pub mod table;
 pub mod text;
 
 #[derive(Clone, Copy, PartialEq, Data, Debug)]
@@ -25,6 +31,9 @@ pub enum DrawingTools {
     Text = 2,
     Eraser = 3,
     Rect = 4,
+    Table = 5,
+    Freehand = 6,
+    Fill = 7,
 }
 
 impl Display for DrawingTools {
@@ -35,6 +44,9 @@ impl Display for DrawingTools {
             DrawingTools::Text => "TEXT",
             DrawingTools::Eraser => "ERASER",
             DrawingTools::Rect => "RECTANGLE",
+            DrawingTools::Table => "TABLE",
+            DrawingTools::Freehand => "FREEHAND",
+            DrawingTools::Fill => "FILL",
         };
         write!(f, "{}", op)
     }
@@ -83,6 +95,10 @@ pub trait ToolControl {
         shape_list: &mut ShapeList,
         grid_list: &mut GridList,
     );
+    // How many cells around the mouse the tool works on, it's outlined on the canvas
+    fn brush_size(&self) -> usize {
+        1
+    }
 }
 
 pub struct ToolManager {
@@ -99,6 +115,9 @@ impl ToolManager {
                 Box::new(TextTool::new()),
                 Box::new(EraserTool::new()),
                 Box::new(RectTool::new()),
+                Box::new(TableTool::new()),
+                Box::new(FreehandTool::new()),
+                Box::new(FillTool::new()),
             ],
             current: DrawingTools::Select,
         }
@@ -149,4 +168,8 @@ impl ToolControl for ToolManager {
     ) {
         self.available_tools[self.current].end(ctx, event, shape_list, grid_list);
     }
+
+    fn brush_size(&self) -> usize {
+        self.available_tools[self.current].brush_size()
+    }
 }
@@ -1,10 +1,10 @@
-use druid::EventCtx;
+use druid::{EventCtx, KbKey};
 use std::ops::Sub;
 
 use crate::shapes::block::BlockShape;
 use crate::{
     consts::{SELECTION_END_COMMAND, SELECTION_MOVE_COMMAND, SELECTION_START_COMMAND},
-    data::{grid_list::GridList, shape_list::ShapeList},
+    data::{grid_list::GridList, history::HISTORY_MANAGER, shape_list::ShapeList},
 };
 
 use super::ToolControl;
@@ -13,6 +13,13 @@ pub struct SelectTool {
     is_selecting: bool,
     offset_row: usize,
     offset_col: usize,
+    // Where the selection being moved as a block used to be, the selected shapes
+    // are moved along with it
+    block_origin: Option<(usize, usize)>,
+    // Cell where dragging the selected shapes started, and how far they moved
+    drag_origin: Option<(usize, usize)>,
+    drag_offset: (isize, isize),
+    is_moving_shapes: bool,
 }
 
 impl SelectTool {
@@ -21,6 +28,10 @@ impl SelectTool {
             is_selecting: false,
             offset_row: 0,
             offset_col: 0,
+            block_origin: None,
+            drag_origin: None,
+            drag_offset: (0, 0),
+            is_moving_shapes: false,
         }
     }
 }
@@ -38,17 +49,47 @@ impl ToolControl for SelectTool {
         let row = (event.pos.y / cell_height) as usize;
         let col = (event.pos.x / cell_width) as usize;
         let i = row * cols + col;
+        self.block_origin = None;
+        self.drag_origin = None;
         if !grid_list.get(i).highlighted {
-            ctx.submit_command(SELECTION_START_COMMAND.with(event.pos));
-            self.is_selecting = true;
+            match shape_list.find_shape_at_cell(row, col, grid_list) {
+                Some(index) => {
+                    shape_list.select(index, event.mods.shift());
+                    grid_list.clear_all_highlight();
+                    if shape_list.selected.contains(&index) {
+                        self.drag_origin = Some((row, col));
+                        self.drag_offset = (0, 0);
+                    }
+                }
+                None => {
+                    if !event.mods.shift() {
+                        shape_list.selected.clear();
+                    }
+                    ctx.submit_command(SELECTION_START_COMMAND.with(event.pos));
+                    self.is_selecting = true;
+                }
+            }
         } else {
             if let Some(((sel_row, sel_col), _)) = grid_list.current_selection {
+                if !shape_list.selected.is_empty() {
+                    self.block_origin = Some((sel_row, sel_col));
+                }
                 // Calculate the offset between current mouse pos and selection rect
                 self.offset_row = (row as isize - sel_row as isize).abs() as usize;
                 self.offset_col = (col as isize - sel_col as isize).abs() as usize;
                 // Create new block shape here
                 let block_content = grid_list.get_highlighted_content();
-                grid_list.erase_highlighted();
+                // The shapes cut by the selection stay behind as text, the selected
+                // ones go along with the block
+                unsafe {
+                    HISTORY_MANAGER.begin_group();
+                }
+                let cells = grid_list.erase_highlighted();
+                let selected = shape_list.selected.clone();
+                shape_list.detach_shapes_except(&cells, &selected, grid_list);
+                unsafe {
+                    HISTORY_MANAGER.end_group();
+                }
                 grid_list.clear_all_highlight();
                 shape_list.add_shape(Box::new(BlockShape::new(row, col, block_content)));
             }
@@ -64,6 +105,32 @@ impl ToolControl for SelectTool {
     ) {
         if self.is_selecting {
             ctx.submit_command(SELECTION_MOVE_COMMAND.with(event.pos));
+        } else if let Some((origin_row, origin_col)) = self.drag_origin {
+            let (cell_width, cell_height) = grid_list.cell_size;
+            let mouse_row = (event.pos.y.max(0.0) / cell_height) as isize;
+            let mouse_col = (event.pos.x.max(0.0) / cell_width) as isize;
+            let mut offset = (
+                mouse_row - origin_row as isize - self.drag_offset.0,
+                mouse_col - origin_col as isize - self.drag_offset.1,
+            );
+            if offset != (0, 0) || self.is_moving_shapes {
+                // Edges and centres close to the ones of other shapes snap to them
+                offset = shape_list.snap_selected(offset);
+            }
+            if offset != (0, 0) {
+                if !self.is_moving_shapes {
+                    // The shapes only leave the canvas once they really move, so a
+                    // plain click doesn't end up in the history
+                    unsafe {
+                        HISTORY_MANAGER.begin_group();
+                    }
+                    shape_list.lift_selected(grid_list);
+                    self.is_moving_shapes = true;
+                }
+                let moved = shape_list.move_selected(offset, grid_list);
+                self.drag_offset = (self.drag_offset.0 + moved.0, self.drag_offset.1 + moved.1);
+                shape_list.update_guides();
+            }
         } else {
             if let Some(block) = shape_list.data.last_mut() {
                 if let Some(mut block) = block.as_any_mut().downcast_mut::<BlockShape>() {
@@ -72,7 +139,7 @@ impl ToolControl for SelectTool {
                     let mouse_col = (event.pos.x / cell_width) as usize;
                     let shape_row = mouse_row.saturating_sub(self.offset_row);
                     let shape_col = mouse_col.saturating_sub(self.offset_col);
-                    block.start = (shape_row, shape_col);
+                    block.set_position(shape_row, shape_col);
                 }
             }
         }
@@ -81,19 +148,56 @@ impl ToolControl for SelectTool {
     fn input(
         &mut self,
         _ctx: &mut EventCtx,
-        _event: &druid::KeyEvent,
-        _shape_list: &mut ShapeList,
-        _grid_list: &mut GridList,
+        event: &druid::KeyEvent,
+        shape_list: &mut ShapeList,
+        grid_list: &mut GridList,
     ) {
+        // The arrows with a modifier align the selected shapes instead
+        if event.mods.ctrl() || event.mods.meta() {
+            return;
+        }
+        let offset = match event.key {
+            KbKey::ArrowUp => (-1, 0),
+            KbKey::ArrowDown => (1, 0),
+            KbKey::ArrowLeft => (0, -1),
+            KbKey::ArrowRight => (0, 1),
+            _ => return,
+        };
+        shape_list.nudge_selected(offset, grid_list);
     }
 
     fn end(
         &mut self,
         ctx: &mut EventCtx,
         event: &druid::MouseEvent,
-        _shape_list: &mut ShapeList,
-        _grid_list: &mut GridList,
+        shape_list: &mut ShapeList,
+        grid_list: &mut GridList,
     ) {
+        if self.is_moving_shapes {
+            self.is_moving_shapes = false;
+            shape_list.guides.clear();
+            shape_list.drop_selected(grid_list);
+            unsafe {
+                HISTORY_MANAGER.end_group();
+            }
+        }
+        self.drag_origin = None;
+        if let Some((origin_row, origin_col)) = self.block_origin.take() {
+            // The moved block already carries the glyphs of the selected shapes, only
+            // their positions are updated
+            let position = shape_list
+                .data
+                .last()
+                .and_then(|shape| shape.as_any().downcast_ref::<BlockShape>())
+                .map(|block| block.start);
+            if let Some((row, col)) = position {
+                let offset = (
+                    row as isize - origin_row as isize,
+                    col as isize - origin_col as isize,
+                );
+                shape_list.drop_block(offset, grid_list);
+            }
+        }
         ctx.submit_command(SELECTION_END_COMMAND.with(event.pos));
         self.is_selecting = false;
     }
@@ -0,0 +1,312 @@
+use druid::{EventCtx, KbKey};
+
+use crate::{
+    data::{grid_list::GridList, shape_list::ShapeList},
+    shapes::{
+        table::{TableShape, TABLE_MIN_CELL_WIDTH},
+        Shape,
+    },
+};
+
+use super::ToolControl;
+
+pub struct TableTool {
+    is_drawing: bool,
+    // The table being edited (index in the shape list), and the cell under the cursor
+    active_cell: Option<(usize, usize, usize)>,
+}
+
+impl TableTool {
+    pub fn new() -> Self {
+        Self {
+            is_drawing: false,
+            active_cell: None,
+        }
+    }
+
+    fn highlight_active_cell(&mut self, shape_list: &mut ShapeList, grid_list: &mut GridList) {
+        let (rows, cols) = grid_list.grid_size;
+        if let Some((index, table_row, table_col)) = self.active_cell {
+            if let Some(table) = table_at(shape_list, index) {
+                let (row, col) = table.text_cursor(table_row, table_col);
+                grid_list.highlight(row.min(rows - 1) * cols + col.min(cols - 1));
+                return;
+            }
+        }
+        self.active_cell = None;
+        grid_list.clear_all_highlight();
+    }
+}
+
+fn table_at(shape_list: &mut ShapeList, index: usize) -> Option<&mut TableShape> {
+    shape_list
+        .data
+        .get_mut(index)
+        .and_then(|shape| shape.as_any_mut().downcast_mut::<TableShape>())
+}
+
+fn as_table(shape: &mut Box<dyn Shape>) -> &mut TableShape {
+    shape
+        .as_any_mut()
+        .downcast_mut::<TableShape>()
+        .expect("Shape is not a table")
+}
+
+// Change a table on the canvas. A change that would grow it past the edges of
+// the canvas is refused, returns whether it was made.
+fn update_table<F>(
+    index: usize,
+    shape_list: &mut ShapeList,
+    grid_list: &mut GridList,
+    update: F,
+) -> bool
+where
+    F: FnOnce(&mut TableShape),
+{
+    let (rows, cols) = grid_list.grid_size;
+    let mut table = match table_at(shape_list, index) {
+        Some(table) => table.clone(),
+        None => return false,
+    };
+    let end = table.end;
+    update(&mut table);
+    // Typing into a cell can make it wider
+    table.update_bounds();
+    if (table.end.0 >= rows && table.end.0 > end.0) || (table.end.1 >= cols && table.end.1 > end.1)
+    {
+        return false;
+    }
+    shape_list.update_shape(index, grid_list, |shape| *as_table(shape) = table);
+    true
+}
+
+impl ToolControl for TableTool {
+    fn start(
+        &mut self,
+        _ctx: &mut EventCtx,
+        event: &druid::MouseEvent,
+        shape_list: &mut ShapeList,
+        grid_list: &mut GridList,
+    ) {
+        let (cell_width, cell_height) = grid_list.cell_size;
+        let mouse_row = (event.pos.y / cell_height) as usize;
+        let mouse_col = (event.pos.x / cell_width) as usize;
+
+        // Clicking on an existing table starts editing the cell under the mouse
+        let clicked_cell =
+            shape_list
+                .data
+                .iter_mut()
+                .enumerate()
+                .rev()
+                .find_map(|(index, shape)| {
+                    let table = shape.as_any_mut().downcast_mut::<TableShape>()?;
+                    let (table_row, table_col) = table.cell_at(mouse_row, mouse_col)?;
+                    Some((index, table_row, table_col))
+                });
+        if clicked_cell.is_some() {
+            self.active_cell = clicked_cell;
+            self.highlight_active_cell(shape_list, grid_list);
+            return;
+        }
+
+        // A single cell table has to fit, it's started further up or left near
+        // the edges
+        let (rows, cols) = grid_list.grid_size;
+        let row = mouse_row.min(rows.saturating_sub(3));
+        let col = mouse_col.min(cols.saturating_sub(TABLE_MIN_CELL_WIDTH + 2));
+        self.is_drawing = true;
+        self.active_cell = None;
+        grid_list.clear_all_highlight();
+        shape_list.add_shape(Box::new(TableShape::new(row, col, 1, 1)));
+    }
+
+    fn draw(
+        &mut self,
+        _ctx: &mut EventCtx,
+        event: &druid::MouseEvent,
+        shape_list: &mut ShapeList,
+        grid_list: &mut GridList,
+    ) {
+        if !self.is_drawing {
+            return;
+        }
+        if let Some(shape) = shape_list.data.last_mut() {
+            if let Some(table) = shape.as_any_mut().downcast_mut::<TableShape>() {
+                let (cell_width, cell_height) = grid_list.cell_size;
+                let (rows, cols) = grid_list.grid_size;
+                let mouse_row = ((event.pos.y / cell_height) as usize).min(rows - 1);
+                let mouse_col = ((event.pos.x / cell_width) as usize).min(cols - 1);
+                let table_rows = mouse_row.saturating_sub(table.start.0) / 2;
+                let table_cols =
+                    mouse_col.saturating_sub(table.start.1) / (TABLE_MIN_CELL_WIDTH + 1);
+                table.resize(table_rows, table_cols);
+            }
+        }
+    }
+
+    fn input(
+        &mut self,
+        _ctx: &mut EventCtx,
+        event: &druid::KeyEvent,
+        shape_list: &mut ShapeList,
+        grid_list: &mut GridList,
+    ) {
+        if event.mods.ctrl() || event.mods.meta() {
+            return;
+        }
+        let (index, table_row, table_col) = match self.active_cell {
+            Some(active_cell) => active_cell,
+            None => return,
+        };
+        let (rows, cols) = match table_at(shape_list, index) {
+            Some(table) => (table.rows(), table.cols()),
+            None => {
+                self.active_cell = None;
+                return;
+            }
+        };
+
+        if event.mods.alt() {
+            // Alt + arrows add a row or column next to the current cell,
+            // Alt + Backspace removes the current row, or column with Shift
+            let (next_row, next_col) = match event.key {
+                KbKey::ArrowUp => {
+                    update_table(index, shape_list, grid_list, |table| {
+                        table.insert_row(table_row)
+                    });
+                    (table_row, table_col)
+                }
+                KbKey::ArrowDown => {
+                    if update_table(index, shape_list, grid_list, |table| {
+                        table.insert_row(table_row + 1)
+                    }) {
+                        (table_row + 1, table_col)
+                    } else {
+                        (table_row, table_col)
+                    }
+                }
+                KbKey::ArrowLeft => {
+                    update_table(index, shape_list, grid_list, |table| {
+                        table.insert_column(table_col)
+                    });
+                    (table_row, table_col)
+                }
+                KbKey::ArrowRight => {
+                    if update_table(index, shape_list, grid_list, |table| {
+                        table.insert_column(table_col + 1)
+                    }) {
+                        (table_row, table_col + 1)
+                    } else {
+                        (table_row, table_col)
+                    }
+                }
+                KbKey::Backspace | KbKey::Delete if event.mods.shift() => {
+                    update_table(index, shape_list, grid_list, |table| {
+                        table.remove_column(table_col)
+                    });
+                    (table_row, table_col.min(cols.saturating_sub(2)))
+                }
+                KbKey::Backspace | KbKey::Delete => {
+                    update_table(index, shape_list, grid_list, |table| {
+                        table.remove_row(table_row)
+                    });
+                    (table_row.min(rows.saturating_sub(2)), table_col)
+                }
+                _ => (table_row, table_col),
+            };
+            self.active_cell = Some((index, next_row, next_col));
+            self.highlight_active_cell(shape_list, grid_list);
+            return;
+        }
+
+        let (next_row, next_col) = match &event.key {
+            KbKey::Character(c) => {
+                let c = c.clone();
+                update_table(index, shape_list, grid_list, |table| {
+                    table.cells[table_row][table_col].push_str(&c);
+                });
+                (table_row, table_col)
+            }
+            KbKey::Backspace => {
+                update_table(index, shape_list, grid_list, |table| {
+                    table.cells[table_row][table_col].pop();
+                });
+                (table_row, table_col)
+            }
+            KbKey::Tab if event.mods.shift() => match (table_row, table_col) {
+                (0, 0) => (0, 0),
+                (row, 0) => (row - 1, cols - 1),
+                (row, col) => (row, col - 1),
+            },
+            KbKey::Tab => match (table_row, table_col) {
+                (row, col) if col + 1 < cols => (row, col + 1),
+                (row, _) if row + 1 < rows => (row + 1, 0),
+                _ => (table_row, table_col),
+            },
+            KbKey::Enter | KbKey::ArrowDown => ((table_row + 1).min(rows - 1), table_col),
+            KbKey::ArrowUp => (table_row.saturating_sub(1), table_col),
+            KbKey::ArrowRight => (table_row, (table_col + 1).min(cols - 1)),
+            KbKey::ArrowLeft => (table_row, table_col.saturating_sub(1)),
+            _ => (table_row, table_col),
+        };
+        self.active_cell = Some((index, next_row, next_col));
+        self.highlight_active_cell(shape_list, grid_list);
+    }
+
+    fn end(
+        &mut self,
+        _ctx: &mut EventCtx,
+        _event: &druid::MouseEvent,
+        shape_list: &mut ShapeList,
+        grid_list: &mut GridList,
+    ) {
+        if self.is_drawing {
+            // Start typing into the first cell of the new table
+            self.is_drawing = false;
+            self.active_cell = Some((shape_list.data.len() - 1, 0, 0));
+            self.highlight_active_cell(shape_list, grid_list);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::history::HISTORY_LOCK;
+
+    fn table_at_bottom(row: usize) -> (TableTool, ShapeList, GridList) {
+        let mut grid_list = GridList::new(10.0, 10.0, 10, 40);
+        let mut shape_list = ShapeList::new();
+        shape_list.insert_shapes(
+            vec![Box::new(TableShape::new(row, 0, 1, 1))],
+            &mut grid_list,
+        );
+        let mut tool = TableTool::new();
+        tool.active_cell = Some((0, 0, 0));
+        (tool, shape_list, grid_list)
+    }
+
+    #[test]
+    fn rows_are_not_added_past_the_bottom_edge() {
+        let _lock = HISTORY_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let (_, mut shape_list, mut grid_list) = table_at_bottom(7);
+        assert!(!update_table(0, &mut shape_list, &mut grid_list, |table| {
+            table.insert_row(1)
+        }));
+        let table = table_at(&mut shape_list, 0).unwrap();
+        assert_eq!((table.rows(), table.end.0), (1, 9));
+        assert!(update_table(0, &mut shape_list, &mut grid_list, |table| {
+            table.insert_column(1)
+        }));
+    }
+
+    #[test]
+    fn active_cell_past_the_bottom_edge_is_highlighted_on_the_last_row() {
+        let _lock = HISTORY_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let (mut tool, mut shape_list, mut grid_list) = table_at_bottom(9);
+        tool.highlight_active_cell(&mut shape_list, &mut grid_list);
+        let (rows, cols) = grid_list.grid_size;
+        assert!(grid_list.get((rows - 1) * cols + 2).highlighted);
+    }
+}
@@ -71,7 +71,7 @@ impl ToolControl for TextTool {
         &mut self,
         _ctx: &mut EventCtx,
         event: &druid::KeyEvent,
-        _shape_list: &mut ShapeList,
+        shape_list: &mut ShapeList,
         grid_list: &mut GridList,
     ) {
         let (rows, cols) = grid_list.grid_size;
@@ -143,10 +143,17 @@ impl ToolControl for TextTool {
         let i = row * cols + col;
         grid_list.highlight(i);
 
+        // Typing over a shape leaves it as text
+        let cells = self.version.cells();
         unsafe {
+            HISTORY_MANAGER.begin_group();
             HISTORY_MANAGER.save_version(self.version.clone());
-            self.version = Version::new();
         }
+        shape_list.detach_shapes(&cells, grid_list);
+        unsafe {
+            HISTORY_MANAGER.end_group();
+        }
+        self.version = Version::new();
     }
 
     fn end(
@@ -1,28 +1,66 @@
-use std::{fs::File, io::Write, usize};
+use std::{
+    ffi::OsString,
+    fs::{self, File},
+    io::{self, Write},
+    path::Path,
+};
 
 use druid::{
     commands::{self, NEW_FILE},
     kurbo::Line,
-    Application, Code, Cursor, Event, FontDescriptor, FontFamily, FontWeight, LifeCycleCtx, Point,
-    Rect, RenderContext, Size, TextLayout, Widget,
+    Affine, Application, ClipboardFormat, Cursor, Event, EventCtx, FileDialogOptions,
+    FontDescriptor, FontFamily, FontWeight, KbKey, KeyEvent, LifeCycleCtx, Modifiers, MouseButton,
+    MouseButtons, MouseEvent, Point, Rect, RenderContext, Size, TextLayout, Vec2, Widget,
 };
 
 use crate::{
-    consts::{CANVAS_SIZE, SELECTION_END_COMMAND, SELECTION_MOVE_COMMAND, SELECTION_START_COMMAND},
+    consts::{
+        ACTION_COMMAND, CANCEL_PLACING_COMMAND, CANVAS_SIZE, COPY_AS_COMMAND, EXPORT_AS_COMMAND,
+        EXPORT_FILE_COMMAND, INSERT_DIRECTORY_TREE_COMMAND, LAYER_COMMAND, PLACE_BLOCK_COMMAND,
+        SAVE_STENCIL_COMMAND, SELECTION_END_COMMAND, SELECTION_MOVE_COMMAND,
+        SELECTION_START_COMMAND, STENCIL_SAVED_COMMAND,
+    },
     data::{
-        grid_list::GridList, history::HISTORY_MANAGER, selection::SelectionRange,
-        shape_list::ShapeList, ApplicationState,
+        align::{Arrange, Guide},
+        clipboard::{self, SHAPES_FORMAT},
+        export::ExportFormat,
+        grid_list::GridList,
+        history::HISTORY_MANAGER,
+        keymap::{Action, KEYMAP},
+        layers::{LayerAction, LayerList},
+        recent::add_recent_file,
+        selection::SelectionRange,
+        settings::SETTINGS,
+        shape_list::{ShapeList, ZOrder},
+        stencil,
+        transform::Transform,
+        vim::{Vim, VimAction},
+        ApplicationState, WindowData,
     },
+    generators::{
+        ascii::AsciiDiagram,
+        fit_in_grid,
+        flowchart::{Graph, Layout},
+        sequence::SequenceDiagram,
+        table::parse_delimited,
+        tree::{self, TreeNode},
+    },
+    shapes::{block::BlockShape, bounds, group::GroupShape, table::TableShape, Shape},
     tools::{DrawingTools, ToolControl, ToolManager},
 };
 
 use super::CURRENT_THEME;
 
+const ZOOM_STEP: f64 = 1.25;
+const MIN_ZOOM: f64 = 0.25;
+const MAX_ZOOM: f64 = 4.0;
+
 pub struct CanvasGrid {
     width: f64,
     height: f64,
     grid_list: GridList,
     shape_list: ShapeList,
+    layers: LayerList,
     cell_size: Option<(f64, f64)>,
     letterbox: TextLayout<String>,
     grid_text: TextLayout<String>,
@@ -31,6 +69,18 @@ pub struct CanvasGrid {
     selection_range: SelectionRange,
     is_mouse_down: bool,
     tool_manager: ToolManager,
+    export_format: Option<ExportFormat>,
+    is_placing: bool,
+    // Waiting for the character to fill the selection with
+    is_filling: bool,
+    // The tools are driven from the keyboard, at the cell cursor
+    keyboard_mode: bool,
+    is_key_pressed: bool,
+    // Where the region being selected from the keyboard started
+    region_anchor: Option<(usize, usize)>,
+    // Vim-style modal editing, when turned on in the settings
+    vim: Option<Vim>,
+    zoom: f64,
 }
 impl CanvasGrid {
     pub fn new(_ctx: &mut LifeCycleCtx) -> Self {
@@ -51,11 +101,20 @@ impl CanvasGrid {
             height: CANVAS_SIZE,
             grid_list: GridList::default(),
             shape_list: ShapeList::new(),
+            layers: LayerList::new(),
             cell_size: None,
             mouse_position: (0, 0),
             is_mouse_down: false,
             tool_manager: ToolManager::new(),
             selection_range: SelectionRange::new(),
+            export_format: None,
+            is_placing: false,
+            is_filling: false,
+            keyboard_mode: SETTINGS.vim_mode,
+            is_key_pressed: false,
+            region_anchor: None,
+            vim: SETTINGS.vim_mode.then(Vim::new),
+            zoom: 1.0,
             letterbox,
             grid_text,
             grid_preview,
@@ -69,7 +128,720 @@ impl CanvasGrid {
             self.grid_list = GridList::new(cell_width, cell_height, rows, cols);
         }
     }
+
+    // Plain text follows the mouse until it's dropped with a click
+    fn paste_content(&mut self, content: String, opaque: bool) {
+        let (row, col) = self.mouse_position;
+        let block = BlockShape::new(row, col, content).with_opaque(opaque);
+        if !self.start_placing(vec![Box::new(block)]) {
+            println!("Text is too big to fit in the canvas");
+        }
+    }
+
+    // Mermaid, Graphviz and sequence diagram sources are laid out as shapes, and
+    // boxes and arrows drawn as text become shapes that can be edited. They're
+    // placed with the mouse like pasted text.
+    fn paste_as_shapes(&mut self, content: String, win_data: &mut WindowData) {
+        let shapes = if let Some(graph) = Graph::parse(&content) {
+            Layout::new(&graph).to_shapes(0, 0)
+        } else if let Some(diagram) = SequenceDiagram::parse(&content) {
+            if !diagram.skipped.is_empty() {
+                let lines: Vec<String> = diagram.skipped.iter().map(|n| n.to_string()).collect();
+                win_data.message = Some(format!(
+                    "Skipped lines that aren't messages: {}",
+                    lines.join(", ")
+                ));
+            }
+            diagram.to_shapes(0, 0)
+        } else if let Some(diagram) = AsciiDiagram::parse(&content) {
+            diagram.to_shapes(0, 0)
+        } else {
+            println!("No diagram found in the clipboard");
+            return;
+        };
+        if !self.start_placing(shapes) {
+            println!("Diagram is too big to fit in the canvas");
+        }
+    }
+
+    // The highlighted area is copied as text, as it looks with the visible layers.
+    // Selected shapes go along in their own format, so another window can paste
+    // them back as shapes.
+    fn copy_selection(&mut self) {
+        let mut flat = self.layers.flatten(&mut self.grid_list, false);
+        let shapes = self.shape_list.selected_shapes();
+        let area = shapes.iter().map(|shape| bounds(shape.as_ref())).reduce(
+            |(start, end), (top_left, bottom_right)| {
+                (
+                    (start.0.min(top_left.0), start.1.min(top_left.1)),
+                    (end.0.max(bottom_right.0), end.1.max(bottom_right.1)),
+                )
+            },
+        );
+        let mut text = flat.get_highlighted_content();
+        if let (true, Some((start, end))) = (text.is_empty(), area) {
+            // Only shapes are selected, the text is what they cover
+            flat.highlight_area(start, end);
+            text = flat.get_highlighted_content();
+        }
+        let mut system_clipboard = Application::global().clipboard();
+        if shapes.is_empty() {
+            system_clipboard.put_string(text);
+        } else {
+            system_clipboard.put_formats(&[
+                ClipboardFormat::new(SHAPES_FORMAT, clipboard::serialize(&shapes)),
+                ClipboardFormat::new(ClipboardFormat::TEXT, text),
+            ]);
+        }
+    }
+
+    // Shapes copied from an ASCII-d window are pasted back as shapes, they're
+    // placed with the mouse like pasted text
+    fn paste_shapes(&mut self) -> bool {
+        let shapes = match Application::global()
+            .clipboard()
+            .get_format(SHAPES_FORMAT)
+            .and_then(|data| String::from_utf8(data).ok())
+            .and_then(|source| clipboard::deserialize(&source, self.grid_list.grid_size))
+        {
+            Some(shapes) => shapes,
+            None => return false,
+        };
+        if !self.start_placing(shapes) {
+            println!("Shapes are too big to fit in the canvas");
+        }
+        true
+    }
+
+    fn paste_table(&mut self, content: String) {
+        if let Some(cells) = parse_delimited(&content) {
+            let (row, col) = self.mouse_position;
+            let mut table = TableShape::from_rows(row, col, cells);
+            let size = (table.end.0 - row + 1, table.end.1 - col + 1);
+            match fit_in_grid(size, (row, col), self.grid_list.grid_size) {
+                Some((row, col)) => {
+                    table.start = (row, col);
+                    table.resize(table.rows(), table.cols());
+                    self.shape_list
+                        .insert_shapes(vec![Box::new(table)], &mut self.grid_list);
+                }
+                None => println!("Table is too big to fit in the canvas"),
+            }
+        }
+    }
+
+    // The shapes being placed are grouped as the last preview shape, they follow
+    // the mouse until they're dropped on the canvas
+    fn move_placing_block(&mut self) {
+        let grid_size = self.grid_list.grid_size;
+        if let Some(shape) = self.shape_list.data.last_mut() {
+            let (start, end) = bounds(shape.as_ref());
+            let size = (end.0 - start.0 + 1, end.1 - start.1 + 1);
+            if let Some(position) = fit_in_grid(size, self.mouse_position, grid_size) {
+                shape.translate((
+                    position.0 as isize - start.0 as isize,
+                    position.1 as isize - start.1 as isize,
+                ));
+            }
+        }
+    }
+
+    // A mouse event on the cell under the cursor, so the tools work the same way
+    // from the keyboard as with the mouse
+    fn cursor_event(&self, mods: Modifiers) -> MouseEvent {
+        let (cell_width, cell_height) = self.grid_list.cell_size;
+        let (row, col) = self.mouse_position;
+        let pos = Point::new(
+            (col as f64 + 0.5) * cell_width,
+            (row as f64 + 0.5) * cell_height,
+        );
+        MouseEvent {
+            pos,
+            window_pos: pos,
+            buttons: MouseButtons::new().with(MouseButton::Left),
+            mods,
+            count: 1,
+            focus: false,
+            button: MouseButton::Left,
+            wheel_delta: Vec2::ZERO,
+        }
+    }
+
+    // Pressing the key works like pressing the mouse button, and pressing it
+    // again like releasing it
+    fn press_at_cursor(&mut self, ctx: &mut EventCtx, mods: Modifiers) {
+        if !self.layers.is_editable() {
+            return;
+        }
+        let event = self.cursor_event(mods);
+        if self.is_placing {
+            self.drop_placing();
+        } else if !self.is_key_pressed {
+            self.is_key_pressed = true;
+            self.tool_manager
+                .start(ctx, &event, &mut self.shape_list, &mut self.grid_list);
+        } else {
+            self.release_at_cursor(ctx, mods);
+        }
+    }
+
+    fn release_at_cursor(&mut self, ctx: &mut EventCtx, mods: Modifiers) {
+        if self.is_key_pressed {
+            self.is_key_pressed = false;
+            let event = self.cursor_event(mods);
+            self.tool_manager
+                .end(ctx, &event, &mut self.shape_list, &mut self.grid_list);
+            self.shape_list.commit(&mut self.grid_list);
+        }
+    }
+
+    // Select the cells between the anchor and the cursor, and the shapes inside
+    // them once the region is done
+    fn select_region(&mut self, done: bool) {
+        let anchor = match self.region_anchor {
+            Some(anchor) => anchor,
+            None => return,
+        };
+        let (row, col) = self.mouse_position;
+        let start = (anchor.0.min(row), anchor.1.min(col));
+        let end = (anchor.0.max(row), anchor.1.max(col));
+        self.grid_list.highlight_area(start, end);
+        self.grid_list.current_selection = Some((start, (end.0 + 1, end.1 + 1)));
+        if done {
+            self.region_anchor = None;
+            let (cell_width, cell_height) = self.grid_list.cell_size;
+            let rect = Rect::new(
+                start.1 as f64 * cell_width,
+                start.0 as f64 * cell_height,
+                (end.1 + 1) as f64 * cell_width,
+                (end.0 + 1) as f64 * cell_height,
+            );
+            self.shape_list.selected = self
+                .shape_list
+                .find_shape_in_rect(rect, &mut self.grid_list);
+        }
+    }
+
+    // Move the cursor to the cell, dragging along what the key press started
+    fn move_cursor(&mut self, ctx: &mut EventCtx, to: (usize, usize), mods: Modifiers) {
+        let (rows, cols) = self.grid_list.grid_size;
+        self.mouse_position = (to.0.min(rows - 1), to.1.min(cols - 1));
+        if self.is_placing {
+            self.move_placing_block();
+        } else if self.is_key_pressed {
+            let event = self.cursor_event(mods);
+            self.tool_manager
+                .draw(ctx, &event, &mut self.shape_list, &mut self.grid_list);
+        } else if self.region_anchor.is_some() {
+            self.select_region(false);
+        }
+        let (cell_width, cell_height) = self.grid_list.cell_size;
+        let (row, col) = self.mouse_position;
+        ctx.scroll_area_to_view(
+            Rect::new(
+                col as f64 * cell_width,
+                row as f64 * cell_height,
+                (col + 1) as f64 * cell_width,
+                (row + 1) as f64 * cell_height,
+            )
+            .scale_from_origin(self.zoom),
+        );
+    }
+
+    // Whether typed characters go to the text or the table cell being edited, and
+    // whether the tool takes them at all, the freehand and fill tools use them as
+    // their brush
+    fn takes_characters(&self, mode: DrawingTools) -> (bool, bool) {
+        let is_typing = mode == DrawingTools::Text
+            || (mode == DrawingTools::Table && self.grid_list.has_highlight());
+        let takes_characters =
+            is_typing || mode == DrawingTools::Freehand || mode == DrawingTools::Fill;
+        (is_typing, takes_characters)
+    }
+
+    // Start typing at the cursor with the text tool
+    fn type_at_cursor(&mut self, ctx: &mut EventCtx, mods: Modifiers) {
+        self.tool_manager.set_tool(DrawingTools::Text);
+        let event = self.cursor_event(mods);
+        self.tool_manager
+            .start(ctx, &event, &mut self.shape_list, &mut self.grid_list);
+        self.tool_manager
+            .end(ctx, &event, &mut self.shape_list, &mut self.grid_list);
+    }
+
+    // Keys of the keyboard mode: hjkl or the arrows move the cursor, with shift
+    // five cells at a time, Enter presses and releases the mouse button, and v
+    // starts and ends selecting a region. Returns false for the keys the current
+    // tool should get instead.
+    fn handle_cursor_key(
+        &mut self,
+        ctx: &mut EventCtx,
+        event: &KeyEvent,
+        mode: DrawingTools,
+    ) -> bool {
+        if event.mods.ctrl() || event.mods.meta() || event.mods.alt() {
+            return false;
+        }
+        let (is_typing, takes_characters) = self.takes_characters(mode);
+        if is_typing {
+            return false;
+        }
+        let letter = match &event.key {
+            KbKey::Character(c) if !takes_characters => c.to_lowercase(),
+            _ => String::new(),
+        };
+        let direction = match (&event.key, letter.as_str()) {
+            (KbKey::ArrowLeft, _) | (_, "h") => Some((0, -1)),
+            (KbKey::ArrowDown, _) | (_, "j") => Some((1, 0)),
+            (KbKey::ArrowUp, _) | (_, "k") => Some((-1, 0)),
+            (KbKey::ArrowRight, _) | (_, "l") => Some((0, 1)),
+            _ => None,
+        };
+        if let Some((d_row, d_col)) = direction {
+            let distance = if event.mods.shift() { 5 } else { 1 };
+            let (row, col) = self.mouse_position;
+            let to = (
+                (row as isize + d_row * distance).max(0) as usize,
+                (col as isize + d_col * distance).max(0) as usize,
+            );
+            self.move_cursor(ctx, to, event.mods);
+            return true;
+        }
+        match event.key {
+            KbKey::Enter => {
+                self.press_at_cursor(ctx, event.mods);
+                true
+            }
+            KbKey::Escape if self.is_key_pressed => {
+                self.release_at_cursor(ctx, event.mods);
+                true
+            }
+            KbKey::Escape if self.region_anchor.is_some() => {
+                self.region_anchor = None;
+                self.grid_list.clear_all_highlight();
+                true
+            }
+            _ if letter == "v" && mode == DrawingTools::Select => {
+                if self.region_anchor.is_some() {
+                    self.select_region(true);
+                } else {
+                    self.region_anchor = Some(self.mouse_position);
+                    self.shape_list.selected.clear();
+                    self.select_region(false);
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    // Returns false when the shapes can't fit in the canvas
+    fn start_placing(&mut self, shapes: Vec<Box<dyn Shape>>) -> bool {
+        self.cancel_placing();
+        let mut group = GroupShape::new(shapes);
+        let size = (
+            group.end.0 - group.start.0 + 1,
+            group.end.1 - group.start.1 + 1,
+        );
+        if fit_in_grid(size, (0, 0), self.grid_list.grid_size).is_none() {
+            return false;
+        }
+        group.preview = true;
+        self.shape_list.add_shape(Box::new(group));
+        self.is_placing = true;
+        self.move_placing_block();
+        true
+    }
+
+    // Put the placed shapes down where they are, each one on its own again
+    fn drop_placing(&mut self) {
+        if !self.is_placing {
+            return;
+        }
+        self.is_placing = false;
+        let shapes = self
+            .shape_list
+            .data
+            .pop()
+            .and_then(|mut shape| {
+                shape
+                    .as_any_mut()
+                    .downcast_mut::<GroupShape>()
+                    .map(|group| std::mem::take(&mut group.shapes))
+            })
+            .unwrap_or_default();
+        self.grid_list.discard_all();
+        self.shape_list.insert_shapes(shapes, &mut self.grid_list);
+    }
+
+    fn cancel_placing(&mut self) {
+        if self.is_placing {
+            self.is_placing = false;
+            if self
+                .shape_list
+                .data
+                .last()
+                .is_some_and(|shape| shape.is_preview())
+            {
+                self.shape_list.data.pop();
+            }
+            self.grid_list.discard_all();
+        }
+    }
+
+    // Replace the diagram drawn as plain characters in the highlighted area with
+    // shapes, at the same place
+    fn convert_selection(&mut self) {
+        let (start, _) = match self.grid_list.current_selection {
+            Some(selection) => selection,
+            None => return,
+        };
+        let content = self.grid_list.get_highlighted_content();
+        let diagram = match AsciiDiagram::parse(&content) {
+            Some(diagram) => diagram,
+            None => {
+                println!("No diagram found in the selection");
+                return;
+            }
+        };
+        unsafe {
+            HISTORY_MANAGER.begin_group();
+        }
+        let cells = self.grid_list.erase_highlighted();
+        self.shape_list.detach_shapes(&cells, &mut self.grid_list);
+        self.grid_list.clear_all_highlight();
+        self.shape_list
+            .insert_shapes(diagram.to_shapes(start.0, start.1), &mut self.grid_list);
+        unsafe {
+            HISTORY_MANAGER.end_group();
+        }
+    }
+
+    // Flip or rotate the highlighted cells together with the selected shapes, the
+    // rotated area keeps its top left corner
+    fn transform_selection(&mut self, transform: Transform) {
+        let mut area = self
+            .grid_list
+            .current_selection
+            .filter(|((top, left), (bottom, right))| bottom > top && right > left)
+            .map(|(start, (bottom, right))| (start, (bottom - 1, right - 1)));
+        let is_highlighted = area.is_some();
+        for (top_left, bottom_right) in self.shape_list.selected_bounds() {
+            area = Some(match area {
+                Some((start, end)) => (
+                    (start.0.min(top_left.0), start.1.min(top_left.1)),
+                    (end.0.max(bottom_right.0), end.1.max(bottom_right.1)),
+                ),
+                None => (top_left, bottom_right),
+            });
+        }
+        let area = match area {
+            Some(area) => area,
+            None => return,
+        };
+        let (rows, cols) = self.grid_list.grid_size;
+        let (new_start, new_end) = transform.map_area(area);
+        if new_end.0 >= rows || new_end.1 >= cols {
+            println!("Not enough room to rotate the selection");
+            return;
+        }
+        unsafe {
+            HISTORY_MANAGER.begin_group();
+        }
+        // The shapes are drawn again once the rest of the area is transformed, so
+        // they are joined with whatever they overlap
+        self.shape_list.lift_selected(&mut self.grid_list);
+        self.grid_list.transform_area(area, transform);
+        self.shape_list
+            .transform_selected(transform, area, &mut self.grid_list);
+        self.shape_list.drop_selected(&mut self.grid_list);
+        unsafe {
+            HISTORY_MANAGER.end_group();
+        }
+        if is_highlighted {
+            self.grid_list.highlight_area(new_start, new_end);
+            self.grid_list.current_selection = Some((new_start, (new_end.0 + 1, new_end.1 + 1)));
+        }
+    }
+
+    fn perform(&mut self, ctx: &mut EventCtx, action: Action, win_data: &mut WindowData) {
+        // Nothing can be changed on a hidden or locked layer
+        let is_editable = self.layers.is_editable();
+        match action {
+            Action::Cancel => {
+                if self.is_placing {
+                    self.cancel_placing();
+                } else {
+                    win_data.mode = DrawingTools::Select;
+                    self.shape_list.selected.clear();
+                }
+            }
+            Action::ToggleKeyboardMode => {
+                self.keyboard_mode = !self.keyboard_mode;
+                if !self.keyboard_mode {
+                    self.release_at_cursor(ctx, Modifiers::empty());
+                    self.region_anchor = None;
+                    if let Some(vim) = self.vim.as_mut() {
+                        vim.leave_visual(&mut self.grid_list);
+                    }
+                }
+            }
+            Action::ToggleLayers => {
+                win_data.show_layers = !win_data.show_layers;
+            }
+            Action::ToggleLibrary => {
+                win_data.show_library = !win_data.show_library;
+            }
+            Action::SelectTool => win_data.mode = DrawingTools::Select,
+            Action::LineTool => win_data.mode = DrawingTools::Line,
+            Action::RectTool => win_data.mode = DrawingTools::Rect,
+            Action::TextTool => win_data.mode = DrawingTools::Text,
+            Action::EraserTool => win_data.mode = DrawingTools::Eraser,
+            Action::TableTool => win_data.mode = DrawingTools::Table,
+            Action::FreehandTool => win_data.mode = DrawingTools::Freehand,
+            Action::FillTool => win_data.mode = DrawingTools::Fill,
+            Action::ToggleTheme => unsafe {
+                CURRENT_THEME.toggle_theme();
+            },
+            Action::ToggleGrid => {
+                win_data.show_grid = !win_data.show_grid;
+            }
+            Action::ToggleTrimWhitespace => {
+                win_data.trim_whitespace = !win_data.trim_whitespace;
+            }
+            Action::ZoomIn | Action::ZoomOut | Action::ResetZoom => {
+                self.zoom = match action {
+                    Action::ZoomIn => (self.zoom * ZOOM_STEP).min(MAX_ZOOM),
+                    Action::ZoomOut => (self.zoom / ZOOM_STEP).max(MIN_ZOOM),
+                    _ => 1.0,
+                };
+                ctx.request_layout();
+            }
+            Action::Copy => {
+                // copy current diagram to clipboard
+                self.copy_selection();
+            }
+            Action::CopyMarkdown => {
+                ctx.submit_command(COPY_AS_COMMAND.with(ExportFormat::Markdown));
+            }
+            Action::CopyHtml => {
+                ctx.submit_command(COPY_AS_COMMAND.with(ExportFormat::Html));
+            }
+            Action::CopyComment => {
+                ctx.submit_command(COPY_AS_COMMAND.with(ExportFormat::LineComment(
+                    SETTINGS.comment_prefix.to_owned(),
+                )));
+            }
+            Action::SaveStencil => {
+                ctx.submit_command(SAVE_STENCIL_COMMAND);
+            }
+            Action::AddLayer => {
+                ctx.submit_command(LAYER_COMMAND.with(LayerAction::Add));
+            }
+            Action::NewFile => {
+                ctx.submit_command(NEW_FILE);
+            }
+            // The toolbar opens and saves the files, the palette opens itself
+            Action::Open | Action::Save | Action::SaveAs | Action::CommandPalette => {}
+            _ if !is_editable => {}
+            Action::SelectAll => {
+                win_data.mode = DrawingTools::Select;
+                let (rows, cols) = self.grid_list.grid_size;
+                self.grid_list.highlight_area((0, 0), (rows - 1, cols - 1));
+                self.grid_list.current_selection = Some(((0, 0), (rows, cols)));
+                self.shape_list.selected = self.shape_list.find_shape_in_rect(
+                    Rect::new(0.0, 0.0, self.width, self.height),
+                    &mut self.grid_list,
+                );
+            }
+            Action::Delete | Action::Cut => unsafe {
+                if action == Action::Cut {
+                    // cut current diagram to clipboard
+                    self.copy_selection();
+                }
+                HISTORY_MANAGER.begin_group();
+                self.shape_list.remove_selected(&mut self.grid_list);
+                let cells = self.grid_list.erase_highlighted();
+                self.shape_list.detach_shapes(&cells, &mut self.grid_list);
+                self.grid_list.clear_all_highlight();
+                HISTORY_MANAGER.end_group();
+            },
+            Action::Paste | Action::PasteOpaque => {
+                // paste clipboard content to mouse position, the opaque paste makes
+                // the spaces cover what's underneath. Shapes copied from ASCII-d
+                // come back as shapes.
+                let opaque = action == Action::PasteOpaque;
+                let is_text =
+                    opaque || win_data.mode == DrawingTools::Table || !self.paste_shapes();
+                if let (true, Some(content)) =
+                    (is_text, Application::global().clipboard().get_string())
+                {
+                    if win_data.mode == DrawingTools::Table {
+                        self.paste_table(content);
+                    } else {
+                        self.paste_content(content, opaque);
+                    }
+                }
+            }
+            Action::PasteAsShapes => {
+                if let Some(content) = Application::global().clipboard().get_string() {
+                    self.paste_as_shapes(content, win_data);
+                }
+            }
+            Action::Duplicate => {
+                self.shape_list.duplicate_selected(&mut self.grid_list);
+            }
+            Action::Group => {
+                self.shape_list.group_selected();
+            }
+            Action::Ungroup => {
+                self.shape_list.ungroup_selected();
+            }
+            Action::BringForward
+            | Action::BringToFront
+            | Action::SendBackward
+            | Action::SendToBack => {
+                let order = match action {
+                    Action::BringForward => ZOrder::Forward,
+                    Action::BringToFront => ZOrder::Front,
+                    Action::SendBackward => ZOrder::Backward,
+                    _ => ZOrder::Back,
+                };
+                self.shape_list.reorder_selected(order, &mut self.grid_list);
+            }
+            Action::AlignLeft
+            | Action::AlignRight
+            | Action::AlignTop
+            | Action::AlignBottom
+            | Action::AlignCenter
+            | Action::AlignMiddle
+            | Action::DistributeHorizontally
+            | Action::DistributeVertically => {
+                let arrange = match action {
+                    Action::AlignLeft => Arrange::AlignLeft,
+                    Action::AlignRight => Arrange::AlignRight,
+                    Action::AlignTop => Arrange::AlignTop,
+                    Action::AlignBottom => Arrange::AlignBottom,
+                    Action::AlignCenter => Arrange::AlignCenter,
+                    Action::AlignMiddle => Arrange::AlignMiddle,
+                    Action::DistributeHorizontally => Arrange::DistributeHorizontally,
+                    _ => Arrange::DistributeVertically,
+                };
+                self.shape_list
+                    .arrange_selected(arrange, &mut self.grid_list);
+            }
+            Action::ConvertToShapes => {
+                self.convert_selection();
+            }
+            Action::FlipHorizontal => {
+                self.transform_selection(Transform::FlipHorizontal);
+            }
+            Action::FlipVertical => {
+                self.transform_selection(Transform::FlipVertical);
+            }
+            Action::RotateClockwise => {
+                self.transform_selection(Transform::RotateClockwise);
+            }
+            Action::RotateCounterClockwise => {
+                self.transform_selection(Transform::RotateCounterClockwise);
+            }
+            Action::InsertDirectoryTree => {
+                let directory_dialog_options = FileDialogOptions::new()
+                    .select_directories()
+                    .title("Insert directory tree")
+                    .button_text("Insert")
+                    .accept_command(INSERT_DIRECTORY_TREE_COMMAND);
+                ctx.submit_command(commands::SHOW_OPEN_PANEL.with(directory_dialog_options));
+            }
+            Action::InsertTree => {
+                // turn the clipboard outline, or directory path, into a tree
+                if let Some(content) = Application::global().clipboard().get_string() {
+                    let path = Path::new(content.trim());
+                    if path.is_dir() {
+                        match tree::read_directory(path) {
+                            Ok(root) => self.insert_tree(vec![root]),
+                            Err(e) => println!("Error reading directory: {e}"),
+                        }
+                    } else {
+                        self.insert_tree(tree::parse_outline(&content));
+                    }
+                }
+            }
+            Action::FillSelection => {
+                // The next typed character fills the selection
+                self.is_filling = self.grid_list.current_selection.is_some();
+            }
+            Action::Undo => unsafe {
+                HISTORY_MANAGER.undo(&mut self.grid_list, &mut self.shape_list);
+            },
+            Action::Redo => unsafe {
+                HISTORY_MANAGER.redo(&mut self.grid_list, &mut self.shape_list);
+            },
+        }
+    }
+
+    fn insert_tree(&mut self, roots: Vec<TreeNode>) {
+        if roots.is_empty() {
+            return;
+        }
+        let content = tree::render(&roots);
+        let size = (
+            content.lines().count(),
+            content
+                .lines()
+                .map(|line| line.chars().count())
+                .max()
+                .unwrap_or(0),
+        );
+        match fit_in_grid(size, self.mouse_position, self.grid_list.grid_size) {
+            Some((row, col)) => {
+                let block = BlockShape::new(row, col, content);
+                self.shape_list
+                    .insert_shapes(vec![Box::new(block)], &mut self.grid_list);
+            }
+            None => println!("Tree is too big to fit in the canvas"),
+        }
+    }
 }
+
+// Write to a temporary file next to it first and then move it in place, so a
+// failed write never leaves the file half written
+fn write_atomically(path: &Path, content: &str) -> io::Result<()> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Not a file path"))?;
+    let mut temp_name = OsString::from(".");
+    temp_name.push(file_name);
+    temp_name.push(".tmp");
+    let temp_path = path.with_file_name(temp_name);
+    let result = File::create(&temp_path)
+        .and_then(|mut file| {
+            file.write_all(content.as_bytes())?;
+            file.sync_all()
+        })
+        .and_then(|_| fs::rename(&temp_path, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
+}
+
+// The mouse events at the position they'd have on the unzoomed canvas
+fn unzoom(event: &Event, zoom: f64) -> Event {
+    let unzoom_mouse = |event: &MouseEvent| {
+        let mut event = event.clone();
+        event.pos = Point::new(event.pos.x / zoom, event.pos.y / zoom);
+        event
+    };
+    match event {
+        Event::MouseDown(event) => Event::MouseDown(unzoom_mouse(event)),
+        Event::MouseUp(event) => Event::MouseUp(unzoom_mouse(event)),
+        Event::MouseMove(event) => Event::MouseMove(unzoom_mouse(event)),
+        Event::Wheel(event) => Event::Wheel(unzoom_mouse(event)),
+        _ => event.clone(),
+    }
+}
+
 impl Widget<ApplicationState> for CanvasGrid {
     fn event(
         &mut self,
@@ -82,89 +854,142 @@ impl Widget<ApplicationState> for CanvasGrid {
             .windows
             .get_mut(&ctx.window_id())
             .expect("Invalid WindowID");
+        // The tools work on the canvas as if it wasn't zoomed
+        let unzoomed;
+        let event = if self.zoom != 1.0 {
+            unzoomed = unzoom(event, self.zoom);
+            &unzoomed
+        } else {
+            event
+        };
         match event {
             Event::WindowConnected => {
                 // Have to request focus in order to get keyboard event
                 ctx.request_focus();
+                let errors: Vec<&str> = SETTINGS
+                    .errors
+                    .iter()
+                    .chain(KEYMAP.errors.iter())
+                    .map(String::as_str)
+                    .collect();
+                if !errors.is_empty() {
+                    win_data.message = Some(format!(
+                        "Some settings couldn't be used:\n{}",
+                        errors.join("\n")
+                    ));
+                }
             }
             Event::KeyDown(event) => {
-                match event.code {
-                    Code::Escape => {
-                        win_data.mode = DrawingTools::Select;
-                    }
-                    keycode => {
-                        if win_data.mode != DrawingTools::Text {
-                            // Only handle shortcut key if not in text mode
-                            match keycode {
-                                Code::Digit1 | Code::KeyL | Code::KeyA => {
-                                    win_data.mode = DrawingTools::Line;
-                                }
-                                Code::Digit2 | Code::KeyR => {
-                                    win_data.mode = DrawingTools::Rect;
-                                }
-                                Code::Digit3 | Code::KeyT => {
-                                    win_data.mode = DrawingTools::Text;
-                                }
-                                Code::Digit4 | Code::KeyE => {
-                                    win_data.mode = DrawingTools::Eraser;
+                // Nothing can be changed on a hidden or locked layer
+                let is_editable = self.layers.is_editable();
+                if self.is_filling {
+                    self.is_filling = false;
+                    if let KbKey::Character(c) = &event.key {
+                        if !(event.mods.meta() || event.mods.ctrl()) {
+                            if let Some(c) = c.chars().next() {
+                                unsafe {
+                                    HISTORY_MANAGER.begin_group();
                                 }
-                                Code::Delete | Code::Backspace => {
-                                    self.grid_list.erase_highlighted();
-                                    self.grid_list.clear_all_highlight();
+                                let cells = self.grid_list.fill_highlighted(c);
+                                self.shape_list.detach_shapes(&cells, &mut self.grid_list);
+                                unsafe {
+                                    HISTORY_MANAGER.end_group();
                                 }
-                                _ => {}
                             }
-
-                            if event.mods.meta() || event.mods.ctrl() {
-                                match keycode {
-                                    Code::KeyD => unsafe {
-                                        if event.mods.shift() {
-                                            CURRENT_THEME.toggle_theme();
-                                        }
-                                    },
-                                    Code::KeyC => {
-                                        // copy current diagram to clipboard
-                                        Application::global()
-                                            .clipboard()
-                                            .put_string(self.grid_list.get_highlighted_content());
-                                    }
-                                    Code::KeyX => {
-                                        // cut current diagram to clipboard
-                                        Application::global()
-                                            .clipboard()
-                                            .put_string(self.grid_list.get_highlighted_content());
-                                        self.grid_list.erase_highlighted();
-                                        self.grid_list.clear_all_highlight();
-                                    }
-                                    Code::KeyV => {
-                                        // paste clipboard content to mouse position
-                                        if let Some(content) =
-                                            Application::global().clipboard().get_string()
-                                        {
-                                            let (row, col) = self.mouse_position;
-                                            self.grid_list.load_content_at(content, row, col);
-                                        }
-                                    }
-                                    Code::KeyN => {
-                                        ctx.submit_command(NEW_FILE);
-                                    }
-                                    Code::KeyZ => unsafe {
-                                        if event.mods.shift() {
-                                            // Redo
-                                            HISTORY_MANAGER.redo(&mut self.grid_list);
-                                        } else {
-                                            // Undo
-                                            HISTORY_MANAGER.undo(&mut self.grid_list);
-                                        }
-                                    },
-                                    _ => {}
-                                }
+                            ctx.request_update();
+                            return;
+                        }
+                    }
+                }
+                let (is_typing, takes_characters) = self.takes_characters(win_data.mode);
+                let action = match self.vim.as_mut() {
+                    Some(vim) if self.keyboard_mode => unsafe {
+                        HISTORY_MANAGER.begin_group();
+                        let action = vim.handle_key(
+                            event,
+                            self.mouse_position,
+                            is_typing,
+                            takes_characters,
+                            is_editable,
+                            &mut self.grid_list,
+                        );
+                        // Editing the cells of a shape leaves it as text
+                        let cells = std::mem::take(&mut vim.changed);
+                        self.shape_list.detach_shapes(&cells, &mut self.grid_list);
+                        HISTORY_MANAGER.end_group();
+                        action
+                    },
+                    _ => VimAction::Ignored,
+                };
+                if action != VimAction::Ignored {
+                    match action {
+                        VimAction::Ignored | VimAction::Handled => {}
+                        VimAction::MoveTo(cell) => {
+                            self.move_cursor(ctx, cell, event.mods);
+                            if let Some(vim) = self.vim.as_ref() {
+                                vim.highlight(self.mouse_position, &mut self.grid_list);
                             }
                         }
+                        VimAction::Insert(cell) => {
+                            self.release_at_cursor(ctx, event.mods);
+                            self.mouse_position = cell;
+                            win_data.mode = DrawingTools::Text;
+                            self.type_at_cursor(ctx, event.mods);
+                        }
+                        VimAction::StopInsert(cell) => {
+                            self.mouse_position = cell;
+                            win_data.mode = DrawingTools::Select;
+                        }
+                        VimAction::Undo(count) if is_editable => unsafe {
+                            for _ in 0..count {
+                                HISTORY_MANAGER.undo(&mut self.grid_list, &mut self.shape_list);
+                            }
+                        },
+                        VimAction::Redo(count) if is_editable => unsafe {
+                            for _ in 0..count {
+                                HISTORY_MANAGER.redo(&mut self.grid_list, &mut self.shape_list);
+                            }
+                        },
+                        VimAction::Undo(_) | VimAction::Redo(_) => {}
+                    }
+                    ctx.request_update();
+                    return;
+                }
+                if self.keyboard_mode && self.handle_cursor_key(ctx, event, win_data.mode) {
+                    ctx.request_update();
+                    return;
+                }
+                let mode = win_data.mode;
+                // Escape and the function keys work everywhere, the other shortcuts
+                // not while typing, and the ones without Ctrl not while the tool
+                // takes typed characters
+                let is_plain = !(event.mods.meta() || event.mods.ctrl());
+                let action = KEYMAP.action(event).filter(|action| match action {
+                    Action::Cancel | Action::ToggleKeyboardMode | Action::ToggleLayers => true,
+                    _ if mode == DrawingTools::Text => false,
+                    _ => {
+                        !is_plain
+                            || !matches!(
+                                mode,
+                                DrawingTools::Table | DrawingTools::Freehand | DrawingTools::Fill
+                            )
+                    }
+                });
+                if let Some(action) = action {
+                    self.perform(ctx, action, win_data);
+                }
+                if is_editable {
+                    self.tool_manager
+                        .input(ctx, event, &mut self.shape_list, &mut self.grid_list);
+                }
+                if self.keyboard_mode && mode != win_data.mode {
+                    self.release_at_cursor(ctx, event.mods);
+                    self.region_anchor = None;
+                    if win_data.mode == DrawingTools::Text && is_editable {
+                        // Start typing at the cursor right away
+                        self.type_at_cursor(ctx, event.mods);
                     }
                 }
-                self.tool_manager
-                    .input(ctx, event, &mut self.shape_list, &mut self.grid_list);
                 ctx.request_update();
             }
             Event::MouseMove(event) => {
@@ -172,7 +997,9 @@ impl Widget<ApplicationState> for CanvasGrid {
                     let mouse_row = (event.pos.y / cell_height) as usize;
                     let mouse_col = (event.pos.x / cell_width) as usize;
                     self.mouse_position = (mouse_row, mouse_col);
-                    if self.is_mouse_down {
+                    if self.is_placing {
+                        self.move_placing_block();
+                    } else if self.is_mouse_down {
                         self.tool_manager.draw(
                             ctx,
                             event,
@@ -184,18 +1011,67 @@ impl Widget<ApplicationState> for CanvasGrid {
                 }
             }
             Event::MouseDown(event) => {
+                if !self.layers.is_editable() {
+                    return;
+                }
                 self.is_mouse_down = true;
+                if self.is_placing {
+                    // The block is dropped on mouse up
+                    return;
+                }
                 self.tool_manager
                     .start(ctx, event, &mut self.shape_list, &mut self.grid_list);
             }
             Event::MouseUp(event) => {
+                if !self.layers.is_editable() {
+                    return;
+                }
                 self.is_mouse_down = false;
+                if self.is_placing {
+                    // Drop the shapes where they are
+                    self.drop_placing();
+                    ctx.request_update();
+                    return;
+                }
                 self.tool_manager
                     .end(ctx, event, &mut self.shape_list, &mut self.grid_list);
                 self.shape_list.commit(&mut self.grid_list);
                 ctx.request_update();
             }
             Event::Command(cmd) => {
+                if let Some(action) = cmd.get(ACTION_COMMAND) {
+                    self.perform(ctx, *action, win_data);
+                    ctx.request_update();
+                }
+                if let Some(content) = cmd.get(PLACE_BLOCK_COMMAND) {
+                    let (row, col) = self.mouse_position;
+                    let block = BlockShape::new(row, col, content.to_owned());
+                    if !self.start_placing(vec![Box::new(block)]) {
+                        println!("Stencil is too big to fit in the canvas");
+                    }
+                    ctx.request_update();
+                }
+                if cmd.is(CANCEL_PLACING_COMMAND) {
+                    self.cancel_placing();
+                    ctx.request_update();
+                }
+                if cmd.is(SAVE_STENCIL_COMMAND) && self.grid_list.current_selection.is_some() {
+                    let content = self
+                        .layers
+                        .flatten(&mut self.grid_list, false)
+                        .export_highlighted(&ExportFormat::Plain, true);
+                    if !content.trim().is_empty() {
+                        match stencil::save_user_stencil("Stencil", &content) {
+                            Ok(path) => {
+                                win_data.show_library = true;
+                                ctx.submit_command(STENCIL_SAVED_COMMAND.with(path));
+                            }
+                            Err(e) => {
+                                println!("Error saving stencil: {e}");
+                            }
+                        }
+                    }
+                }
                 if let Some(point) = cmd.get(SELECTION_START_COMMAND) {
                     self.selection_range.set_start(*point);
                 }
@@ -205,27 +1081,99 @@ impl Widget<ApplicationState> for CanvasGrid {
                 if let Some(_point) = cmd.get(SELECTION_END_COMMAND) {
                     if let Some(rect) = self.selection_range.as_rect() {
                         self.grid_list.highlight_rect(rect);
+                        // Shapes that are completely inside the selection are selected too
+                        for index in self
+                            .shape_list
+                            .find_shape_in_rect(rect, &mut self.grid_list)
+                        {
+                            if !self.shape_list.selected.contains(&index) {
+                                self.shape_list.selected.push(index);
+                            }
+                        }
                     } else {
                         self.grid_list.clear_all_highlight();
                     }
-                    // TODO: Visually highlight selected shapes, and make them movable
                     self.selection_range.discard();
                 }
                 if let Some(file_info) = cmd.get(commands::SAVE_FILE_AS) {
                     println!("Save File {:?}", file_info.path());
-                    if let Ok(mut file) = File::create(file_info.path()) {
-                        let _ = file.write_all(self.grid_list.to_string().as_bytes());
-                        if let Some(file_name) =
-                            file_info.path().to_str().and_then(|s| Some(s.to_string()))
-                        {
+                    // Hidden layers are part of the document, they're saved too
+                    let content = self.layers.flatten(&mut self.grid_list, true).to_string();
+                    let file_name = file_info.path().to_string_lossy().to_string();
+                    match write_atomically(file_info.path(), &content) {
+                        Ok(_) => {
                             win_data.current_file = Some(file_name.clone());
                             ctx.window().set_title(file_name.as_str());
+                            add_recent_file(&mut data.recent_files, file_name);
+                        }
+                        Err(e) => {
+                            println!("Error saving file: {e}");
+                            win_data.message = Some(format!("Couldn't save {file_name}: {e}"));
+                        }
+                    }
+                }
+                if let Some(format) = cmd.get(COPY_AS_COMMAND) {
+                    let content = self
+                        .layers
+                        .flatten(&mut self.grid_list, false)
+                        .export_highlighted(format, win_data.trim_whitespace);
+                    Application::global().clipboard().put_string(content);
+                }
+                if let Some(format) = cmd.get(EXPORT_AS_COMMAND) {
+                    let file_spec = format.file_spec();
+                    let export_dialog_options = FileDialogOptions::new()
+                        .allowed_types(vec![file_spec])
+                        .default_type(file_spec)
+                        .default_name(format!("diagram.{}", file_spec.extensions[0]))
+                        .name_label("Destination")
+                        .title(format!("Export diagram as {}", format))
+                        .button_text("Export")
+                        .accept_command(EXPORT_FILE_COMMAND);
+                    self.export_format = Some(format.clone());
+                    ctx.submit_command(commands::SHOW_SAVE_PANEL.with(export_dialog_options));
+                }
+                if let Some(file_info) = cmd.get(EXPORT_FILE_COMMAND) {
+                    if let Some(format) = self.export_format.take() {
+                        let content = self
+                            .layers
+                            .flatten(&mut self.grid_list, false)
+                            .export(&format, win_data.trim_whitespace);
+                        if let Err(e) = write_atomically(file_info.path(), &content) {
+                            println!("Error exporting file: {e}");
+                            win_data.message = Some(format!(
+                                "Couldn't export {}: {e}",
+                                file_info.path().to_string_lossy()
+                            ));
+                        }
+                    }
+                }
+                if let Some(action) = cmd.get(LAYER_COMMAND) {
+                    self.cancel_placing();
+                    self.layers
+                        .apply(action, &mut self.grid_list, &mut self.shape_list);
+                    win_data.layers = self.layers.infos();
+                    win_data.active_layer = self.layers.active;
+                    ctx.request_update();
+                }
+                if let Some(file_info) = cmd.get(INSERT_DIRECTORY_TREE_COMMAND) {
+                    match tree::read_directory(file_info.path()) {
+                        Ok(root) => self.insert_tree(vec![root]),
+                        Err(e) => {
+                            println!("Error reading directory: {e}");
                         }
                     }
                 }
                 if let Some(file_info) = cmd.get(commands::OPEN_FILE) {
                     match std::fs::read_to_string(file_info.path()) {
                         Ok(content) => {
+                            // The file is loaded as a single layer, with nothing to undo
+                            unsafe {
+                                HISTORY_MANAGER.reset();
+                            }
+                            self.layers = LayerList::new();
+                            win_data.layers = self.layers.infos();
+                            win_data.active_layer = self.layers.active;
+                            self.shape_list = ShapeList::new();
                             self.grid_list.clear_all();
                             self.grid_list.load_content(content);
                             if let Some(file_name) =
@@ -233,10 +1181,15 @@ impl Widget<ApplicationState> for CanvasGrid {
                             {
                                 win_data.current_file = Some(file_name.clone());
                                 ctx.window().set_title(file_name.as_str());
+                                add_recent_file(&mut data.recent_files, file_name);
                             }
                         }
                         Err(e) => {
                             println!("Error opening file: {e}");
+                            win_data.message = Some(format!(
+                                "Couldn't open {}: {e}",
+                                file_info.path().to_string_lossy()
+                            ));
                         }
                     }
                 }
@@ -275,10 +1228,14 @@ impl Widget<ApplicationState> for CanvasGrid {
             .expect("Invalid WindowID");
         if old_win_data.mode != win_data.mode {
             self.tool_manager.set_tool(win_data.mode);
+            self.shape_list.selected.clear();
             if old_win_data.mode == DrawingTools::Text {
                 self.shape_list.commit_all(&mut self.grid_list);
                 self.grid_list.clear_all_highlight();
             }
+            if old_win_data.mode == DrawingTools::Table {
+                self.grid_list.clear_all_highlight();
+            }
 
             match win_data.mode {
                 DrawingTools::Select => ctx.set_cursor(&Cursor::Arrow),
@@ -286,6 +1243,9 @@ impl Widget<ApplicationState> for CanvasGrid {
                 DrawingTools::Rect => ctx.set_cursor(&Cursor::Crosshair),
                 DrawingTools::Text => ctx.set_cursor(&Cursor::IBeam),
                 DrawingTools::Eraser => ctx.set_cursor(&Cursor::Crosshair),
+                DrawingTools::Table => ctx.set_cursor(&Cursor::Crosshair),
+                DrawingTools::Freehand => ctx.set_cursor(&Cursor::Crosshair),
+                DrawingTools::Fill => ctx.set_cursor(&Cursor::Crosshair),
             }
         }
     }
@@ -306,8 +1266,8 @@ impl Widget<ApplicationState> for CanvasGrid {
         self.grid_text.rebuild_if_needed(ctx.text(), env);
         self.grid_preview.rebuild_if_needed(ctx.text(), env);
         Size {
-            width: self.width,
-            height: self.height,
+            width: self.width * self.zoom,
+            height: self.height * self.zoom,
         }
     }
 
@@ -317,10 +1277,14 @@ impl Widget<ApplicationState> for CanvasGrid {
             .get(&ctx.window_id())
             .expect("Invalid WindowID");
         let current_theme = unsafe { CURRENT_THEME.current() };
-        let bound = ctx.region().bounding_box();
+        let bound = ctx
+            .region()
+            .bounding_box()
+            .scale_from_origin(1.0 / self.zoom);
         let brush = ctx.solid_brush(current_theme.bg);
         let preview_brush = ctx.solid_brush(current_theme.preview);
         ctx.with_save(|ctx| {
+            ctx.transform(Affine::scale(self.zoom));
             ctx.clip(bound);
             ctx.fill(bound, &brush);
             let grid_brush = ctx.solid_brush(current_theme.grid);
@@ -341,21 +1305,23 @@ impl Widget<ApplicationState> for CanvasGrid {
                 let cols = (self.width / cell_width) as usize;
                 let _rows = (self.height / cell_height) as usize;
 
-                for row in (start.1)..=(end.1) {
-                    let row = row as f64;
-                    let line = Line::new(
-                        Point::new(bound.x0, row * cell_height),
-                        Point::new(bound.x1, row * cell_height),
-                    );
-                    ctx.stroke(line, &grid_brush, 1.0);
-                }
-                for col in (start.0)..=(end.0) {
-                    let col = col as f64;
-                    let line = Line::new(
-                        Point::new(col * cell_width, bound.y0),
-                        Point::new(col * cell_width, bound.y1),
-                    );
-                    ctx.stroke(line, &grid_brush, 1.0);
+                if win_data.show_grid {
+                    for row in (start.1)..=(end.1) {
+                        let row = row as f64;
+                        let line = Line::new(
+                            Point::new(bound.x0, row * cell_height),
+                            Point::new(bound.x1, row * cell_height),
+                        );
+                        ctx.stroke(line, &grid_brush, 1.0);
+                    }
+                    for col in (start.0)..=(end.0) {
+                        let col = col as f64;
+                        let line = Line::new(
+                            Point::new(col * cell_width, bound.y0),
+                            Point::new(col * cell_width, bound.y1),
+                        );
+                        ctx.stroke(line, &grid_brush, 1.0);
+                    }
                 }
 
                 self.shape_list.draw(&mut self.grid_list);
@@ -374,6 +1340,9 @@ impl Widget<ApplicationState> for CanvasGrid {
                                 m_row * cell_height + cell_height,
                             );
                             ctx.fill(m_rect, &cursor_brush);
+                            if self.keyboard_mode {
+                                ctx.stroke(m_rect, &preview_brush, 1.5);
+                            }
                         }
 
                         if self.grid_list.get(i).highlighted {
@@ -386,15 +1355,22 @@ impl Widget<ApplicationState> for CanvasGrid {
                                 h_row * cell_height + cell_height,
                             );
 
-                            if win_data.mode != DrawingTools::Text {
+                            if win_data.mode != DrawingTools::Text
+                                && win_data.mode != DrawingTools::Table
+                            {
                                 ctx.fill(h_rect, &highlight_brush);
                             } else {
                                 ctx.stroke(h_rect, &preview_brush, 1.0);
                             }
                         }
 
-                        let (cell_content, cell_preview) = self.grid_list.get(i).read();
-                        if !cell_content.is_ascii_whitespace() {
+                        let cell = self.grid_list.get(i);
+                        // A space preview comes from an opaque block, it hides what's
+                        // under it
+                        let is_covered = cell.preview.is_some_and(|c| c.is_whitespace());
+                        let (cell_content, cell_preview) = cell.read();
+                        let cell_content = self.layers.content_at(i, cell_content);
+                        if !cell_content.is_ascii_whitespace() && !is_covered {
                             self.grid_text.set_text(cell_content.to_string());
                             self.grid_text.set_text_color(current_theme.fg);
                             self.grid_text.rebuild_if_needed(ctx.text(), env);
@@ -414,6 +1390,42 @@ impl Widget<ApplicationState> for CanvasGrid {
                 if let Some(rect) = self.selection_range.as_rect() {
                     ctx.fill(rect, &selection_brush);
                 }
+
+                for ((top, left), (bottom, right)) in self.shape_list.selected_bounds() {
+                    let outline = Rect::new(
+                        left as f64 * cell_width,
+                        top as f64 * cell_height,
+                        (right + 1) as f64 * cell_width,
+                        (bottom + 1) as f64 * cell_height,
+                    );
+                    ctx.stroke(outline.inflate(2.0, 2.0), &preview_brush, 1.5);
+                }
+
+                // The cells the eraser brush clears around the mouse
+                if win_data.mode == DrawingTools::Eraser {
+                    let radius = (self.tool_manager.brush_size() / 2) as f64;
+                    let brush_outline = Rect::new(
+                        (m_col as f64 - radius) * cell_width,
+                        (m_row as f64 - radius) * cell_height,
+                        (m_col as f64 + radius + 1.0) * cell_width,
+                        (m_row as f64 + radius + 1.0) * cell_height,
+                    );
+                    ctx.stroke(brush_outline, &preview_brush, 1.0);
+                }
+
+                for guide in self.shape_list.guides.iter() {
+                    let line = match guide {
+                        Guide::Row(row) => Line::new(
+                            Point::new(bound.x0, row * cell_height),
+                            Point::new(bound.x1, row * cell_height),
+                        ),
+                        Guide::Column(col) => Line::new(
+                            Point::new(col * cell_width, bound.y0),
+                            Point::new(col * cell_width, bound.y1),
+                        ),
+                    };
+                    ctx.stroke(line, &cursor_brush, 1.0);
+                }
             }
         });
     }
@@ -0,0 +1,348 @@
+use druid::{
+    Color, Event, FontDescriptor, FontFamily, FontWeight, KbKey, Point, Rect, RenderContext, Size,
+    TextLayout, Widget,
+};
+
+use crate::{
+    consts::LAYER_COMMAND,
+    data::{
+        layers::{LayerAction, LayerInfo},
+        ApplicationState,
+    },
+};
+
+const PANEL_WIDTH: f64 = 180.0;
+const PANEL_MARGIN: f64 = 16.0;
+const ROW_HEIGHT: f64 = 26.0;
+const ITEM_PADDING: f64 = 8.0;
+const ICON_WIDTH: f64 = 18.0;
+
+// The buttons in the header of the panel, from right to left
+const HEADER_BUTTONS: [(&str, LayerAction); 4] = [
+    ("↓", LayerAction::MoveDown),
+    ("↑", LayerAction::MoveUp),
+    ("−", LayerAction::Remove),
+    ("+", LayerAction::Add),
+];
+
+pub struct LayersPanel {
+    layers: Vec<LayerInfo>,
+    active: usize,
+    title: TextLayout<String>,
+    buttons: Vec<TextLayout<String>>,
+    // Visible, hidden, locked and unlocked icons
+    icons: [TextLayout<String>; 4],
+    labels: Vec<TextLayout<String>>,
+    panel_rect: Rect,
+    // The layer being renamed, and the name typed so far
+    renaming: Option<(usize, String)>,
+}
+
+fn text_layout(text: &str) -> TextLayout<String> {
+    let mut layout = TextLayout::<String>::new();
+    layout.set_font(
+        FontDescriptor::new(FontFamily::SYSTEM_UI)
+            .with_weight(FontWeight::REGULAR)
+            .with_size(12.0),
+    );
+    layout.set_text_color(Color::from_hex_str("#e0e0e0").unwrap());
+    layout.set_text(text.to_string());
+    layout
+}
+
+impl LayersPanel {
+    pub fn new() -> Self {
+        LayersPanel {
+            layers: vec![],
+            active: 0,
+            title: text_layout("Layers"),
+            buttons: HEADER_BUTTONS
+                .iter()
+                .map(|(label, _)| text_layout(label))
+                .collect(),
+            icons: [
+                text_layout("●"),
+                text_layout("○"),
+                text_layout("▣"),
+                text_layout("□"),
+            ],
+            labels: vec![],
+            panel_rect: Rect::ZERO,
+            renaming: None,
+        }
+    }
+
+    fn reload(&mut self, layers: &[LayerInfo], active: usize) {
+        self.layers = layers.to_vec();
+        self.active = active;
+        self.renaming = None;
+        self.labels = self
+            .layers
+            .iter()
+            .map(|layer| text_layout(&layer.name))
+            .collect();
+    }
+
+    fn header_rect(&self) -> Rect {
+        Rect::new(
+            self.panel_rect.x0,
+            self.panel_rect.y0,
+            self.panel_rect.x1,
+            self.panel_rect.y0 + ROW_HEIGHT,
+        )
+    }
+
+    fn button_rect(&self, index: usize) -> Rect {
+        let header = self.header_rect();
+        let x1 = header.x1 - ITEM_PADDING / 2.0 - index as f64 * ROW_HEIGHT;
+        Rect::new(x1 - ROW_HEIGHT, header.y0, x1, header.y1)
+    }
+
+    // The top most layer is shown first
+    fn row_rect(&self, layer: usize) -> Rect {
+        let row = self.layers.len() - 1 - layer;
+        let y0 = self.panel_rect.y0 + ROW_HEIGHT * (row + 1) as f64;
+        Rect::new(self.panel_rect.x0, y0, self.panel_rect.x1, y0 + ROW_HEIGHT)
+    }
+
+    fn layer_at(&self, pos: Point) -> Option<usize> {
+        (0..self.layers.len()).find(|layer| self.row_rect(*layer).contains(pos))
+    }
+
+    fn action_at(&mut self, pos: Point, count: u8) -> Option<LayerAction> {
+        if let Some(index) = (0..HEADER_BUTTONS.len()).find(|i| self.button_rect(*i).contains(pos))
+        {
+            return Some(HEADER_BUTTONS[index].1.clone());
+        }
+        let layer = self.layer_at(pos)?;
+        let x = pos.x - self.panel_rect.x0 - ITEM_PADDING;
+        if x < ICON_WIDTH {
+            Some(LayerAction::ToggleVisible(layer))
+        } else if x < ICON_WIDTH * 2.0 {
+            Some(LayerAction::ToggleLocked(layer))
+        } else if count >= 2 {
+            self.start_renaming(layer);
+            None
+        } else {
+            Some(LayerAction::Select(layer))
+        }
+    }
+
+    fn start_renaming(&mut self, layer: usize) {
+        let name = self.layers[layer].name.to_owned();
+        self.labels[layer].set_text(format!("{}|", name));
+        self.renaming = Some((layer, name));
+    }
+}
+
+fn is_visible(window_id: druid::WindowId, data: &ApplicationState) -> bool {
+    data.windows
+        .get(&window_id)
+        .is_some_and(|win_data| win_data.show_layers)
+}
+
+impl Widget<ApplicationState> for LayersPanel {
+    fn event(
+        &mut self,
+        ctx: &mut druid::EventCtx,
+        event: &druid::Event,
+        data: &mut ApplicationState,
+        _env: &druid::Env,
+    ) {
+        if !is_visible(ctx.window_id(), data) {
+            return;
+        }
+        // Prevent the mouse event to be propagated to the canvas
+        match event {
+            Event::MouseDown(event) if self.panel_rect.contains(event.pos) => {
+                if let Some((layer, _)) = self.renaming.take() {
+                    self.labels[layer].set_text(self.layers[layer].name.to_owned());
+                }
+                if let Some(action) = self.action_at(event.pos, event.count) {
+                    ctx.submit_command(LAYER_COMMAND.with(action));
+                }
+                ctx.request_paint();
+                ctx.set_handled();
+            }
+            Event::MouseMove(event) | Event::MouseUp(event)
+                if self.panel_rect.contains(event.pos) =>
+            {
+                ctx.set_handled();
+            }
+            Event::KeyDown(event) => {
+                if let Some((layer, name)) = self.renaming.as_mut() {
+                    let layer = *layer;
+                    match &event.key {
+                        KbKey::Character(c) => {
+                            name.push_str(c);
+                        }
+                        KbKey::Backspace => {
+                            name.pop();
+                        }
+                        KbKey::Enter => {
+                            if !name.trim().is_empty() {
+                                ctx.submit_command(
+                                    LAYER_COMMAND
+                                        .with(LayerAction::Rename(layer, name.trim().to_string())),
+                                );
+                            }
+                            self.renaming = None;
+                        }
+                        KbKey::Escape => {
+                            self.renaming = None;
+                        }
+                        _ => {}
+                    }
+                    match self.renaming.as_ref() {
+                        Some((_, name)) => self.labels[layer].set_text(format!("{}|", name)),
+                        None => self.labels[layer].set_text(self.layers[layer].name.to_owned()),
+                    }
+                    ctx.request_layout();
+                    ctx.request_paint();
+                    // Typing a name shouldn't trigger the canvas shortcuts
+                    ctx.set_handled();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn lifecycle(
+        &mut self,
+        ctx: &mut druid::LifeCycleCtx,
+        event: &druid::LifeCycle,
+        data: &ApplicationState,
+        _env: &druid::Env,
+    ) {
+        if let druid::LifeCycle::WidgetAdded = event {
+            if let Some(win_data) = data.windows.get(&ctx.window_id()) {
+                self.reload(&win_data.layers, win_data.active_layer);
+            }
+        }
+    }
+
+    fn update(
+        &mut self,
+        ctx: &mut druid::UpdateCtx,
+        old_data: &ApplicationState,
+        data: &ApplicationState,
+        _env: &druid::Env,
+    ) {
+        let win_data = data
+            .windows
+            .get(&ctx.window_id())
+            .expect("Invalid WindowID");
+        if win_data.layers != self.layers || win_data.active_layer != self.active {
+            self.reload(&win_data.layers, win_data.active_layer);
+            ctx.request_layout();
+            ctx.request_paint();
+        }
+        if is_visible(ctx.window_id(), old_data) != is_visible(ctx.window_id(), data) {
+            self.renaming = None;
+            ctx.request_layout();
+            ctx.request_paint();
+        }
+    }
+
+    fn layout(
+        &mut self,
+        ctx: &mut druid::LayoutCtx,
+        bc: &druid::BoxConstraints,
+        _data: &ApplicationState,
+        env: &druid::Env,
+    ) -> Size {
+        self.panel_rect = Rect::new(
+            PANEL_MARGIN,
+            PANEL_MARGIN,
+            PANEL_MARGIN + PANEL_WIDTH,
+            PANEL_MARGIN + ROW_HEIGHT * (self.layers.len() + 1) as f64,
+        );
+        self.title.rebuild_if_needed(ctx.text(), env);
+        for button in self.buttons.iter_mut() {
+            button.rebuild_if_needed(ctx.text(), env);
+        }
+        for layout in self.icons.iter_mut().chain(self.labels.iter_mut()) {
+            layout.rebuild_if_needed(ctx.text(), env);
+        }
+        bc.max()
+    }
+
+    fn paint(&mut self, ctx: &mut druid::PaintCtx, data: &ApplicationState, env: &druid::Env) {
+        if !is_visible(ctx.window_id(), data) {
+            return;
+        }
+        let brush = ctx.solid_brush(Color::from_hex_str("#333333").unwrap());
+        let stroke_brush = ctx.solid_brush(Color::from_hex_str("#4c4c4c").unwrap());
+        let shadow_brush = ctx.solid_brush(Color::rgba(0.0, 0.0, 0.0, 0.55));
+        let active_brush = ctx.solid_brush(Color::from_hex_str("#404040").unwrap());
+        let rename_brush = ctx.solid_brush(Color::from_hex_str("#549bf5").unwrap());
+        let rect = self.panel_rect;
+        ctx.blurred_rect(rect, 5.0, &shadow_brush);
+        ctx.fill(rect.to_rounded_rect(5.0), &brush);
+        ctx.stroke(rect.to_rounded_rect(5.0), &stroke_brush, 1.0);
+
+        let header = self.header_rect();
+        let title_height = self.title.size().height;
+        self.title.draw(
+            ctx,
+            (
+                header.x0 + ITEM_PADDING,
+                header.y0 + (ROW_HEIGHT - title_height) / 2.0,
+            ),
+        );
+        for i in 0..self.buttons.len() {
+            let button_rect = self.button_rect(i);
+            let button = &mut self.buttons[i];
+            button.rebuild_if_needed(ctx.text(), env);
+            let size = button.size();
+            button.draw(
+                ctx,
+                (
+                    button_rect.x0 + (button_rect.width() - size.width) / 2.0,
+                    button_rect.y0 + (button_rect.height() - size.height) / 2.0,
+                ),
+            );
+        }
+        ctx.stroke(
+            druid::kurbo::Line::new((header.x0, header.y1), (header.x1, header.y1)),
+            &stroke_brush,
+            1.0,
+        );
+
+        let renaming = self.renaming.as_ref().map(|(layer, _)| *layer);
+        for layer in 0..self.layers.len() {
+            let row = self.row_rect(layer);
+            if layer == self.active {
+                ctx.fill(row, &active_brush);
+            }
+            // The visibility and lock state are shown in front of the layer name
+            let info = &self.layers[layer];
+            let visible_icon = if info.visible { 0 } else { 1 };
+            let locked_icon = if info.locked { 2 } else { 3 };
+            for (column, icon) in [visible_icon, locked_icon].iter().enumerate() {
+                let icon = &mut self.icons[*icon];
+                icon.rebuild_if_needed(ctx.text(), env);
+                let height = icon.size().height;
+                icon.draw(
+                    ctx,
+                    (
+                        row.x0 + ITEM_PADDING + ICON_WIDTH * column as f64,
+                        row.y0 + (ROW_HEIGHT - height) / 2.0,
+                    ),
+                );
+            }
+            let label = &mut self.labels[layer];
+            label.rebuild_if_needed(ctx.text(), env);
+            let label_origin = Point::new(
+                row.x0 + ITEM_PADDING + ICON_WIDTH * 2.0,
+                row.y0 + (ROW_HEIGHT - label.size().height) / 2.0,
+            );
+            if renaming == Some(layer) {
+                let label_rect =
+                    Rect::from_origin_size(label_origin, label.size()).inflate(3.0, 1.0);
+                ctx.stroke(label_rect, &rename_brush, 1.0);
+            }
+            label.draw(ctx, label_origin);
+        }
+    }
+}
@@ -0,0 +1,396 @@
+use druid::{
+    Color, Event, FontDescriptor, FontFamily, FontWeight, KbKey, Point, Rect, RenderContext, Size,
+    TextLayout, Widget,
+};
+
+use crate::{
+    consts::{CANCEL_PLACING_COMMAND, PLACE_BLOCK_COMMAND, STENCIL_SAVED_COMMAND},
+    data::{
+        stencil::{all_stencils, delete_user_stencil, is_valid_name, rename_user_stencil, Stencil},
+        ApplicationState,
+    },
+};
+
+const PANEL_WIDTH: f64 = 200.0;
+const PANEL_MARGIN: f64 = 16.0;
+// Keep the panel clear of the toolbar at the bottom of the window
+const PANEL_BOTTOM: f64 = 70.0;
+const ITEM_PADDING: f64 = 8.0;
+const DELETE_BUTTON_SIZE: f64 = 14.0;
+
+pub struct LibraryPanel {
+    stencils: Vec<Stencil>,
+    labels: Vec<TextLayout<String>>,
+    previews: Vec<TextLayout<String>>,
+    delete_label: TextLayout<String>,
+    panel_rect: Rect,
+    item_rects: Vec<Rect>,
+    content_height: f64,
+    scroll_offset: f64,
+    hovered: Option<usize>,
+    is_dragging: bool,
+    has_left_panel: bool,
+    // The user stencil being renamed, and the name typed so far
+    renaming: Option<(usize, String)>,
+    scroll_to_renaming: bool,
+}
+
+impl LibraryPanel {
+    pub fn new() -> Self {
+        let mut delete_label = TextLayout::<String>::new();
+        delete_label.set_font(
+            FontDescriptor::new(FontFamily::SYSTEM_UI)
+                .with_weight(FontWeight::REGULAR)
+                .with_size(12.0),
+        );
+        delete_label.set_text_color(Color::from_hex_str("#e0e0e0").unwrap());
+        delete_label.set_text("×".to_string());
+        let mut panel = LibraryPanel {
+            stencils: vec![],
+            labels: vec![],
+            previews: vec![],
+            delete_label,
+            panel_rect: Rect::ZERO,
+            item_rects: vec![],
+            content_height: 0.0,
+            scroll_offset: 0.0,
+            hovered: None,
+            is_dragging: false,
+            has_left_panel: false,
+            renaming: None,
+            scroll_to_renaming: false,
+        };
+        panel.reload();
+        panel
+    }
+
+    pub fn reload(&mut self) {
+        let label_font = FontDescriptor::new(FontFamily::SYSTEM_UI)
+            .with_weight(FontWeight::REGULAR)
+            .with_size(12.0);
+        let preview_font = FontDescriptor::new(FontFamily::MONOSPACE)
+            .with_weight(FontWeight::REGULAR)
+            .with_size(9.0);
+        self.stencils = all_stencils();
+        self.labels = vec![];
+        self.previews = vec![];
+        for stencil in self.stencils.iter() {
+            let mut label = TextLayout::<String>::new();
+            label.set_font(label_font.clone());
+            label.set_text_color(Color::from_hex_str("#e0e0e0").unwrap());
+            label.set_text(stencil.name.to_owned());
+            self.labels.push(label);
+
+            let mut preview = TextLayout::<String>::new();
+            preview.set_font(preview_font.clone());
+            preview.set_text_color(Color::from_hex_str("#a0a0a0").unwrap());
+            preview.set_text(stencil.content.to_owned());
+            self.previews.push(preview);
+        }
+        self.hovered = None;
+        self.renaming = None;
+    }
+
+    fn item_at(&self, pos: Point) -> Option<usize> {
+        if !self.panel_rect.contains(pos) {
+            return None;
+        }
+        let pos = Point::new(pos.x, pos.y + self.scroll_offset);
+        self.item_rects.iter().position(|rect| rect.contains(pos))
+    }
+
+    fn delete_button_at(&self, index: usize, pos: Point) -> bool {
+        if self.stencils[index].path.is_none() {
+            return false;
+        }
+        let item = self.item_rects[index];
+        let button = Rect::new(
+            item.x1 - ITEM_PADDING - DELETE_BUTTON_SIZE,
+            item.y0 + ITEM_PADDING,
+            item.x1 - ITEM_PADDING,
+            item.y0 + ITEM_PADDING + DELETE_BUTTON_SIZE,
+        );
+        button.contains(Point::new(pos.x, pos.y + self.scroll_offset))
+    }
+
+    fn start_renaming(&mut self, index: usize) {
+        if self.stencils[index].path.is_some() {
+            let name = self.stencils[index].name.to_owned();
+            self.labels[index].set_text(format!("{}|", name));
+            self.renaming = Some((index, name));
+            self.scroll_to_renaming = true;
+        }
+    }
+
+    fn stop_renaming(&mut self, accept: bool) {
+        if let Some((index, name)) = self.renaming.take() {
+            if accept && is_valid_name(&name) {
+                match rename_user_stencil(&self.stencils[index], &name) {
+                    Ok(_) => {
+                        self.reload();
+                        return;
+                    }
+                    Err(e) => {
+                        println!("Error renaming stencil: {e}");
+                    }
+                }
+            }
+            self.labels[index].set_text(self.stencils[index].name.to_owned());
+        }
+    }
+}
+
+fn is_visible(window_id: druid::WindowId, data: &ApplicationState) -> bool {
+    data.windows
+        .get(&window_id)
+        .is_some_and(|win_data| win_data.show_library)
+}
+
+impl Widget<ApplicationState> for LibraryPanel {
+    fn event(
+        &mut self,
+        ctx: &mut druid::EventCtx,
+        event: &druid::Event,
+        data: &mut ApplicationState,
+        _env: &druid::Env,
+    ) {
+        if let Event::Command(cmd) = event {
+            if let Some(path) = cmd.get(STENCIL_SAVED_COMMAND) {
+                // Ask for a name right away
+                self.reload();
+                if let Some(index) = self
+                    .stencils
+                    .iter()
+                    .position(|stencil| stencil.path.as_ref() == Some(path))
+                {
+                    self.start_renaming(index);
+                }
+                ctx.request_layout();
+                ctx.request_paint();
+            }
+        }
+        if !is_visible(ctx.window_id(), data) {
+            return;
+        }
+        // Prevent the mouse event to be propagated to underlying widgets, except
+        // when a stencil is being dragged onto the canvas
+        match event {
+            Event::MouseDown(event) => {
+                if let Some(index) = self.item_at(event.pos) {
+                    if self.renaming.as_ref().is_some_and(|(i, _)| *i != index) {
+                        self.stop_renaming(true);
+                    }
+                    if self.delete_button_at(index, event.pos) {
+                        match delete_user_stencil(&self.stencils[index]) {
+                            Ok(_) => self.reload(),
+                            Err(e) => println!("Error deleting stencil: {e}"),
+                        }
+                        ctx.request_layout();
+                    } else if event.count >= 2 {
+                        ctx.submit_command(CANCEL_PLACING_COMMAND);
+                        self.is_dragging = false;
+                        self.start_renaming(index);
+                    } else if self.renaming.is_none() {
+                        ctx.submit_command(
+                            PLACE_BLOCK_COMMAND.with(self.stencils[index].content.to_owned()),
+                        );
+                        self.is_dragging = true;
+                        self.has_left_panel = false;
+                    }
+                    ctx.request_paint();
+                }
+                if self.panel_rect.contains(event.pos) {
+                    ctx.set_handled();
+                }
+            }
+            Event::MouseMove(event) => {
+                let hovered = self.item_at(event.pos);
+                if hovered != self.hovered {
+                    self.hovered = hovered;
+                    ctx.request_paint();
+                }
+                if !self.panel_rect.contains(event.pos) {
+                    self.has_left_panel = true;
+                } else if !self.is_dragging {
+                    ctx.set_handled();
+                }
+            }
+            Event::MouseUp(event) => {
+                if self.panel_rect.contains(event.pos) {
+                    // A plain click keeps the stencil following the mouse until it's
+                    // dropped with another click, dragging it back to the panel
+                    // cancels it
+                    if self.is_dragging && self.has_left_panel {
+                        ctx.submit_command(CANCEL_PLACING_COMMAND);
+                    }
+                    ctx.set_handled();
+                }
+                self.is_dragging = false;
+            }
+            Event::Wheel(event) if self.panel_rect.contains(event.pos) => {
+                let max_offset = (self.content_height - self.panel_rect.height()).max(0.0);
+                self.scroll_offset =
+                    (self.scroll_offset + event.wheel_delta.y).clamp(0.0, max_offset);
+                ctx.request_paint();
+                ctx.set_handled();
+            }
+            Event::KeyDown(event) => {
+                if let Some((index, name)) = self.renaming.as_mut() {
+                    let index = *index;
+                    match &event.key {
+                        KbKey::Character(c) => {
+                            name.push_str(c);
+                        }
+                        KbKey::Backspace => {
+                            name.pop();
+                        }
+                        KbKey::Enter => {
+                            self.stop_renaming(true);
+                            ctx.request_layout();
+                        }
+                        KbKey::Escape => {
+                            self.stop_renaming(false);
+                        }
+                        _ => {}
+                    }
+                    if let Some((_, name)) = self.renaming.as_ref() {
+                        self.labels[index].set_text(format!("{}|", name));
+                    }
+                    ctx.request_paint();
+                    // Typing a name shouldn't trigger the canvas shortcuts
+                    ctx.set_handled();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn lifecycle(
+        &mut self,
+        _ctx: &mut druid::LifeCycleCtx,
+        _event: &druid::LifeCycle,
+        _data: &ApplicationState,
+        _env: &druid::Env,
+    ) {
+    }
+
+    fn update(
+        &mut self,
+        ctx: &mut druid::UpdateCtx,
+        old_data: &ApplicationState,
+        data: &ApplicationState,
+        _env: &druid::Env,
+    ) {
+        if is_visible(ctx.window_id(), old_data) != is_visible(ctx.window_id(), data) {
+            self.stop_renaming(false);
+            ctx.request_layout();
+            ctx.request_paint();
+        }
+    }
+
+    fn layout(
+        &mut self,
+        ctx: &mut druid::LayoutCtx,
+        bc: &druid::BoxConstraints,
+        _data: &ApplicationState,
+        env: &druid::Env,
+    ) -> Size {
+        let window_size = ctx.window().get_size();
+        self.panel_rect = Rect::new(
+            window_size.width - PANEL_WIDTH - PANEL_MARGIN,
+            PANEL_MARGIN,
+            window_size.width - PANEL_MARGIN,
+            (window_size.height - PANEL_BOTTOM).max(PANEL_MARGIN),
+        );
+
+        self.delete_label.rebuild_if_needed(ctx.text(), env);
+        self.item_rects = vec![];
+        let mut y = self.panel_rect.y0;
+        for (label, preview) in self.labels.iter_mut().zip(self.previews.iter_mut()) {
+            label.rebuild_if_needed(ctx.text(), env);
+            preview.rebuild_if_needed(ctx.text(), env);
+            let height = ITEM_PADDING * 3.0 + label.size().height + preview.size().height;
+            self.item_rects.push(Rect::new(
+                self.panel_rect.x0,
+                y,
+                self.panel_rect.x1,
+                y + height,
+            ));
+            y += height;
+        }
+        self.content_height = y - self.panel_rect.y0;
+        let max_offset = (self.content_height - self.panel_rect.height()).max(0.0);
+        self.scroll_offset = self.scroll_offset.min(max_offset);
+
+        if self.scroll_to_renaming {
+            self.scroll_to_renaming = false;
+            if let Some((index, _)) = self.renaming {
+                let item = self.item_rects[index];
+                if item.y1 - self.scroll_offset > self.panel_rect.y1 {
+                    self.scroll_offset = (item.y1 - self.panel_rect.y1).min(max_offset);
+                }
+            }
+        }
+
+        bc.max()
+    }
+
+    fn paint(&mut self, ctx: &mut druid::PaintCtx, data: &ApplicationState, env: &druid::Env) {
+        if !is_visible(ctx.window_id(), data) {
+            return;
+        }
+        let brush = ctx.solid_brush(Color::from_hex_str("#333333").unwrap());
+        let stroke_brush = ctx.solid_brush(Color::from_hex_str("#4c4c4c").unwrap());
+        let shadow_brush = ctx.solid_brush(Color::rgba(0.0, 0.0, 0.0, 0.55));
+        let hover_brush = ctx.solid_brush(Color::from_hex_str("#404040").unwrap());
+        let rename_brush = ctx.solid_brush(Color::from_hex_str("#549bf5").unwrap());
+        let rect = self.panel_rect;
+        ctx.blurred_rect(rect, 5.0, &shadow_brush);
+        ctx.fill(rect.to_rounded_rect(5.0), &brush);
+        ctx.stroke(rect.to_rounded_rect(5.0), &stroke_brush, 1.0);
+
+        let renaming = self.renaming.as_ref().map(|(index, _)| *index);
+        ctx.with_save(|ctx| {
+            ctx.clip(rect);
+            for (i, item) in self.item_rects.iter().enumerate() {
+                let item = item.with_origin(Point::new(item.x0, item.y0 - self.scroll_offset));
+                if item.y1 < rect.y0 || item.y0 > rect.y1 {
+                    continue;
+                }
+                if self.hovered == Some(i) {
+                    ctx.fill(item, &hover_brush);
+                }
+                let label = &mut self.labels[i];
+                label.rebuild_if_needed(ctx.text(), env);
+                let label_origin = Point::new(item.x0 + ITEM_PADDING, item.y0 + ITEM_PADDING);
+                if renaming == Some(i) {
+                    let label_rect =
+                        Rect::from_origin_size(label_origin, label.size()).inflate(3.0, 1.0);
+                    ctx.stroke(label_rect, &rename_brush, 1.0);
+                }
+                label.draw(ctx, label_origin);
+                self.previews[i].draw(
+                    ctx,
+                    (
+                        item.x0 + ITEM_PADDING,
+                        item.y0 + ITEM_PADDING * 2.0 + label.size().height,
+                    ),
+                );
+                if self.hovered == Some(i) && self.stencils[i].path.is_some() {
+                    self.delete_label.draw(
+                        ctx,
+                        (
+                            item.x1 - ITEM_PADDING - DELETE_BUTTON_SIZE,
+                            item.y0 + ITEM_PADDING,
+                        ),
+                    );
+                }
+                ctx.stroke(
+                    druid::kurbo::Line::new((item.x0, item.y1), (item.x1, item.y1)),
+                    &stroke_brush,
+                    1.0,
+                );
+            }
+        });
+    }
+}
@@ -3,7 +3,11 @@ use once_cell::sync::Lazy;
 
 pub mod grid;
 pub mod image_button;
+pub mod layers;
 pub mod layout;
+pub mod library;
+pub mod palette;
+pub mod status;
 pub mod toolbar;
 
 pub struct ColorScheme {
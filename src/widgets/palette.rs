@@ -0,0 +1,400 @@
+use druid::{
+    Color, Command, Event, FontDescriptor, FontFamily, FontWeight, KbKey, Point, Rect,
+    RenderContext, Size, TextLayout, Widget,
+};
+
+use crate::{
+    consts::{ACTION_COMMAND, COPY_AS_COMMAND, EXPORT_AS_COMMAND},
+    data::{
+        export::ExportFormat,
+        keymap::{Action, KEYMAP},
+        ApplicationState,
+    },
+    tools::DrawingTools,
+};
+
+const PALETTE_WIDTH: f64 = 420.0;
+const PALETTE_TOP: f64 = 60.0;
+const ITEM_HEIGHT: f64 = 28.0;
+const ITEM_PADDING: f64 = 10.0;
+const MAX_ITEMS: usize = 10;
+
+struct Entry {
+    name: String,
+    command: Command,
+    label: TextLayout<String>,
+    shortcut: TextLayout<String>,
+}
+
+pub struct CommandPalette {
+    entries: Vec<Entry>,
+    is_open: bool,
+    query: String,
+    query_label: TextLayout<String>,
+    // Indices of the entries matching the query, best first
+    matches: Vec<usize>,
+    selected: usize,
+    first_visible: usize,
+    panel_rect: Rect,
+}
+
+impl CommandPalette {
+    pub fn new() -> Self {
+        let label_font = FontDescriptor::new(FontFamily::SYSTEM_UI)
+            .with_weight(FontWeight::REGULAR)
+            .with_size(13.0);
+        let mut commands: Vec<(String, String, Command)> = Action::ALL
+            .iter()
+            .filter(|action| !matches!(action, Action::Cancel | Action::CommandPalette))
+            .map(|action| {
+                let shortcut = KEYMAP
+                    .keys(*action)
+                    .first()
+                    .map_or(String::new(), |key| key.to_string());
+                (
+                    action.label().to_string(),
+                    shortcut,
+                    ACTION_COMMAND.with(*action),
+                )
+            })
+            .collect();
+        for format in ExportFormat::all() {
+            commands.push((
+                format!("Export as {}…", format),
+                String::new(),
+                EXPORT_AS_COMMAND.with(format.clone()),
+            ));
+            if matches!(
+                format,
+                ExportFormat::LineComment(_) | ExportFormat::BlockComment
+            ) {
+                commands.push((
+                    format!("Copy as {}", format),
+                    String::new(),
+                    COPY_AS_COMMAND.with(format),
+                ));
+            }
+        }
+
+        let entries = commands
+            .into_iter()
+            .map(|(name, shortcut_text, command)| {
+                let mut label = TextLayout::<String>::new();
+                label.set_font(label_font.clone());
+                label.set_text_color(Color::from_hex_str("#e0e0e0").unwrap());
+                label.set_text(name.to_owned());
+                let mut shortcut = TextLayout::<String>::new();
+                shortcut.set_font(label_font.clone().with_size(11.0));
+                shortcut.set_text_color(Color::from_hex_str("#a0a0a0").unwrap());
+                shortcut.set_text(shortcut_text);
+                Entry {
+                    name,
+                    command,
+                    label,
+                    shortcut,
+                }
+            })
+            .collect();
+        let mut query_label = TextLayout::<String>::new();
+        query_label.set_font(label_font);
+        let mut palette = CommandPalette {
+            entries,
+            is_open: false,
+            query: String::new(),
+            query_label,
+            matches: vec![],
+            selected: 0,
+            first_visible: 0,
+            panel_rect: Rect::ZERO,
+        };
+        palette.filter();
+        palette
+    }
+
+    fn open(&mut self) {
+        self.is_open = true;
+        self.query = String::new();
+        self.filter();
+    }
+
+    fn filter(&mut self) {
+        let mut scored: Vec<(i64, usize)> = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter_map(|(i, entry)| fuzzy_score(&self.query, &entry.name).map(|score| (score, i)))
+            .collect();
+        // Keep the original order for an empty query, shorter names win the ties
+        if !self.query.is_empty() {
+            scored.sort_by_key(|(score, i)| (-score, self.entries[*i].name.len()));
+        }
+        self.matches = scored.into_iter().map(|(_, i)| i).collect();
+        self.selected = 0;
+        self.first_visible = 0;
+
+        if self.query.is_empty() {
+            self.query_label
+                .set_text_color(Color::from_hex_str("#808080").unwrap());
+            self.query_label.set_text("Type a command".to_string());
+        } else {
+            self.query_label
+                .set_text_color(Color::from_hex_str("#e0e0e0").unwrap());
+            self.query_label.set_text(format!("{}|", self.query));
+        }
+    }
+
+    fn select(&mut self, index: usize) {
+        self.selected = index;
+        if self.selected < self.first_visible {
+            self.first_visible = self.selected;
+        } else if self.selected >= self.first_visible + MAX_ITEMS {
+            self.first_visible = self.selected + 1 - MAX_ITEMS;
+        }
+    }
+
+    fn run(&mut self, ctx: &mut druid::EventCtx, index: usize) {
+        if let Some(entry) = self.matches.get(index).map(|i| &self.entries[*i]) {
+            ctx.submit_command(entry.command.clone());
+        }
+        self.is_open = false;
+    }
+
+    fn query_rect(&self) -> Rect {
+        Rect::new(
+            self.panel_rect.x0,
+            self.panel_rect.y0,
+            self.panel_rect.x1,
+            self.panel_rect.y0 + ITEM_HEIGHT + ITEM_PADDING,
+        )
+    }
+
+    fn item_at(&self, pos: Point) -> Option<usize> {
+        let top = self.query_rect().y1;
+        if !self.panel_rect.contains(pos) || pos.y < top {
+            return None;
+        }
+        let index = self.first_visible + ((pos.y - top) / ITEM_HEIGHT) as usize;
+        (index < self.matches.len()).then_some(index)
+    }
+}
+
+// Matches the query characters in order, anywhere in the name. Runs of
+// consecutive characters and the starts of words score higher, gaps lower.
+fn fuzzy_score(query: &str, name: &str) -> Option<i64> {
+    let name: Vec<char> = name.chars().flat_map(|c| c.to_lowercase()).collect();
+    let mut score = 0;
+    let mut position = 0;
+    let mut last_match: Option<usize> = None;
+    for c in query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(|c| c.to_lowercase())
+    {
+        let found = position + name[position..].iter().position(|other| *other == c)?;
+        if found == 0 || !name[found - 1].is_alphanumeric() {
+            score += 10;
+        }
+        if last_match.is_some_and(|last| last + 1 == found) {
+            score += 5;
+        }
+        score -= (found - position) as i64;
+        last_match = Some(found);
+        position = found + 1;
+    }
+    Some(score)
+}
+
+impl Widget<ApplicationState> for CommandPalette {
+    fn event(
+        &mut self,
+        ctx: &mut druid::EventCtx,
+        event: &druid::Event,
+        data: &mut ApplicationState,
+        _env: &druid::Env,
+    ) {
+        if !self.is_open {
+            let is_requested = match event {
+                Event::KeyDown(event) if !ctx.is_handled() => {
+                    // Keys without Ctrl are typed into the text
+                    let is_typing = data
+                        .windows
+                        .get(&ctx.window_id())
+                        .is_some_and(|win_data| win_data.mode == DrawingTools::Text)
+                        && !(event.mods.meta() || event.mods.ctrl());
+                    !is_typing && KEYMAP.action(event) == Some(Action::CommandPalette)
+                }
+                Event::Command(cmd) => cmd.get(ACTION_COMMAND) == Some(&Action::CommandPalette),
+                _ => false,
+            };
+            if is_requested {
+                self.open();
+                ctx.request_layout();
+                ctx.request_paint();
+                ctx.set_handled();
+            }
+            return;
+        }
+
+        // Everything goes to the palette while it's open
+        match event {
+            Event::KeyDown(event) => {
+                match &event.key {
+                    _ if KEYMAP.action(event) == Some(Action::CommandPalette) => {
+                        self.is_open = false;
+                    }
+                    KbKey::Character(c) if !(event.mods.meta() || event.mods.ctrl()) => {
+                        self.query.push_str(c);
+                        self.filter();
+                    }
+                    KbKey::Backspace => {
+                        self.query.pop();
+                        self.filter();
+                    }
+                    KbKey::ArrowUp if self.selected > 0 => {
+                        self.select(self.selected - 1);
+                    }
+                    KbKey::ArrowDown if self.selected + 1 < self.matches.len() => {
+                        self.select(self.selected + 1);
+                    }
+                    KbKey::Enter => {
+                        self.run(ctx, self.selected);
+                    }
+                    KbKey::Escape => {
+                        self.is_open = false;
+                    }
+                    _ => {}
+                }
+                ctx.request_layout();
+                ctx.request_paint();
+                ctx.set_handled();
+            }
+            Event::MouseDown(event) => {
+                if let Some(index) = self.item_at(event.pos) {
+                    self.run(ctx, index);
+                } else if !self.panel_rect.contains(event.pos) {
+                    self.is_open = false;
+                }
+                ctx.request_paint();
+                ctx.set_handled();
+            }
+            Event::MouseMove(event) => {
+                if let Some(index) = self.item_at(event.pos) {
+                    if index != self.selected {
+                        self.selected = index;
+                        ctx.request_paint();
+                    }
+                }
+                ctx.set_handled();
+            }
+            Event::MouseUp(_) => {
+                ctx.set_handled();
+            }
+            Event::Wheel(event) => {
+                let max_first = self.matches.len().saturating_sub(MAX_ITEMS);
+                if event.wheel_delta.y > 0.0 && self.first_visible < max_first {
+                    self.first_visible += 1;
+                } else if event.wheel_delta.y < 0.0 && self.first_visible > 0 {
+                    self.first_visible -= 1;
+                }
+                ctx.request_paint();
+                ctx.set_handled();
+            }
+            _ => {}
+        }
+    }
+
+    fn lifecycle(
+        &mut self,
+        _ctx: &mut druid::LifeCycleCtx,
+        _event: &druid::LifeCycle,
+        _data: &ApplicationState,
+        _env: &druid::Env,
+    ) {
+    }
+
+    fn update(
+        &mut self,
+        _ctx: &mut druid::UpdateCtx,
+        _old_data: &ApplicationState,
+        _data: &ApplicationState,
+        _env: &druid::Env,
+    ) {
+    }
+
+    fn layout(
+        &mut self,
+        ctx: &mut druid::LayoutCtx,
+        bc: &druid::BoxConstraints,
+        _data: &ApplicationState,
+        _env: &druid::Env,
+    ) -> Size {
+        let window_size = ctx.window().get_size();
+        let width = PALETTE_WIDTH.min(window_size.width - ITEM_PADDING * 2.0);
+        let x = (window_size.width - width) / 2.0;
+        let height =
+            ITEM_HEIGHT + ITEM_PADDING + self.matches.len().min(MAX_ITEMS) as f64 * ITEM_HEIGHT;
+        self.panel_rect = Rect::new(x, PALETTE_TOP, x + width, PALETTE_TOP + height);
+        bc.max()
+    }
+
+    fn paint(&mut self, ctx: &mut druid::PaintCtx, _data: &ApplicationState, env: &druid::Env) {
+        if !self.is_open {
+            return;
+        }
+        let brush = ctx.solid_brush(Color::from_hex_str("#333333").unwrap());
+        let stroke_brush = ctx.solid_brush(Color::from_hex_str("#4c4c4c").unwrap());
+        let shadow_brush = ctx.solid_brush(Color::rgba(0.0, 0.0, 0.0, 0.55));
+        let selected_brush = ctx.solid_brush(Color::from_hex_str("#404040").unwrap());
+        let query_brush = ctx.solid_brush(Color::from_hex_str("#2a2a2a").unwrap());
+        let rect = self.panel_rect;
+        ctx.blurred_rect(rect, 5.0, &shadow_brush);
+        ctx.fill(rect.to_rounded_rect(5.0), &brush);
+        ctx.stroke(rect.to_rounded_rect(5.0), &stroke_brush, 1.0);
+
+        let query_rect = self.query_rect().inset(-ITEM_PADDING / 2.0);
+        ctx.fill(query_rect.to_rounded_rect(3.0), &query_brush);
+        self.query_label.rebuild_if_needed(ctx.text(), env);
+        let label_height = self.query_label.size().height;
+        self.query_label.draw(
+            ctx,
+            (
+                query_rect.x0 + ITEM_PADDING / 2.0,
+                query_rect.y0 + (query_rect.height() - label_height) / 2.0,
+            ),
+        );
+
+        let mut y = self.query_rect().y1;
+        for (index, i) in self
+            .matches
+            .iter()
+            .enumerate()
+            .skip(self.first_visible)
+            .take(MAX_ITEMS)
+        {
+            let item = Rect::new(rect.x0, y, rect.x1, y + ITEM_HEIGHT);
+            if index == self.selected {
+                ctx.fill(item, &selected_brush);
+            }
+            let entry = &mut self.entries[*i];
+            entry.label.rebuild_if_needed(ctx.text(), env);
+            entry.shortcut.rebuild_if_needed(ctx.text(), env);
+            let label_size = entry.label.size();
+            let shortcut_size = entry.shortcut.size();
+            entry.label.draw(
+                ctx,
+                (
+                    item.x0 + ITEM_PADDING,
+                    item.y0 + (ITEM_HEIGHT - label_size.height) / 2.0,
+                ),
+            );
+            entry.shortcut.draw(
+                ctx,
+                (
+                    item.x1 - ITEM_PADDING - shortcut_size.width,
+                    item.y0 + (ITEM_HEIGHT - shortcut_size.height) / 2.0,
+                ),
+            );
+            y += ITEM_HEIGHT;
+        }
+    }
+}
@@ -0,0 +1,139 @@
+use std::time::Duration;
+
+use druid::{
+    Color, Event, FontDescriptor, FontFamily, FontWeight, Rect, RenderContext, Size, TextLayout,
+    TimerToken, Widget,
+};
+
+use crate::data::ApplicationState;
+
+const MESSAGE_PADDING: f64 = 10.0;
+// Above the toolbar
+const MESSAGE_BOTTOM: f64 = 64.0;
+const MESSAGE_WIDTH: f64 = 480.0;
+const MESSAGE_TIMEOUT: Duration = Duration::from_secs(8);
+
+// Shows the message of the window, like a file that couldn't be saved, until it's
+// clicked away or it times out
+pub struct StatusMessage {
+    message: Option<String>,
+    label: TextLayout<String>,
+    rect: Rect,
+    timer: TimerToken,
+}
+
+impl StatusMessage {
+    pub fn new() -> Self {
+        let mut label = TextLayout::<String>::new();
+        label.set_font(
+            FontDescriptor::new(FontFamily::SYSTEM_UI)
+                .with_weight(FontWeight::REGULAR)
+                .with_size(13.0),
+        );
+        label.set_text_color(Color::from_hex_str("#e0e0e0").unwrap());
+        StatusMessage {
+            message: None,
+            label,
+            rect: Rect::ZERO,
+            timer: TimerToken::INVALID,
+        }
+    }
+}
+
+impl Widget<ApplicationState> for StatusMessage {
+    fn event(
+        &mut self,
+        ctx: &mut druid::EventCtx,
+        event: &druid::Event,
+        data: &mut ApplicationState,
+        _env: &druid::Env,
+    ) {
+        let win_data = match data.windows.get_mut(&ctx.window_id()) {
+            Some(win_data) => win_data,
+            None => return,
+        };
+        if win_data.message.is_none() {
+            return;
+        }
+        match event {
+            Event::MouseDown(event) if self.rect.contains(event.pos) => {
+                win_data.message = None;
+                ctx.set_handled();
+            }
+            Event::MouseUp(event) if self.rect.contains(event.pos) => {
+                ctx.set_handled();
+            }
+            Event::Timer(token) if *token == self.timer => {
+                win_data.message = None;
+            }
+            _ => {}
+        }
+    }
+
+    fn lifecycle(
+        &mut self,
+        _ctx: &mut druid::LifeCycleCtx,
+        _event: &druid::LifeCycle,
+        _data: &ApplicationState,
+        _env: &druid::Env,
+    ) {
+    }
+
+    fn update(
+        &mut self,
+        ctx: &mut druid::UpdateCtx,
+        _old_data: &ApplicationState,
+        data: &ApplicationState,
+        _env: &druid::Env,
+    ) {
+        let message = data
+            .windows
+            .get(&ctx.window_id())
+            .and_then(|win_data| win_data.message.clone());
+        if message != self.message {
+            if let Some(text) = message.as_ref() {
+                self.label.set_text(text.to_owned());
+                self.timer = ctx.request_timer(MESSAGE_TIMEOUT);
+            }
+            self.message = message;
+            ctx.request_layout();
+            ctx.request_paint();
+        }
+    }
+
+    fn layout(
+        &mut self,
+        ctx: &mut druid::LayoutCtx,
+        bc: &druid::BoxConstraints,
+        _data: &ApplicationState,
+        env: &druid::Env,
+    ) -> Size {
+        let size = bc.max();
+        let max_width = MESSAGE_WIDTH.min(size.width - MESSAGE_PADDING * 4.0);
+        self.label.set_wrap_width(max_width);
+        self.label.rebuild_if_needed(ctx.text(), env);
+        let label_size = self.label.size();
+        let width = label_size.width + MESSAGE_PADDING * 2.0;
+        let height = label_size.height + MESSAGE_PADDING * 2.0;
+        let x = (size.width - width) / 2.0;
+        let y = size.height - MESSAGE_BOTTOM - height;
+        self.rect = Rect::new(x, y, x + width, y + height);
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut druid::PaintCtx, _data: &ApplicationState, env: &druid::Env) {
+        if self.message.is_none() {
+            return;
+        }
+        let brush = ctx.solid_brush(Color::from_hex_str("#333333").unwrap());
+        let stroke_brush = ctx.solid_brush(Color::from_hex_str("#4c4c4c").unwrap());
+        let shadow_brush = ctx.solid_brush(Color::rgba(0.0, 0.0, 0.0, 0.55));
+        let rect = self.rect;
+        ctx.blurred_rect(rect, 5.0, &shadow_brush);
+        ctx.fill(rect.to_rounded_rect(5.0), &brush);
+        ctx.stroke(rect.to_rounded_rect(5.0), &stroke_brush, 1.0);
+        self.label.rebuild_if_needed(ctx.text(), env);
+        self.label
+            .draw(ctx, (rect.x0 + MESSAGE_PADDING, rect.y0 + MESSAGE_PADDING));
+    }
+}
@@ -1,14 +1,24 @@
 use std::path::PathBuf;
 
 use super::image_button::ImageButton;
-use crate::data::WindowData;
-use crate::{consts::BUTTON_HIGHLIGHT_COMMAND, data::ApplicationState, tools::DrawingTools};
+use crate::data::{
+    keymap::{Action, KEYMAP},
+    WindowData,
+};
+use crate::{
+    consts::{ACTION_COMMAND, BUTTON_HIGHLIGHT_COMMAND},
+    data::ApplicationState,
+    tools::DrawingTools,
+};
 use druid::{
     widget::{CrossAxisAlignment, Flex, MainAxisAlignment},
     Color, Event, FileDialogOptions, FileInfo, FileSpec, ImageBuf, Point, Rect, RenderContext,
     Size, Widget, WidgetPod,
 };
 
+// Space between the right row and the window edge
+const RIGHT_PADDING: f64 = 30.0;
+
 pub struct ToolBarWidget {
     left_buttons: WidgetPod<ApplicationState, Flex<ApplicationState>>,
     right_buttons: WidgetPod<ApplicationState, Flex<ApplicationState>>,
@@ -21,10 +31,19 @@ impl ToolBarWidget {
         let line_icon = ImageBuf::from_data(include_bytes!("../../assets/line-icon.png")).unwrap();
         let text_icon = ImageBuf::from_data(include_bytes!("../../assets/text-icon.png")).unwrap();
         let rect_icon = ImageBuf::from_data(include_bytes!("../../assets/rect-icon.png")).unwrap();
+        let freehand_icon =
+            ImageBuf::from_data(include_bytes!("../../assets/freehand-icon.png")).unwrap();
+        let table_icon =
+            ImageBuf::from_data(include_bytes!("../../assets/table-icon.png")).unwrap();
         let eraser_icon =
             ImageBuf::from_data(include_bytes!("../../assets/eraser-icon.png")).unwrap();
+        let fill_icon = ImageBuf::from_data(include_bytes!("../../assets/fill-icon.png")).unwrap();
         let save_icon = ImageBuf::from_data(include_bytes!("../../assets/save-icon.png")).unwrap();
         let open_icon = ImageBuf::from_data(include_bytes!("../../assets/open-icon.png")).unwrap();
+        let library_icon =
+            ImageBuf::from_data(include_bytes!("../../assets/library-icon.png")).unwrap();
+        let layers_icon =
+            ImageBuf::from_data(include_bytes!("../../assets/layers-icon.png")).unwrap();
 
         let left_buttons = WidgetPod::new(
             Flex::row()
@@ -64,6 +83,24 @@ impl ToolBarWidget {
                     }),
                 )
                 .with_spacer(4.0)
+                .with_child(
+                    ImageButton::new(
+                        freehand_icon,
+                        Size::new(26.0, 26.0),
+                        DrawingTools::Freehand.to_string(),
+                    )
+                    .on_click(|ctx, data: &mut ApplicationState, _env| {
+                        let win_data = data
+                            .windows
+                            .get_mut(&ctx.window_id())
+                            .expect("Invalid WindowID");
+                        let tool = DrawingTools::Freehand;
+                        win_data.mode = tool;
+                        ctx.submit_notification(BUTTON_HIGHLIGHT_COMMAND.with(tool.to_string()));
+                        ctx.set_handled();
+                    }),
+                )
+                .with_spacer(4.0)
                 .with_child(
                     ImageButton::new(
                         rect_icon,
@@ -82,6 +119,24 @@ impl ToolBarWidget {
                     }),
                 )
                 .with_spacer(4.0)
+                .with_child(
+                    ImageButton::new(
+                        table_icon,
+                        Size::new(26.0, 26.0),
+                        DrawingTools::Table.to_string(),
+                    )
+                    .on_click(|ctx, data: &mut ApplicationState, _env| {
+                        let win_data = data
+                            .windows
+                            .get_mut(&ctx.window_id())
+                            .expect("Invalid WindowID");
+                        let tool = DrawingTools::Table;
+                        win_data.mode = tool;
+                        ctx.submit_notification(BUTTON_HIGHLIGHT_COMMAND.with(tool.to_string()));
+                        ctx.set_handled();
+                    }),
+                )
+                .with_spacer(4.0)
                 .with_child(
                     ImageButton::new(
                         text_icon,
@@ -117,12 +172,55 @@ impl ToolBarWidget {
                         ctx.set_handled();
                     }),
                 )
+                .with_spacer(4.0)
+                .with_child(
+                    ImageButton::new(
+                        fill_icon,
+                        Size::new(26.0, 26.0),
+                        DrawingTools::Fill.to_string(),
+                    )
+                    .on_click(|ctx, data: &mut ApplicationState, _env| {
+                        let win_data = data
+                            .windows
+                            .get_mut(&ctx.window_id())
+                            .expect("Invalid WindowID");
+                        let tool = DrawingTools::Fill;
+                        win_data.mode = tool;
+                        ctx.submit_notification(BUTTON_HIGHLIGHT_COMMAND.with(tool.to_string()));
+                        ctx.set_handled();
+                    }),
+                )
                 .cross_axis_alignment(CrossAxisAlignment::End)
                 .main_axis_alignment(MainAxisAlignment::Start),
         );
 
+        let layers_button = ImageButton::new(layers_icon, Size::new(26.0, 26.0), String::new())
+            .on_click(move |ctx, data: &mut ApplicationState, _env| {
+                let win_data = data
+                    .windows
+                    .get_mut(&ctx.window_id())
+                    .expect("Invalid WindowID");
+                win_data.show_layers = !win_data.show_layers;
+                ctx.set_handled();
+            });
+
         let right_buttons = WidgetPod::new(
             Flex::row()
+                .with_child(layers_button)
+                .with_spacer(4.0)
+                .with_child(
+                    ImageButton::new(library_icon, Size::new(26.0, 26.0), String::new()).on_click(
+                        move |ctx, data: &mut ApplicationState, _env| {
+                            let win_data = data
+                                .windows
+                                .get_mut(&ctx.window_id())
+                                .expect("Invalid WindowID");
+                            win_data.show_library = !win_data.show_library;
+                            ctx.set_handled();
+                        },
+                    ),
+                )
+                .with_spacer(4.0)
                 .with_child(
                     ImageButton::new(open_icon, Size::new(26.0, 26.0), String::new()).on_click(
                         move |ctx, _: &mut ApplicationState, _env| {
@@ -168,6 +266,17 @@ fn open_from_file(ctx: &mut druid::EventCtx) {
 }
 
 fn save_to_file(data: &mut WindowData, ctx: &mut druid::EventCtx) {
+    if let Some(current_file) = &data.current_file {
+        ctx.submit_command(druid::commands::SAVE_FILE_AS.with(FileInfo {
+            path: PathBuf::from(current_file),
+            format: None,
+        }));
+    } else {
+        save_as_file(ctx);
+    }
+}
+
+fn save_as_file(ctx: &mut druid::EventCtx) {
     let save_dialog_options = FileDialogOptions::new()
         .allowed_types(vec![FileSpec::TEXT])
         .default_type(FileSpec::TEXT)
@@ -176,14 +285,7 @@ fn save_to_file(data: &mut WindowData, ctx: &mut druid::EventCtx) {
         .title("Save diagram")
         .button_text("Save");
 
-    if let Some(current_file) = &data.current_file {
-        ctx.submit_command(druid::commands::SAVE_FILE_AS.with(FileInfo {
-            path: PathBuf::from(current_file),
-            format: None,
-        }));
-    } else {
-        ctx.submit_command(druid::commands::SHOW_SAVE_PANEL.with(save_dialog_options));
-    }
+    ctx.submit_command(druid::commands::SHOW_SAVE_PANEL.with(save_dialog_options));
 }
 
 impl Widget<ApplicationState> for ToolBarWidget {
@@ -211,17 +313,34 @@ impl Widget<ApplicationState> for ToolBarWidget {
                     ctx.submit_command(BUTTON_HIGHLIGHT_COMMAND.with(name.to_string()));
                 }
             }
-            Event::KeyDown(event) => {
-                if win_data.mode != DrawingTools::Text && event.mods.meta() || event.mods.ctrl() {
-                    match event.code {
-                        druid::Code::KeyS => {
-                            save_to_file(win_data, ctx);
-                        }
-                        druid::Code::KeyO => {
-                            open_from_file(ctx);
-                        }
-                        _ => {}
+            Event::Command(cmd) => match cmd.get(ACTION_COMMAND) {
+                Some(Action::Save) => {
+                    save_to_file(win_data, ctx);
+                }
+                Some(Action::SaveAs) => {
+                    save_as_file(ctx);
+                }
+                Some(Action::Open) => {
+                    open_from_file(ctx);
+                }
+                _ => {}
+            },
+            // Keys typed into the command palette are already handled
+            Event::KeyDown(event) if !ctx.is_handled() => {
+                // Keys without Ctrl are typed into the text
+                let is_typing = win_data.mode == DrawingTools::Text
+                    && !(event.mods.meta() || event.mods.ctrl());
+                match KEYMAP.action(event).filter(|_| !is_typing) {
+                    Some(Action::Save) => {
+                        save_to_file(win_data, ctx);
                     }
+                    Some(Action::SaveAs) => {
+                        save_as_file(ctx);
+                    }
+                    Some(Action::Open) => {
+                        open_from_file(ctx);
+                    }
+                    _ => {}
                 }
             }
 
@@ -275,14 +394,17 @@ impl Widget<ApplicationState> for ToolBarWidget {
         self.left_buttons
             .set_origin(ctx, Point::new(26.0, -26.0 + 4.0));
 
+        self.left_buttons.layout(ctx, bc, data, env);
+        // The right row grows with its buttons, place it from its own width
+        let right_size = self.right_buttons.layout(ctx, bc, data, env);
         self.right_buttons.set_origin(
             ctx,
-            Point::new(window_size.width - 26.0 * 3.0 - 8.0, -26.0 + 4.0),
+            Point::new(
+                window_size.width - right_size.width - RIGHT_PADDING,
+                -26.0 + 4.0,
+            ),
         );
 
-        self.left_buttons.layout(ctx, bc, data, env);
-        self.right_buttons.layout(ctx, bc, data, env);
-
         Size {
             width: window_size.width,
             height: 26.0,